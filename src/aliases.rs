@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::{text, Match};
+
+// Folds the match at `alias` into `canonical`: all of its triggers become aliases of
+// the canonical match, whose replacement wins. Returns the new index of `canonical`.
+pub fn merge_into(matches: &mut Vec<Match>, alias: usize, canonical: usize) -> Option<usize> {
+    if alias == canonical || alias >= matches.len() || canonical >= matches.len() {
+        return None;
+    }
+    let removed = matches.remove(alias);
+    let canonical = if alias < canonical { canonical - 1 } else { canonical };
+    let target = &mut matches[canonical];
+    for trigger in removed.all_triggers() {
+        if !target.all_triggers().contains(&trigger) {
+            target.aliases.push(trigger);
+        }
+    }
+    Some(canonical)
+}
+
+// Turns one alias back into a match of its own with the same replacement
+pub fn detach(matches: &mut Vec<Match>, index: usize, alias: &str) {
+    let Some(source) = matches.get_mut(index) else { return };
+    let Some(position) = source.aliases.iter().position(|a| a == alias) else { return };
+    let trigger = source.aliases.remove(position);
+    let detached = Match { trigger, aliases: Vec::new(), ..source.clone() };
+    matches.insert(index + 1, detached);
+}
+
+// Groups of plain matches whose replacements only differ in surrounding whitespace,
+// i.e. candidates for becoming aliases of each other
+pub fn variant_groups(matches: &[Match]) -> Vec<Vec<usize>> {
+    let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, m) in matches.iter().enumerate() {
+        if m.regex.is_none() {
+            groups.entry(m.replace.trim()).or_default().push(index);
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort();
+    groups
+}
+
+// Groups of triggers espanso treats as different although they only differ in case,
+// accents or a `:` prefix. The preferred spelling comes first: prefixed, lowercase, plain ASCII.
+pub fn spelling_groups<'a>(triggers: impl Iterator<Item = &'a String>) -> Vec<Vec<String>> {
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    for trigger in triggers {
+        groups.entry(text::spelling_key(trigger)).or_default().push(trigger.clone());
+    }
+    let mut groups: Vec<Vec<String>> = groups
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|mut group| {
+            group.sort_by_key(|t| (!t.starts_with(':'), t.chars().any(char::is_uppercase), !t.is_ascii(), t.clone()));
+            group
+        })
+        .collect();
+    groups.sort();
+    groups
+}
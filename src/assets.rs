@@ -0,0 +1,113 @@
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::tasks::TaskControl;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "svg", "webp", "ico"];
+
+// A match whose `image_path` points to a file that doesn't exist
+#[derive(Debug, Clone)]
+pub struct MissingImage {
+    pub file: String,
+    pub trigger: String,
+    pub image_path: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AssetReport {
+    // Images below the config directory that no match refers to
+    pub orphaned: Vec<PathBuf>,
+    pub missing: Vec<MissingImage>,
+}
+
+// Compares the images on disk with the `image_path` of every match below `config_root`,
+// the espanso directory holding `match/` and `config/`
+pub fn scan(config_root: &Path, control: &TaskControl) -> Result<AssetReport, String> {
+    let mut files = Vec::new();
+    collect_files(config_root, &mut files);
+    let match_files: Vec<&PathBuf> = files.iter().filter(|p| has_extension(p, &["yml", "yaml"])).collect();
+    control.set_total(match_files.len());
+
+    let mut referenced = BTreeSet::new();
+    let mut report = AssetReport::default();
+    for path in match_files {
+        if control.is_cancelled() {
+            return Err("Image scan cancelled".to_string());
+        }
+        control.advance();
+        let Ok(contents) = fs::read_to_string(path) else { continue };
+        let base = path.parent().unwrap_or(config_root);
+        for (trigger, image_path) in image_references(&contents) {
+            let resolved = resolve(&image_path, config_root, base);
+            if resolved.exists() {
+                referenced.insert(canonical(&resolved));
+            } else {
+                let file = path.strip_prefix(config_root).unwrap_or(path).display().to_string();
+                report.missing.push(MissingImage { file, trigger, image_path });
+            }
+        }
+    }
+    report.orphaned = files
+        .into_iter()
+        .filter(|p| has_extension(p, IMAGE_EXTENSIONS) && !referenced.contains(&canonical(p)))
+        .collect();
+    Ok(report)
+}
+
+// (trigger, image_path) of every image match in a match file
+fn image_references(contents: &str) -> Vec<(String, String)> {
+    let mut references = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(contents) {
+        let Ok(value) = Value::deserialize(document) else { continue };
+        let Some(matches) = value.get("matches").and_then(Value::as_sequence) else { continue };
+        for m in matches {
+            let Some(image_path) = m.get("image_path").and_then(Value::as_str) else { continue };
+            let trigger = m
+                .get("trigger")
+                .or_else(|| m.get("triggers").and_then(|t| t.get(0)))
+                .or_else(|| m.get("regex"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            references.push((trigger.to_string(), image_path.to_string()));
+        }
+    }
+    references
+}
+
+// espanso expands `$CONFIG` to its config directory; other relative paths are taken
+// relative to the match file
+fn resolve(image_path: &str, config_root: &Path, base: &Path) -> PathBuf {
+    if let Some(rest) = image_path.strip_prefix("$CONFIG") {
+        return config_root.join(rest.trim_start_matches(['/', '\\']));
+    }
+    let path = Path::new(image_path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::Match;
+
+// Renders matches as AutoHotkey v2 hotstrings, one per trigger and alias. Matches
+// that are more than plain text are left out; their number is returned alongside.
+pub fn render(matches: &[Match]) -> (String, usize) {
+    let plain: Vec<&Match> = matches.iter().filter(|m| m.plain_text_issue().is_none()).collect();
+    let skipped = matches.len() - plain.len();
+    let mut output = String::from("; Hotstrings exported by Espanso Helper\n#Requires AutoHotkey v2.0\n\n");
+    for m in plain {
+        if let Some(label) = &m.label {
+            output.push_str(&format!("; {}\n", label.replace('\n', " ")));
+        }
+        // T sends the text as is; espanso expands right away unless in word mode and
+        // is case sensitive unless it propagates case
+        let options = format!("{}{}T", if m.word { "" } else { "*" }, if m.propagate_case { "" } else { "C" });
+        for trigger in m.all_triggers() {
+            output.push_str(&format!(":{}:{}::{}\n", options, escape_trigger(&trigger), escape_replacement(&m.replace)));
+        }
+    }
+    (output, skipped)
+}
+
+fn escape_trigger(trigger: &str) -> String {
+    trigger.replace('`', "``").replace(':', "`:")
+}
+
+// Hotstrings are single lines where leading spaces and ` ;` comments are dropped
+fn escape_replacement(replace: &str) -> String {
+    let mut escaped = String::new();
+    for c in replace.chars() {
+        match c {
+            '`' => escaped.push_str("``"),
+            '\n' => escaped.push_str("`n"),
+            '\r' => escaped.push_str("`r"),
+            '\t' => escaped.push_str("`t"),
+            ';' if escaped.is_empty() || escaped.ends_with([' ', '\t']) => escaped.push_str("`;"),
+            ' ' if escaped.is_empty() => escaped.push_str("`s"),
+            c => escaped.push(c),
+        }
+    }
+    if escaped.ends_with(' ') {
+        escaped.pop();
+        escaped.push_str("`s");
+    }
+    escaped
+}
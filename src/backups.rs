@@ -0,0 +1,99 @@
+use chrono::Local;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_config_dir;
+
+// Sortable and valid in file names on every platform; milliseconds keep saves within
+// the same second apart
+const STAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S-%3f";
+
+// Backups taken before milliseconds were added
+const SECONDS_STAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+fn parse_stamp(taken: &str) -> Option<chrono::NaiveDateTime> {
+    [STAMP_FORMAT, SECONDS_STAMP_FORMAT].iter().find_map(|format| chrono::NaiveDateTime::parse_from_str(taken, format).ok())
+}
+
+// A copy of a match file taken before it was overwritten
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    // When it was taken, as written in the file name
+    pub taken: String,
+}
+
+fn backup_dir() -> PathBuf {
+    app_config_dir().join("backups")
+}
+
+// Files in subfolders of the match folder are kept apart from same-named ones elsewhere
+fn stem(file: &str) -> String {
+    file.replace(['/', '\\'], "%")
+}
+
+// Backups of `file`, newest first
+pub fn list(file: &str) -> Vec<Backup> {
+    let prefix = format!("{}.", stem(file));
+    let mut backups: Vec<Backup> = fs::read_dir(backup_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let taken = name.strip_prefix(&prefix)?;
+            // `base.yml.` is also the start of backups of `base.yml.yml`
+            parse_stamp(taken)?;
+            Some(Backup { taken: taken.to_string(), path })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.taken.cmp(&a.taken));
+    backups
+}
+
+// Copies `file` of the match folder into the backup folder before it gets overwritten
+// and drops all but the newest `keep` backups of it. Nothing is copied when the newest
+// backup already has the same contents.
+pub fn create(config_dir: &Path, file: &str, keep: usize) -> io::Result<()> {
+    let path = config_dir.join(file);
+    if keep == 0 || !path.exists() {
+        return Ok(());
+    }
+    let contents = fs::read(&path)?;
+    let existing = list(file);
+    if existing.first().is_some_and(|newest| fs::read(&newest.path).is_ok_and(|c| c == contents)) {
+        return Ok(());
+    }
+    let dir = backup_dir();
+    fs::create_dir_all(&dir)?;
+    // An existing backup is never overwritten, a taken name moves the stamp on instead
+    let mut taken = Local::now().naive_local();
+    let mut backup = loop {
+        let name = format!("{}.{}", stem(file), taken.format(STAMP_FORMAT));
+        match fs::OpenOptions::new().write(true).create_new(true).open(dir.join(name)) {
+            Ok(backup) => break backup,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => taken += chrono::Duration::milliseconds(1),
+            Err(error) => return Err(error),
+        }
+    };
+    backup.write_all(&contents)?;
+    for old in list(file).iter().skip(keep) {
+        fs::remove_file(&old.path)?;
+    }
+    Ok(())
+}
+
+// Backups of any file taken at or after `since`
+pub fn count_since(since: chrono::NaiveDateTime) -> usize {
+    fs::read_dir(backup_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let (_, taken) = name.rsplit_once('.')?;
+            parse_stamp(taken)
+        })
+        .filter(|taken| *taken >= since)
+        .count()
+}
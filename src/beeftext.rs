@@ -0,0 +1,60 @@
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::Match;
+
+// Beeftext's own backup files currently use format version 8
+const FILE_FORMAT_VERSION: u64 = 8;
+
+// Reads the combos of a Beeftext JSON backup as matches
+pub fn parse(contents: &str) -> Result<Vec<Match>, String> {
+    let data: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let combos = data.get("combos").and_then(Value::as_array).ok_or("not a Beeftext backup: no combos found")?;
+    Ok(combos.iter().filter_map(|combo| {
+        let trigger = combo.get("keyword")?.as_str()?.to_string();
+        let replace = combo.get("snippet")?.as_str()?.to_string();
+        Some(Match { trigger, replace, ..Default::default() })
+    }).collect())
+}
+
+// Renders matches as a Beeftext backup. Regex, form and variable matches have no
+// Beeftext counterpart and are left out; their number is returned alongside the JSON.
+pub fn render(matches: &[Match]) -> (String, usize) {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    let plain: Vec<&Match> = matches.iter().filter(|m| m.plain_text_issue().is_none()).collect();
+    let skipped = matches.len() - plain.len();
+    // Beeftext combos have a single keyword, so every alias becomes a combo of its own
+    let mut combos = Vec::new();
+    for m in plain {
+        for trigger in m.all_triggers() {
+            combos.push(json!({
+                "uuid": stable_uuid(&trigger),
+                "name": trigger,
+                "keyword": trigger,
+                "snippet": m.replace,
+                "enabled": true,
+                "created": now,
+                "lastModified": now,
+            }));
+        }
+    }
+    let data = json!({
+        "fileFormatVersion": FILE_FORMAT_VERSION,
+        "combos": combos,
+        "groups": [],
+    });
+    (serde_json::to_string_pretty(&data).unwrap_or_default(), skipped)
+}
+
+// Derives a UUID-shaped identifier from the trigger, so re-exports keep their ids
+fn stable_uuid(trigger: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    trigger.hash(&mut hasher);
+    let high = hasher.finish();
+    "beeftext".hash(&mut hasher);
+    let low = hasher.finish();
+    let hex = format!("{:016x}{:016x}", high, low);
+    format!("{{{}-{}-{}-{}-{}}}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+// How many copied texts the watcher keeps as candidates
+const MAX_CANDIDATES: usize = 20;
+
+pub fn read_text() -> Result<String, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    let text = clipboard.get_text().map_err(|e| e.to_string())?;
+    Ok(text.replace("\r\n", "\n"))
+}
+
+// Text copied while the watcher is on, newest first, as candidates for new matches.
+// Held in memory only, since the clipboard may carry passwords.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    // The clipboard when it was last read, so only changes are taken
+    last: Option<String>,
+    pub candidates: VecDeque<String>,
+}
+
+impl Watcher {
+    // Takes `text` as a candidate when the clipboard changed to it; true if it was added
+    pub fn observe(&mut self, text: String) -> bool {
+        if self.last.as_ref() == Some(&text) {
+            return false;
+        }
+        self.last = Some(text.clone());
+        if text.trim().is_empty() {
+            return false;
+        }
+        self.candidates.retain(|candidate| *candidate != text);
+        self.candidates.push_front(text);
+        self.candidates.truncate(MAX_CANDIDATES);
+        true
+    }
+
+    // Starts over, without taking what is on the clipboard right now
+    pub fn restart(&mut self, current: Option<String>) {
+        self.last = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changes_become_candidates() {
+        let mut watcher = Watcher::default();
+        watcher.restart(Some("already copied".to_string()));
+        assert!(!watcher.observe("already copied".to_string()));
+        assert!(watcher.observe("Dear Sir,\nthanks".to_string()));
+        assert!(!watcher.observe("Dear Sir,\nthanks".to_string()));
+        assert!(!watcher.observe("  \n".to_string()));
+        assert!(watcher.observe("second".to_string()));
+        // Copied again, it moves to the front instead of showing twice
+        assert!(watcher.observe("Dear Sir,\nthanks".to_string()));
+        assert_eq!(watcher.candidates, ["Dear Sir,\nthanks", "second"]);
+    }
+
+    #[test]
+    fn candidates_are_capped() {
+        let mut watcher = Watcher::default();
+        for n in 0..MAX_CANDIDATES + 5 {
+            watcher.observe(format!("copy {}", n));
+        }
+        assert_eq!(watcher.candidates.len(), MAX_CANDIDATES);
+        assert_eq!(watcher.candidates[0], format!("copy {}", MAX_CANDIDATES + 4));
+    }
+}
@@ -0,0 +1,227 @@
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+// Files above this size (emoji packs and other generated files) open in the compact view
+pub const LARGE_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: u32,
+    len: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    trigger: Span,
+    replace: Span,
+}
+
+// Read-only match list keeping all text in one buffer. Identical strings are stored
+// once, which matters for generated files where many replacements repeat.
+#[derive(Debug, Default)]
+pub struct CompactMatches {
+    text: String,
+    entries: Vec<Entry>,
+}
+
+impl CompactMatches {
+    // Reads the `matches` list of the first document that has one, without building
+    // an intermediate YAML tree
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut builder = Builder::default();
+        for document in serde_yaml::Deserializer::from_str(contents) {
+            let found = document.deserialize_map(FileVisitor { builder: &mut builder }).map_err(|e| e.to_string())?;
+            if found {
+                break;
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Triggers of a match as shown in the list, or its regex pattern
+    pub fn trigger(&self, index: usize) -> &str {
+        self.resolve(self.entries[index].trigger)
+    }
+
+    pub fn replace(&self, index: usize) -> &str {
+        self.resolve(self.entries[index].replace)
+    }
+
+    // Approximate heap usage, shown next to the match count
+    pub fn memory_bytes(&self) -> usize {
+        self.text.capacity() + self.entries.capacity() * std::mem::size_of::<Entry>()
+    }
+
+    fn resolve(&self, span: Span) -> &str {
+        &self.text[span.start as usize..(span.start + span.len) as usize]
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    matches: CompactMatches,
+    // Spans already stored, by hash of their text; dropped once parsing is done
+    interned: HashMap<u64, Vec<Span>>,
+}
+
+impl Builder {
+    fn intern(&mut self, value: &str) -> Span {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let candidates = self.interned.entry(hasher.finish()).or_default();
+        let text = &mut self.matches.text;
+        if let Some(span) = candidates
+            .iter()
+            .find(|span| &text[span.start as usize..(span.start + span.len) as usize] == value)
+        {
+            return *span;
+        }
+        let span = Span { start: text.len() as u32, len: value.len() as u32 };
+        text.push_str(value);
+        candidates.push(span);
+        span
+    }
+
+    fn push(&mut self, raw: RawMatch) {
+        let trigger = match (raw.regex, raw.triggers, raw.trigger) {
+            (Some(regex), _, _) => format!("regex: {}", regex),
+            (None, Some(triggers), _) if !triggers.is_empty() => triggers.join(", "),
+            (None, _, Some(trigger)) => trigger,
+            _ => return,
+        };
+        let Some(replace) = raw.replace else { return };
+        let entry = Entry { trigger: self.intern(&trigger), replace: self.intern(&replace) };
+        self.matches.entries.push(entry);
+    }
+
+    fn finish(mut self) -> CompactMatches {
+        self.matches.text.shrink_to_fit();
+        self.matches.entries.shrink_to_fit();
+        self.matches
+    }
+}
+
+// The fields of a match the compact view needs; everything else is skipped
+#[derive(Deserialize)]
+struct RawMatch {
+    trigger: Option<String>,
+    triggers: Option<Vec<String>>,
+    regex: Option<String>,
+    replace: Option<String>,
+}
+
+// Walks the top level of a document, returning whether it had a `matches` list
+struct FileVisitor<'a> {
+    builder: &'a mut Builder,
+}
+
+impl<'de> Visitor<'de> for FileVisitor<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a match file")
+    }
+
+    // Empty documents and comments-only files
+    fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        let mut found = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "matches" {
+                map.next_value_seed(MatchesSeed { builder: &mut *self.builder })?;
+                found = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(found)
+    }
+}
+
+// Pushes every entry of the `matches` list into the builder as soon as it is read
+struct MatchesSeed<'a> {
+    builder: &'a mut Builder,
+}
+
+impl<'de> de::DeserializeSeed<'de> for MatchesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for MatchesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of matches")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(raw) = seq.next_element::<RawMatch>()? {
+            self.builder.push(raw);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_every_kind_of_trigger() {
+        let contents = concat!(
+            "# generated\n",
+            "global_vars: []\n",
+            "matches:\n",
+            "  - trigger: \":a\"\n",
+            "    replace: \"Same\"\n",
+            "    word: true\n",
+            "  - triggers: [\":b\", \":c\"]\n",
+            "    replace: \"Same\"\n",
+            "  - regex: \":d(?P<n>\\\\d)\"\n",
+            "    replace: \"{{n}}\"\n",
+            "  - trigger: \":form\"\n",
+            "    form: \"[[name]]\"\n",
+        );
+        let matches = CompactMatches::parse(contents).unwrap();
+        // The form match has no replacement to show
+        assert_eq!(matches.len(), 3);
+        assert_eq!((matches.trigger(0), matches.replace(0)), (":a", "Same"));
+        assert_eq!(matches.trigger(1), ":b, :c");
+        assert_eq!((matches.trigger(2), matches.replace(2)), (r"regex: :d(?P<n>\d)", "{{n}}"));
+    }
+
+    #[test]
+    fn repeated_text_is_stored_once() {
+        let mut contents = String::from("matches:\n");
+        for n in 0..100 {
+            contents.push_str(&format!("  - trigger: \":e{}\"\n    replace: \"the same long replacement text\"\n", n));
+        }
+        let matches = CompactMatches::parse(&contents).unwrap();
+        assert_eq!(matches.len(), 100);
+        assert_eq!(matches.replace(99), "the same long replacement text");
+        assert!(matches.text.len() < 100 * 5 + 40, "{} bytes of text", matches.text.len());
+    }
+
+    #[test]
+    fn the_first_document_with_matches_counts() {
+        let matches = CompactMatches::parse("name: header\n---\nmatches:\n  - trigger: \":x\"\n    replace: \"X\"\n").unwrap();
+        assert_eq!(matches.trigger(0), ":x");
+        assert_eq!(CompactMatches::parse("").unwrap().len(), 0);
+        assert!(CompactMatches::parse("matches: [unclosed").is_err());
+    }
+}
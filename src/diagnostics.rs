@@ -0,0 +1,185 @@
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::logging;
+use crate::yaml_io::{self, MatchFile};
+
+// Setting names that may carry credentials and are masked in the bundle
+const SECRET_MARKERS: &[&str] = &["token", "password", "secret", "key"];
+
+// Masks the value of every `name: value` line whose name looks like a credential
+pub fn strip_secrets(settings: &str) -> String {
+    settings
+        .lines()
+        .map(|line| match line.split_once(':') {
+            Some((name, _)) if SECRET_MARKERS.iter().any(|m| name.to_lowercase().contains(m)) => {
+                format!("{}: <redacted>", name)
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Replaces `token` where it stands on its own, not as part of a longer word
+fn replace_token(text: &str, token: &str, with: &str) -> String {
+    let mut replaced = String::new();
+    let mut last = 0;
+    for (at, _) in text.match_indices(token) {
+        let end = at + token.len();
+        let before = text[..at].chars().next_back();
+        let after = text[end..].chars().next();
+        if at >= last && !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric) {
+            replaced.push_str(&text[last..at]);
+            replaced.push_str(with);
+            last = end;
+        }
+    }
+    replaced.push_str(&text[last..]);
+    replaced
+}
+
+// Takes what the logs say about the user's matches out: paths below the home folder,
+// match file names, numbered as in `match-files.txt`, and triggers. Single characters
+// are left, since replacing them would garble the log without hiding anything.
+pub fn anonymize(log: &str, config_dir: &Path, files: &[String]) -> String {
+    let mut log = log.replace(&config_dir.display().to_string(), "<config dir>");
+    if let Some(home) = dirs::home_dir().filter(|home| home.components().count() > 1) {
+        log = log.replace(&home.display().to_string(), "~");
+    }
+    let mut names: Vec<(usize, &String)> = files.iter().enumerate().collect();
+    names.sort_by_key(|(_, file)| std::cmp::Reverse(file.len()));
+    for (number, file) in names {
+        log = replace_token(&log, file, &format!("file{}", number + 1));
+    }
+    let mut triggers: Vec<String> = files
+        .iter()
+        .flat_map(|file| MatchFile::load(&config_dir.join(file)).matches())
+        .flat_map(|m| m.all_triggers())
+        .filter(|trigger| trigger.chars().count() > 1)
+        .collect();
+    triggers.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    triggers.dedup();
+    for trigger in triggers {
+        log = replace_token(&log, &trigger, "<trigger>");
+    }
+    log
+}
+
+fn espanso_version() -> String {
+    match Command::new("espanso").arg("--version").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        Err(error) => format!("espanso not available: {}", error),
+    }
+}
+
+// Sizes, counts and parse errors of every match file, with file names replaced by numbers
+fn match_file_structure(config_dir: &Path, files: &[String]) -> String {
+    let mut report = format!("{} match files\n", files.len());
+    for (number, file) in files.iter().enumerate() {
+        let path = config_dir.join(file);
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let document = MatchFile::parse(&contents);
+        let matches = document.matches();
+        let regex = matches.iter().filter(|m| m.regex.is_some()).count();
+        let aliases: usize = matches.iter().map(|m| m.aliases.len()).sum();
+        let longest = matches.iter().map(|m| m.replace.len()).max().unwrap_or_default();
+        report.push_str(&format!(
+            "file{}{}: {} bytes, {} documents, {} matches ({} regex, {} aliases), longest replacement {} bytes\n",
+            number + 1,
+            if file.starts_with('_') { " (underscore)" } else { "" },
+            contents.len(),
+            document.document_count(),
+            matches.len(),
+            regex,
+            aliases,
+            longest
+        ));
+        if let Some(error) = yaml_io::parse_error(&contents) {
+            report.push_str(&format!("  parse error: {}\n", error));
+        }
+    }
+    report
+}
+
+// Writes the bundle into the download folder and returns the path of the archive
+pub fn export_bundle(config_dir: &Path, files: &[String], session_log: &[String], settings: &str) -> Result<PathBuf, String> {
+    let name = format!("espanso-helper-diagnostics-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let target_dir = dirs::download_dir().or_else(dirs::home_dir).unwrap_or_default();
+    let bundle_dir = std::env::temp_dir().join(&name);
+    fs::create_dir_all(&bundle_dir).map_err(|e| e.to_string())?;
+
+    let app_info = format!(
+        "Espanso Helper {}\nOS: {} ({})\nespanso: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        espanso_version()
+    );
+    let contents = [
+        ("app.txt", app_info),
+        ("session.log", anonymize(&session_log.join("\n"), config_dir, files)),
+        ("settings.txt", strip_secrets(settings)),
+        ("match-files.txt", match_file_structure(config_dir, files)),
+    ];
+    for (file, text) in contents {
+        fs::write(bundle_dir.join(file), text).map_err(|e| e.to_string())?;
+    }
+    // Earlier sessions, e.g. the one that crashed
+    if let Ok(log) = fs::read_to_string(logging::log_path()) {
+        fs::write(bundle_dir.join("app.log"), anonymize(&log, config_dir, files)).map_err(|e| e.to_string())?;
+    }
+
+    let archive = target_dir.join(format!("{}.tar.gz", name));
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(std::env::temp_dir())
+        .arg(&name)
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            let _ = fs::remove_dir_all(&bundle_dir);
+            Ok(archive)
+        }
+        // Without tar the plain folder still does the job
+        _ => {
+            let fallback = target_dir.join(&name);
+            fs::rename(&bundle_dir, &fallback)
+                .or_else(|_| copy_dir(&bundle_dir, &fallback))
+                .map_err(|e| e.to_string())?;
+            Ok(fallback)
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        fs::copy(entry.path(), to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_lose_triggers_and_file_names() {
+        let config_dir = std::env::temp_dir().join("espanso-helper-diagnostics-test");
+        fs::create_dir_all(config_dir.join("work")).unwrap();
+        fs::write(config_dir.join("base.yml"), "matches:\n  - trigger: \":sig\"\n    replace: \"Best regards\"\n  - trigger: \"x\"\n    replace: \"y\"\n").unwrap();
+        fs::write(config_dir.join("work/mail.yml"), "matches:\n  - trigger: \":mail\"\n    replace: \"ann@example.com\"\n").unwrap();
+        let files = ["base.yml".to_string(), "work/mail.yml".to_string()];
+        let log = format!("Saved base.yml\nMoved :mail to work/mail.yml\nMerged into :sig in {}\n:signature stays, x too", config_dir.join("base.yml").display());
+        assert_eq!(
+            anonymize(&log, &config_dir, &files),
+            "Saved file1\nMoved <trigger> to file2\nMerged into <trigger> in <config dir>/file1\n:signature stays, x too"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{Duration, Local};
+use regex::{Captures, Regex};
+
+use crate::{locale, Match, Var};
+
+// README-style overview of a match file, meant to be committed next to it
+pub fn render(file: &str, header: &str, matches: &[Match], locale: &str) -> String {
+    let mut output = format!("# {}\n\n", file);
+    if !header.trim().is_empty() {
+        output.push_str(header.trim());
+        output.push_str("\n\n");
+    }
+    output.push_str(&format!("{} matches.\n", matches.len()));
+    for m in matches {
+        output.push_str(&format!("\n## `{}`\n\n", m.display_trigger()));
+        if let Some(label) = &m.label {
+            output.push_str(&format!("{}\n\n", label));
+        }
+        if !m.aliases.is_empty() {
+            let aliases: Vec<String> = m.aliases.iter().map(|a| format!("`{}`", a)).collect();
+            output.push_str(&format!("Also triggered by {}.\n\n", aliases.join(", ")));
+        }
+        if m.word {
+            output.push_str("Only expands as a whole word.\n\n");
+        }
+        let source = match m.content_key() {
+            Some(key) if m.replace.is_empty() => m.extra.get(key).and_then(serde_yaml::Value::as_str).unwrap_or_default(),
+            _ => &m.replace,
+        };
+        let example = example(source, &m.vars, locale);
+        let fence = if example.contains("```") { "````" } else { "```" };
+        output.push_str(&format!("{}text\n{}\n{}\n", fence, example.trim_end_matches('\n'), fence));
+    }
+    output
+}
+
+// The replacement with its variables filled in with plausible sample values. Dates
+// use the variable's own `locale` param, or `locale` when it has none.
+pub fn example(replace: &str, vars: &[Var], locale: &str) -> String {
+    let variable = Regex::new(r"\{\{\s*([\w.]+)\s*\}\}").expect("valid regex");
+    let now = Local::now();
+    let rendered = variable.replace_all(replace, |caps: &Captures| {
+        let name = &caps[1];
+        let param = |var: &Var, key: &str| var.params.get(key).and_then(serde_yaml::Value::as_str).map(str::to_string);
+        match vars.iter().find(|v| v.name == name) {
+            Some(var) if var.var_type == "date" => {
+                let format = param(var, "format").unwrap_or("%Y-%m-%d".to_string());
+                let offset = var.params.get("offset").and_then(serde_yaml::Value::as_i64).unwrap_or_default();
+                let locale = param(var, "locale").unwrap_or(locale.to_string());
+                locale::format_date(now + Duration::seconds(offset), &format, &locale).unwrap_or(format!("<{}>", name))
+            }
+            Some(var) if var.var_type == "echo" => param(var, "echo").unwrap_or_default(),
+            Some(var) if var.var_type == "random" => var.params.get("choices")
+                .and_then(|c| c.get(0))
+                .and_then(serde_yaml::Value::as_str)
+                .map_or(format!("<{}>", name), str::to_string),
+            Some(var) if var.var_type == "clipboard" => "<clipboard contents>".to_string(),
+            Some(var) if var.var_type == "shell" || var.var_type == "script" => format!("<output of {}>", name),
+            _ => format!("<{}>", name),
+        }
+    });
+    // `$|$` only tells espanso where to leave the cursor
+    rendered.replace("$|$", "")
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+// State of the espanso daemon as reported by `espanso status`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonState {
+    Unknown,
+    Running,
+    Stopped,
+    Unavailable(String),
+}
+
+fn run(args: &[&str]) -> Result<std::process::Output, String> {
+    Command::new("espanso")
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run espanso: {}", e))
+}
+
+pub fn status() -> DaemonState {
+    match run(&["status"]) {
+        Ok(output) => {
+            let text = String::from_utf8_lossy(&output.stdout).to_lowercase();
+            if output.status.success() && !text.contains("not running") {
+                DaemonState::Running
+            } else {
+                DaemonState::Stopped
+            }
+        }
+        Err(error) => DaemonState::Unavailable(error),
+    }
+}
+
+// Restarts the daemon, which also starts it when it isn't running
+pub fn restart() -> Result<(), String> {
+    let output = run(&["restart"])?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Err(if stderr.is_empty() { stdout } else { stderr })
+    }
+}
+
+// The match folder espanso itself uses, from the `Config:` line of `espanso path`
+pub fn match_dir() -> Option<PathBuf> {
+    let output = run(&["path"]).ok().filter(|output| output.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout).to_string();
+    let config = text.lines().find_map(|line| line.trim().strip_prefix("Config:"))?.trim();
+    (!config.is_empty()).then(|| PathBuf::from(config).join("match"))
+}
+
+// Where espanso keeps its matches when the CLI can't be asked: the current layout,
+// the legacy `~/.espanso` and a portable install next to the executable
+pub fn known_match_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    dirs.extend(dirs::config_dir().map(|dir| dir.join("espanso").join("match")));
+    dirs.extend(dirs::home_dir().map(|dir| dir.join(".espanso").join("match")));
+    let portable = which("espanso").and_then(|binary| binary.parent().map(|dir| dir.join(".espanso").join("match")));
+    dirs.extend(portable);
+    dirs.dedup();
+    dirs
+}
+
+// The first existing folder: espanso's answer, then the known locations
+pub fn detect_match_dir() -> Option<PathBuf> {
+    match_dir().filter(|dir| dir.is_dir()).or_else(|| known_match_dirs().into_iter().find(|dir| dir.is_dir()))
+}
+
+fn which(name: &str) -> Option<PathBuf> {
+    let binary = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join(&binary)).find(|path| path.is_file())
+}
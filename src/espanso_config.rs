@@ -0,0 +1,204 @@
+use serde_yaml::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::Match;
+
+// espanso's default for `clipboard_threshold`: longer replacements are pasted
+const DEFAULT_CLIPBOARD_THRESHOLD: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Auto,
+    Clipboard,
+    Inject,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Auto, Backend::Clipboard, Backend::Inject];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Auto => "Auto",
+            Backend::Clipboard => "Clipboard",
+            Backend::Inject => "Inject",
+        }
+    }
+}
+
+// The options of espanso's `config/default.yml` that affect how matches are typed
+#[derive(Debug, Clone)]
+pub struct GlobalConfig {
+    pub path: PathBuf,
+    pub backend: Backend,
+    pub clipboard_threshold: usize,
+    pub show_notifications: bool,
+}
+
+impl GlobalConfig {
+    pub fn load(espanso_dir: &Path) -> Self {
+        let path = espanso_dir.join("config").join("default.yml");
+        let root: Value = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or(Value::Null);
+        let backend = match root.get("backend").and_then(Value::as_str).map(str::to_lowercase).as_deref() {
+            Some("clipboard") => Backend::Clipboard,
+            Some("inject") => Backend::Inject,
+            _ => Backend::Auto,
+        };
+        Self {
+            path,
+            backend,
+            clipboard_threshold: root
+                .get("clipboard_threshold")
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_CLIPBOARD_THRESHOLD, |t| t as usize),
+            show_notifications: root.get("show_notifications").and_then(Value::as_bool).unwrap_or(true),
+        }
+    }
+
+    pub fn set_backend(&mut self, backend: Backend) -> io::Result<()> {
+        set_option(&self.path, "backend", backend.name())?;
+        self.backend = backend;
+        Ok(())
+    }
+
+    pub fn set_show_notifications(&mut self, show: bool) -> io::Result<()> {
+        set_option(&self.path, "show_notifications", if show { "true" } else { "false" })?;
+        self.show_notifications = show;
+        Ok(())
+    }
+}
+
+// Rewrites a single top-level `key: value` line, leaving comments and other options alone
+fn set_option(path: &Path, key: &str, value: &str) -> io::Result<()> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let prefix = format!("{}:", key);
+    let line = format!("{}: {}", key, value);
+    let mut found = false;
+    let mut lines: Vec<String> = contents
+        .lines()
+        .map(|l| {
+            if l.starts_with(&prefix) {
+                found = true;
+                line.clone()
+            } else {
+                l.to_string()
+            }
+        })
+        .collect();
+    if !found {
+        lines.push(line);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, lines.join("\n") + "\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fix {
+    // Set `force_clipboard` on the match
+    ForceClipboard,
+    // Switch the global backend to Auto
+    AutoBackend,
+}
+
+impl Fix {
+    pub fn label(self) -> &'static str {
+        match self {
+            Fix::ForceClipboard => "Paste this match through the clipboard",
+            Fix::AutoBackend => "Switch backend to Auto",
+        }
+    }
+}
+
+// Hints for matches that are unlikely to be typed reliably with the current backend
+pub fn advice(m: &Match, config: &GlobalConfig) -> Option<(String, Vec<Fix>)> {
+    if m.force_clipboard || config.backend == Backend::Clipboard {
+        return None;
+    }
+    let length = m.replace.chars().count();
+    let long = length > config.clipboard_threshold;
+    let classes: Vec<&str> = char_classes(&m.replace).iter().map(|stats| stats.class.name()).collect();
+    match config.backend {
+        Backend::Inject if long || !classes.is_empty() => {
+            let what = if classes.is_empty() { format!("{} characters", length) } else { classes.join(", ") };
+            Some((
+                format!("espanso types {} key by key with the Inject backend, which is slow and may garble them.", what),
+                vec![Fix::ForceClipboard, Fix::AutoBackend],
+            ))
+        }
+        Backend::Auto if !classes.is_empty() && !long => Some((
+            format!("Short replacements are typed key by key, and some applications drop {} typed that way.", classes.join(", ")),
+            vec![Fix::ForceClipboard],
+        )),
+        _ => None,
+    }
+}
+
+// Characters the Inject backend types unreliably: they have no key of their own on
+// most layouts, so espanso has to emulate them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CharClass {
+    Emoji,
+    Cjk,
+    // Letters like é or ñ, typed with a dead key followed by the base letter
+    DeadKey,
+}
+
+impl CharClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            CharClass::Emoji => "emoji",
+            CharClass::Cjk => "CJK characters",
+            CharClass::DeadKey => "dead-key accents",
+        }
+    }
+
+    fn of(c: char) -> Option<CharClass> {
+        use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+        match c as u32 {
+            0x2600..=0x27BF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D => return Some(CharClass::Emoji),
+            0x3000..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF => {
+                return Some(CharClass::Cjk)
+            }
+            _ => {}
+        }
+        let mut accented = is_combining_mark(c) || matches!(c, '´' | '¨' | '¸' | '˘' | 'ˇ' | '˛' | '˙' | '˝');
+        decompose_canonical(c, |part| accented |= is_combining_mark(part));
+        accented.then_some(CharClass::DeadKey)
+    }
+}
+
+// How many characters of each class `text` holds, with the first few of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharStats {
+    pub class: CharClass,
+    pub count: usize,
+    pub examples: String,
+}
+
+pub fn char_classes(text: &str) -> Vec<CharStats> {
+    let mut found: Vec<CharStats> = Vec::new();
+    for c in text.chars() {
+        let Some(class) = CharClass::of(c) else { continue };
+        let index = match found.iter().position(|stats| stats.class == class) {
+            Some(index) => index,
+            None => {
+                found.push(CharStats { class, count: 0, examples: String::new() });
+                found.len() - 1
+            }
+        };
+        let stats = &mut found[index];
+        stats.count += 1;
+        // Joiners and variation selectors are counted, but show nothing on their own
+        if stats.examples.chars().count() < 5 && !stats.examples.contains(c) && !matches!(c, '\u{FE0F}' | '\u{200D}') {
+            stats.examples.push(c);
+        }
+    }
+    found.sort_by_key(|stats| stats.class);
+    found
+}
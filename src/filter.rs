@@ -0,0 +1,156 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    #[default]
+    Substring,
+    // The typed letters in order, with gaps; best matches first
+    Fuzzy,
+    Regex,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 3] = [FilterMode::Substring, FilterMode::Fuzzy, FilterMode::Regex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FilterMode::Substring => "Contains",
+            FilterMode::Fuzzy => "Fuzzy",
+            FilterMode::Regex => "Regex",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterScope {
+    #[default]
+    All,
+    Trigger,
+    Replacement,
+}
+
+impl FilterScope {
+    pub const ALL: [FilterScope; 3] = [FilterScope::All, FilterScope::Trigger, FilterScope::Replacement];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FilterScope::All => "Everywhere",
+            FilterScope::Trigger => "Triggers",
+            FilterScope::Replacement => "Replacements",
+        }
+    }
+}
+
+// The filter text prepared once for testing many matches, ignoring case in every mode
+pub enum Query {
+    Empty,
+    Substring(String),
+    Fuzzy(Vec<char>),
+    Regex(Regex),
+    // A regex that doesn't compile matches nothing
+    Invalid(String),
+}
+
+impl Query {
+    pub fn new(text: &str, mode: FilterMode) -> Self {
+        if text.is_empty() {
+            return Query::Empty;
+        }
+        match mode {
+            FilterMode::Substring => Query::Substring(text.to_lowercase()),
+            FilterMode::Fuzzy => Query::Fuzzy(text.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).collect()),
+            FilterMode::Regex => match RegexBuilder::new(text).case_insensitive(true).build() {
+                Ok(regex) => Query::Regex(regex),
+                Err(error) => Query::Invalid(error.to_string()),
+            },
+        }
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Query::Invalid(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    // Whether the match passes, with a higher score for better fuzzy matches
+    pub fn score(&self, scope: FilterScope, trigger: &str, replace: &str) -> Option<i64> {
+        match scope {
+            FilterScope::All => self.score_text(trigger).max(self.score_text(replace)),
+            FilterScope::Trigger => self.score_text(trigger),
+            FilterScope::Replacement => self.score_text(replace),
+        }
+    }
+
+    fn score_text(&self, text: &str) -> Option<i64> {
+        match self {
+            Query::Empty => Some(0),
+            Query::Substring(filter) => text.to_lowercase().contains(filter).then_some(0),
+            Query::Fuzzy(pattern) => fuzzy_score(pattern, text),
+            Query::Regex(regex) => regex.is_match(text).then_some(0),
+            Query::Invalid(_) => None,
+        }
+    }
+}
+
+// Every pattern character has to appear in order. Runs of adjacent characters and
+// characters starting a word count more, gaps count less.
+fn fuzzy_score(pattern: &[char], text: &str) -> Option<i64> {
+    let chars: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    for &wanted in pattern {
+        let start = previous.map_or(0, |p| p + 1);
+        let found = start + chars.get(start..)?.iter().position(|&c| c == wanted)?;
+        score += 10;
+        score -= (found - start).min(10) as i64;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 15;
+        }
+        if found == 0 || !chars[found - 1].is_alphanumeric() {
+            score += 10;
+        }
+        previous = Some(found);
+    }
+    // Of two equally good matches the shorter text wins
+    Some(score * 100 - chars.len().min(99) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substring_ignores_case() {
+        let query = Query::new("REGARDS", FilterMode::Substring);
+        assert_eq!(query.score(FilterScope::All, ":sig", "Best regards"), Some(0));
+        assert_eq!(query.score(FilterScope::Trigger, ":sig", "Best regards"), None);
+        assert_eq!(Query::new("", FilterMode::Regex).score(FilterScope::Trigger, ":sig", ""), Some(0));
+    }
+
+    #[test]
+    fn regex_mode() {
+        let query = Query::new("^:d", FilterMode::Regex);
+        assert_eq!(query.score(FilterScope::All, ":date", "{{today}}"), Some(0));
+        assert_eq!(query.score(FilterScope::All, ":sig", "Best regards"), None);
+        let invalid = Query::new("(", FilterMode::Regex);
+        assert!(invalid.error().is_some());
+        assert_eq!(invalid.score(FilterScope::All, "(", "("), None);
+    }
+
+    #[test]
+    fn fuzzy_mode() {
+        // "Best regards" has the letters in order, its trigger doesn't
+        let query = Query::new("rgrds", FilterMode::Fuzzy);
+        assert!(query.score(FilterScope::Replacement, ":sig", "Best regards").is_some());
+        assert_eq!(query.score(FilterScope::Trigger, ":sig", "Best regards"), None);
+        // Adjacent letters at a word start beat scattered ones
+        let query = Query::new("reg", FilterMode::Fuzzy);
+        let close = query.score(FilterScope::All, ":regards", "").unwrap();
+        let scattered = query.score(FilterScope::All, ":rxexg", "").unwrap();
+        assert!(close > scattered, "{} vs {}", close, scattered);
+    }
+}
@@ -0,0 +1,130 @@
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Multiline,
+    Choice,
+    List,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 4] = [FieldKind::Text, FieldKind::Multiline, FieldKind::Choice, FieldKind::List];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldKind::Text => "Text",
+            FieldKind::Multiline => "Multiline",
+            FieldKind::Choice => "Choice",
+            FieldKind::List => "List",
+        }
+    }
+
+    pub fn has_values(self) -> bool {
+        matches!(self, FieldKind::Choice | FieldKind::List)
+    }
+}
+
+// A `[[name]]` placeholder of a form layout and its entry under `form_fields`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormField {
+    pub name: String,
+    pub kind: FieldKind,
+    // Choices of a choice or list field, one per line
+    pub values: String,
+    pub default: String,
+}
+
+// Placeholder names in the order they first appear in the layout
+pub fn field_names(layout: &str) -> Vec<String> {
+    let placeholder = Regex::new(r"\[\[\s*(\w+)\s*\]\]").expect("valid regex");
+    let mut names: Vec<String> = Vec::new();
+    for caps in placeholder.captures_iter(layout) {
+        if !names.iter().any(|n| n == &caps[1]) {
+            names.push(caps[1].to_string());
+        }
+    }
+    names
+}
+
+// One field per placeholder, keeping the settings of fields that are still used
+pub fn sync_fields(layout: &str, fields: &[FormField]) -> Vec<FormField> {
+    field_names(layout)
+        .into_iter()
+        .map(|name| fields.iter().find(|f| f.name == name).cloned().unwrap_or(FormField { name, ..Default::default() }))
+        .collect()
+}
+
+// The layout and fields of a form match from its `form` and `form_fields` keys
+pub fn read(extra: &Mapping) -> (String, Vec<FormField>) {
+    let layout = extra.get("form").and_then(Value::as_str).unwrap_or_default().to_string();
+    let definitions = extra.get("form_fields").and_then(Value::as_mapping);
+    let fields = field_names(&layout)
+        .into_iter()
+        .map(|name| {
+            let Some(definition) = definitions.and_then(|d| d.get(name.as_str())) else {
+                return FormField { name, ..Default::default() };
+            };
+            let kind = match definition.get("type").and_then(Value::as_str) {
+                Some("choice") => FieldKind::Choice,
+                Some("list") => FieldKind::List,
+                _ if definition.get("multiline").and_then(Value::as_bool) == Some(true) => FieldKind::Multiline,
+                _ => FieldKind::Text,
+            };
+            let values = match definition.get("values") {
+                Some(Value::Sequence(values)) => values.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("\n"),
+                Some(Value::String(values)) => values.clone(),
+                _ => String::new(),
+            };
+            let default = definition.get("default").and_then(Value::as_str).unwrap_or_default().to_string();
+            FormField { name, kind, values, default }
+        })
+        .collect();
+    (layout, fields)
+}
+
+// Stores the form in `extra`, or removes it when the layout is empty. Definitions of
+// fields the builder doesn't know about are kept.
+pub fn write(extra: &mut Mapping, layout: &str, fields: &[FormField]) {
+    if layout.trim().is_empty() {
+        extra.remove("form");
+        extra.remove("form_fields");
+        return;
+    }
+    let mut definitions = extra.get("form_fields").and_then(Value::as_mapping).cloned().unwrap_or_default();
+    for field in fields {
+        let mut definition = definitions.get(field.name.as_str()).and_then(Value::as_mapping).cloned().unwrap_or_default();
+        for key in ["type", "multiline", "values", "default"] {
+            definition.remove(key);
+        }
+        match field.kind {
+            FieldKind::Text => {}
+            FieldKind::Multiline => {
+                definition.insert("multiline".into(), true.into());
+            }
+            FieldKind::Choice | FieldKind::List => {
+                let kind = if field.kind == FieldKind::Choice { "choice" } else { "list" };
+                definition.insert("type".into(), kind.into());
+                let values: Vec<Value> = field.values.lines().filter(|l| !l.is_empty()).map(Value::from).collect();
+                definition.insert("values".into(), Value::Sequence(values));
+            }
+        }
+        if !field.default.is_empty() {
+            definition.insert("default".into(), field.default.as_str().into());
+        }
+        if definition.is_empty() {
+            definitions.remove(field.name.as_str());
+        } else {
+            definitions.insert(field.name.as_str().into(), Value::Mapping(definition));
+        }
+    }
+    definitions.retain(|name, _| name.as_str().is_some_and(|name| fields.iter().any(|f| f.name == name)));
+    extra.insert("form".into(), layout.into());
+    if definitions.is_empty() {
+        extra.remove("form_fields");
+    } else {
+        extra.insert("form_fields".into(), Value::Mapping(definitions));
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::presets::FilePreset;
+use crate::table::{self, ColumnMapping};
+use crate::{yaml_io, Match};
+
+const NOTICE: &str = "Generated by Espanso Helper, Regenerate replaces manual edits";
+const MARKER: &str = "generator: ";
+
+// How a generated file came about, kept in its header comment so the file can be
+// rebuilt later with the same parameters
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Generator {
+    EmojiPack,
+    // One match per row of a CSV or JSON table
+    Table {
+        source: PathBuf,
+        // The delimiter picked for a CSV file; None for JSON
+        delimiter: Option<char>,
+        mapping: ColumnMapping,
+    },
+}
+
+// The matches a generator produced, with tags per trigger for the metadata
+pub struct Output {
+    pub matches: Vec<Match>,
+    pub tags: HashMap<String, Vec<String>>,
+}
+
+impl Generator {
+    pub fn describe(&self) -> String {
+        match self {
+            Generator::EmojiPack => "the emoji pack preset".to_string(),
+            Generator::Table { source, .. } => source.display().to_string(),
+        }
+    }
+
+    // Runs the generator again; a table is read from its source file as it is now
+    pub fn run(&self) -> Result<Output, String> {
+        match self {
+            Generator::EmojiPack => {
+                Ok(Output { matches: yaml_io::MatchFile::parse(FilePreset::EmojiPack.contents()).matches(), tags: HashMap::new() })
+            }
+            Generator::Table { source, delimiter, mapping } => {
+                let unreadable = |error: String| format!("Could not read {}: {}", source.display(), error);
+                let bytes = fs::read(source).map_err(|e| unreadable(e.to_string()))?;
+                let table = match delimiter {
+                    Some(delimiter) => table::parse_csv(&bytes).and_then(|table| table.with_delimiter(*delimiter)),
+                    None => table::parse_json(&table::decode(&bytes).0),
+                }
+                .map_err(unreadable)?;
+                if table.width() <= mapping.trigger.max(mapping.replace) {
+                    return Err(format!("{} no longer has the columns it was generated from", source.display()));
+                }
+                Ok(Output { matches: table.to_matches(mapping), tags: table.tags(mapping).into_iter().collect() })
+            }
+        }
+    }
+}
+
+// The generator recorded in a file header, if any
+pub fn read(header: &str) -> Option<Generator> {
+    header.lines().find_map(|line| serde_json::from_str(line.strip_prefix(MARKER)?).ok())
+}
+
+// `header` with the notice and `generator` in place of a previous marker
+pub fn mark(header: &str, generator: &Generator) -> String {
+    let kept: Vec<&str> = header.lines().filter(|line| *line != NOTICE && !line.starts_with(MARKER)).collect();
+    let mut lines = vec![NOTICE.to_string(), format!("{}{}", MARKER, serde_json::to_string(generator).unwrap_or_default())];
+    lines.extend(kept.iter().map(|line| line.to_string()));
+    lines.join("\n").trim_end().to_string()
+}
+
+// The header comment as it starts a freshly written file
+pub fn comment(generator: &Generator) -> String {
+    mark("", generator).lines().map(|line| format!("# {}\n", line)).collect()
+}
@@ -0,0 +1,149 @@
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+use crate::backups;
+use crate::vars;
+use crate::yaml_io::MatchFile;
+use crate::Var;
+
+// A variable under `global_vars` and the file defining it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub var_type: String,
+    pub file: String,
+}
+
+pub fn definitions(config_dir: &Path, files: &[String]) -> Vec<Definition> {
+    let mut found = Vec::new();
+    for file in files {
+        let contents = fs::read_to_string(config_dir.join(file)).unwrap_or_default();
+        for var in MatchFile::parse(&contents).global_vars() {
+            found.push(Definition { name: var.name, var_type: var.var_type, file: file.clone() });
+        }
+    }
+    found
+}
+
+// One place a rename touches, for the preview
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub file: String,
+    // The trigger of the match, or the global variable itself
+    pub subject: String,
+    pub before: String,
+    pub after: String,
+}
+
+// A global variable rename worked out over every match file, not written yet
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub old: String,
+    pub new: String,
+    pub changes: Vec<Change>,
+    // New contents of every affected file
+    outputs: Vec<(String, String)>,
+}
+
+impl Rename {
+    pub fn files(&self) -> Vec<&str> {
+        self.outputs.iter().map(|(file, _)| file.as_str()).collect()
+    }
+}
+
+// Renames `old` where it is defined and in every `{{old}}` reference, except in matches
+// with a variable or capture group of that name, which refer to their own
+pub fn plan(config_dir: &Path, files: &[String], old: &str, new: &str) -> Result<Rename, String> {
+    if !Regex::new(r"^\w+$").expect("valid regex").is_match(new) {
+        return Err(format!("{} is not a valid variable name, use letters, digits and _", new));
+    }
+    let defined = definitions(config_dir, files);
+    if !defined.iter().any(|d| d.name == old) {
+        return Err(format!("{} is no longer a global variable", old));
+    }
+    if defined.iter().any(|d| d.name == new) {
+        return Err(format!("There already is a global variable {}", new));
+    }
+    let update = |text: &str| vars::rename_references(text, old, new);
+    let mut rename = Rename { old: old.to_string(), new: new.to_string(), changes: Vec::new(), outputs: Vec::new() };
+    for file in files {
+        let contents = fs::read_to_string(config_dir.join(file)).map_err(|e| format!("Could not read {}: {}", file, e))?;
+        let mut document = MatchFile::parse(&contents);
+        if let Some(error) = document.error() {
+            return Err(format!("{} can't be read in full, fix it first ({})", file, error));
+        }
+        let before_vars = document.global_vars();
+        let mut changed = document.rename_global_var(old, new, &update);
+        for (before, after) in before_vars.iter().zip(document.global_vars()) {
+            if *before != after {
+                rename.changes.push(Change {
+                    file: file.clone(),
+                    subject: "global_vars".to_string(),
+                    before: describe(&before.name, std::slice::from_ref(before)),
+                    after: describe(&after.name, std::slice::from_ref(&after)),
+                });
+            }
+        }
+        let mut matches = document.matches();
+        for m in matches.iter_mut() {
+            let captures = m.regex.as_deref().and_then(|r| Regex::new(r).ok()).is_some_and(|r| r.capture_names().flatten().any(|c| c == old));
+            if captures || m.vars.iter().any(|v| v.name == old) {
+                continue;
+            }
+            let original = m.clone();
+            m.replace = update(&m.replace);
+            for var in m.vars.iter_mut() {
+                let mut params = serde_yaml::Value::Mapping(std::mem::take(&mut var.params));
+                vars::map_strings(&mut params, &update);
+                if let serde_yaml::Value::Mapping(params) = params {
+                    var.params = params;
+                }
+            }
+            if *m == original {
+                continue;
+            }
+            if m.vars.iter().any(|v| v.name == new) {
+                return Err(format!("{} in {} has its own variable {}", m.display_trigger(), file, new));
+            }
+            let (before, after) = if original.replace != m.replace {
+                (original.replace, m.replace.clone())
+            } else {
+                (describe(&m.replace, &original.vars), describe(&m.replace, &m.vars))
+            };
+            rename.changes.push(Change { file: file.clone(), subject: m.display_trigger(), before, after });
+            changed = true;
+        }
+        if changed {
+            rename.outputs.push((file.clone(), document.render(&matches)?));
+        }
+    }
+    Ok(rename)
+}
+
+// Writes every affected file. All new contents go to temporary files first, so a
+// failure leaves the match folder as it was.
+pub fn apply(config_dir: &Path, rename: &Rename, keep_backups: usize) -> Result<(), String> {
+    let temporary = |file: &str| config_dir.join(format!("{}.renaming", file));
+    for (file, contents) in &rename.outputs {
+        if let Err(error) = fs::write(temporary(file), contents) {
+            for (file, _) in &rename.outputs {
+                let _ = fs::remove_file(temporary(file));
+            }
+            return Err(format!("Writing {} failed: {}", file, error));
+        }
+    }
+    for (file, _) in &rename.outputs {
+        if let Err(error) = backups::create(config_dir, file, keep_backups) {
+            log::warn!("Backing up {} failed: {}", file, error);
+        }
+        fs::rename(temporary(file), config_dir.join(file)).map_err(|e| format!("Replacing {} failed: {}", file, e))?;
+    }
+    Ok(())
+}
+
+// A variable or replacement with the params of `vars`, where a reference may hide
+fn describe(text: &str, vars: &[Var]) -> String {
+    let params: Vec<String> = vars.iter().map(|var| serde_json::to_string(&var.params).unwrap_or_default()).collect();
+    format!("{} {}", text, params.join(" "))
+}
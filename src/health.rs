@@ -0,0 +1,157 @@
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::validation::Report;
+use crate::yaml_io::MatchFile;
+use crate::{app_config_dir, backups};
+
+const HEALTH_FILE: &str = "health.yml";
+const STAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+const SUMMARY_INTERVAL_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FileStats {
+    pub bytes: u64,
+    pub matches: usize,
+}
+
+// What the match folder looked like when the last weekly summary was shown, to
+// compare the next one against
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct HealthLog {
+    // None until the first baseline is taken
+    pub last_summary: Option<String>,
+    pub files: BTreeMap<String, FileStats>,
+    // Triggers defined more than once
+    pub duplicates: BTreeSet<String>,
+    // When the daemon was first seen running since it was last seen stopped
+    pub daemon_up_since: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub since: String,
+    // Files with more bytes than a week ago, with their stats then and now
+    pub grown: Vec<(String, FileStats, FileStats)>,
+    pub new_files: Vec<String>,
+    pub new_duplicates: Vec<String>,
+    pub backups: usize,
+    pub daemon_uptime: Option<chrono::Duration>,
+}
+
+impl Summary {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (file, before, now) in &self.grown {
+            lines.push(format!("{} grew from {} to {} matches ({} → {} bytes)", file, before.matches, now.matches, before.bytes, now.bytes));
+        }
+        if !self.new_files.is_empty() {
+            lines.push(format!("New files: {}", self.new_files.join(", ")));
+        }
+        if self.grown.is_empty() && self.new_files.is_empty() {
+            lines.push("No file grew.".to_string());
+        }
+        lines.push(match self.new_duplicates.len() {
+            0 => "No new duplicate triggers.".to_string(),
+            _ => format!("New duplicate triggers: {}", self.new_duplicates.join(", ")),
+        });
+        lines.push(format!("{} backups taken", self.backups));
+        lines.push(match self.daemon_uptime {
+            Some(uptime) => format!("espanso has been running for {} days and {} hours", uptime.num_days(), uptime.num_hours() % 24),
+            None => "espanso was not seen running".to_string(),
+        });
+        lines
+    }
+}
+
+pub fn now() -> String {
+    Local::now().format(STAMP_FORMAT).to_string()
+}
+
+fn parse(stamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).ok()
+}
+
+pub fn stats(config_dir: &Path, files: &[String]) -> BTreeMap<String, FileStats> {
+    files
+        .iter()
+        .map(|file| {
+            let contents = fs::read_to_string(config_dir.join(file)).unwrap_or_default();
+            let matches = MatchFile::parse(&contents).matches().len();
+            (file.clone(), FileStats { bytes: contents.len() as u64, matches })
+        })
+        .collect()
+}
+
+pub fn duplicates(report: &Report) -> BTreeSet<String> {
+    report.triggers.iter().filter(|(_, defined)| defined.len() > 1).map(|(trigger, _)| trigger.clone()).collect()
+}
+
+impl HealthLog {
+    pub fn load() -> Self {
+        fs::read_to_string(app_config_dir().join(HEALTH_FILE))
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(dir.join(HEALTH_FILE), contents)
+    }
+
+    // A week after the last summary, or right away when there is no baseline yet
+    pub fn due(&self) -> bool {
+        match self.last_summary.as_deref().and_then(parse) {
+            Some(last) => Local::now().naive_local() - last >= chrono::Duration::days(SUMMARY_INTERVAL_DAYS),
+            None => true,
+        }
+    }
+
+    // Compares the folder as it is now with the baseline; None without a baseline
+    pub fn summarize(&self, files: &BTreeMap<String, FileStats>, duplicates: &BTreeSet<String>) -> Option<Summary> {
+        let since = self.last_summary.clone()?;
+        let mut grown = Vec::new();
+        let mut new_files = Vec::new();
+        for (file, now) in files {
+            match self.files.get(file) {
+                Some(before) if now.bytes > before.bytes => grown.push((file.clone(), *before, *now)),
+                Some(_) => {}
+                None => new_files.push(file.clone()),
+            }
+        }
+        let uptime = self.daemon_up_since.as_deref().and_then(parse).map(|up| Local::now().naive_local() - up);
+        Some(Summary {
+            backups: parse(&since).map_or(0, backups::count_since),
+            since,
+            grown,
+            new_files,
+            new_duplicates: duplicates.difference(&self.duplicates).cloned().collect(),
+            daemon_uptime: uptime,
+        })
+    }
+
+    // Takes the current state as the baseline for the next summary
+    pub fn record(&mut self, files: BTreeMap<String, FileStats>, duplicates: BTreeSet<String>) {
+        self.last_summary = Some(now());
+        self.files = files;
+        self.duplicates = duplicates;
+    }
+
+    // Follows the daemon state; true when something changed worth saving
+    pub fn track_daemon(&mut self, running: bool) -> bool {
+        match (running, &self.daemon_up_since) {
+            (true, None) => self.daemon_up_since = Some(now()),
+            (false, Some(_)) => self.daemon_up_since = None,
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,108 @@
+use regex::Regex;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token {
+    Plain,
+    Comment,
+    Key,
+    String,
+    Variable,
+    Punctuation,
+}
+
+// Splits YAML source into highlighted ranges covering all of it. This is a line-based
+// approximation good enough for match files, not a YAML parser: keys, quoted strings,
+// comments, block scalars and `{{variables}}` are recognized.
+pub fn yaml(source: &str) -> Vec<(Range<usize>, Token)> {
+    let key = Regex::new(r#"^(?:[\w\-. ]+|"[^"]*"|'[^']*'):(?:\s|$)"#).expect("valid regex");
+    let mut spans: Vec<(Range<usize>, Token)> = Vec::new();
+    let mut push = |range: Range<usize>, token: Token| {
+        if range.is_empty() {
+            return;
+        }
+        match spans.last_mut() {
+            Some((last, last_token)) if *last_token == token && last.end == range.start => last.end = range.end,
+            _ => spans.push((range, token)),
+        }
+    };
+    // Indentation of the key owning a `|` or `>` block while inside it
+    let mut block_indent: Option<usize> = None;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let indent = content.len() - content.trim_start().len();
+        if let Some(owner) = block_indent {
+            if content.trim().is_empty() || indent > owner {
+                variables(start, line, Token::String, &mut push);
+                continue;
+            }
+            block_indent = None;
+        }
+        let mut pos = indent;
+        push(start..start + pos, Token::Plain);
+        let rest = &content[pos..];
+        if rest.starts_with("- ") || rest == "-" {
+            push(start + pos..start + pos + 1, Token::Punctuation);
+            pos += 1;
+            let spaces = content[pos..].len() - content[pos..].trim_start().len();
+            push(start + pos..start + pos + spaces, Token::Plain);
+            pos += spaces;
+        }
+        let item_indent = pos;
+        if content[pos..].starts_with('#') {
+            push(start + pos..start + line.len(), Token::Comment);
+            continue;
+        }
+        if let Some(found) = key.find(&content[pos..]) {
+            let colon = content[pos..pos + found.end()].trim_end().len();
+            push(start + pos..start + pos + colon - 1, Token::Key);
+            push(start + pos + colon - 1..start + pos + colon, Token::Punctuation);
+            pos += colon;
+        }
+        let value = content[pos..].trim();
+        if value.starts_with('|') || value.starts_with('>') {
+            block_indent = Some(item_indent);
+        }
+        scalar(start + pos, &content[pos..], &mut push);
+        push(start + content.len()..start + line.len(), Token::Plain);
+    }
+    spans
+}
+
+// A value after the key: quoted strings, trailing comments and variables
+fn scalar(start: usize, value: &str, push: &mut impl FnMut(Range<usize>, Token)) {
+    let trimmed = value.trim_start();
+    let lead = value.len() - trimmed.len();
+    push(start..start + lead, Token::Plain);
+    let start = start + lead;
+    if let Some(quote) = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = trimmed[1..].find(quote).map_or(trimmed.len(), |i| i + 2);
+        variables(start, &trimmed[..end], Token::String, push);
+        scalar(start + end, &trimmed[end..], push);
+        return;
+    }
+    match trimmed.find(" #").or_else(|| trimmed.starts_with('#').then_some(0)) {
+        Some(comment) => {
+            variables(start, &trimmed[..comment], Token::Plain, push);
+            push(start + comment..start + trimmed.len(), Token::Comment);
+        }
+        None => variables(start, trimmed, Token::Plain, push),
+    }
+}
+
+// `text` as `token`, except for `{{variable}}` placeholders
+fn variables(start: usize, text: &str, token: Token, push: &mut impl FnMut(Range<usize>, Token)) {
+    let mut pos = 0;
+    while let Some(open) = text[pos..].find("{{") {
+        let open = pos + open;
+        let Some(close) = text[open..].find("}}") else { break };
+        let close = open + close + 2;
+        push(start + pos..start + open, token);
+        push(start + open..start + close, Token::Variable);
+        pos = close;
+    }
+    push(start + pos..start + text.len(), token);
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::generated::Generator;
+use crate::table::{ColumnMapping, Table};
+use crate::{Match, Var};
+
+// Files that already define a trigger proposed by an import
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub trigger: String,
+    pub files: Vec<String>,
+}
+
+// Matches waiting to be imported together with the conflicts found against the config
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    pub source: String,
+    pub candidates: Vec<Match>,
+    pub collisions: Vec<Collision>,
+    pub skip_collisions: bool,
+    // Replace matches of the target file that have the same trigger instead of adding
+    // a second definition
+    pub merge_existing: bool,
+    // Rows of a CSV or JSON table and how they turn into `candidates`
+    pub table: Option<(Table, ColumnMapping)>,
+    // Tags per trigger from the table's tags column, stored as metadata on import
+    pub tags: HashMap<String, Vec<String>>,
+    // The file a table was read from, which a generated file refers back to
+    pub path: Option<PathBuf>,
+    // Replace the target's matches with the table and remember it for regenerating
+    pub generated: bool,
+    // What converting an espanso 1 file left to check by hand
+    pub legacy_notes: Option<Vec<String>>,
+    // Global variables of the source, added to the target file unless already defined
+    pub global_vars: Vec<Var>,
+    existing: HashMap<String, Vec<String>>,
+}
+
+impl ImportPreview {
+    // `existing` maps every trigger in the config directory to the files defining it.
+    // Triggers repeated within the import itself are reported as well.
+    pub fn new(source: String, candidates: Vec<Match>, existing: &HashMap<String, Vec<String>>) -> Self {
+        let mut preview = Self {
+            source,
+            candidates,
+            collisions: Vec::new(),
+            skip_collisions: true,
+            merge_existing: false,
+            table: None,
+            tags: HashMap::new(),
+            path: None,
+            generated: false,
+            legacy_notes: None,
+            global_vars: Vec::new(),
+            existing: existing.clone(),
+        };
+        preview.find_collisions();
+        preview
+    }
+
+    pub fn from_table(source: String, table: Table, existing: &HashMap<String, Vec<String>>) -> Self {
+        let mapping = table.guess_mapping();
+        let mut preview = Self::new(source, Vec::new(), existing);
+        preview.table = Some((table, mapping.clone()));
+        preview.remap(mapping);
+        preview
+    }
+
+    // How to build the table's matches again, when it came from a local file
+    pub fn generator(&self) -> Option<Generator> {
+        let (table, mapping) = self.table.as_ref()?;
+        let delimiter = table.csv.as_ref().map(|csv| csv.delimiter);
+        Some(Generator::Table { source: self.path.clone()?, delimiter, mapping: mapping.clone() })
+    }
+
+    // Rebuilds the candidates after the column mapping changed
+    pub fn remap(&mut self, mapping: ColumnMapping) {
+        let Some((table, _)) = self.table.take() else { return };
+        self.candidates = table.to_matches(&mapping);
+        self.tags = table.tags(&mapping).into_iter().collect();
+        self.table = Some((table, mapping));
+        self.find_collisions();
+    }
+
+    // Reads the CSV again split at `delimiter`, which changes the columns
+    pub fn set_delimiter(&mut self, delimiter: char) -> Result<(), String> {
+        let Some((table, _)) = &self.table else { return Ok(()) };
+        let table = table.with_delimiter(delimiter)?;
+        let mapping = table.guess_mapping();
+        self.table = Some((table, mapping.clone()));
+        self.remap(mapping);
+        Ok(())
+    }
+
+    fn find_collisions(&mut self) {
+        let (source, candidates, existing) = (&self.source, &self.candidates, &self.existing);
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for trigger in candidates.iter().flat_map(Match::all_triggers) {
+            *seen.entry(trigger).or_default() += 1;
+        }
+
+        let mut collisions: Vec<Collision> = Vec::new();
+        for trigger in candidates.iter().flat_map(Match::all_triggers) {
+            if collisions.iter().any(|c| c.trigger == trigger) {
+                continue;
+            }
+            let mut files = existing.get(&trigger).cloned().unwrap_or_default();
+            if seen[&trigger] > 1 {
+                files.push(format!("{} (repeated)", source));
+            }
+            if !files.is_empty() {
+                collisions.push(Collision { trigger, files });
+            }
+        }
+        self.collisions = collisions;
+    }
+
+    pub fn is_colliding(&self, trigger: &str) -> bool {
+        self.collisions.iter().any(|c| c.trigger == trigger)
+    }
+
+    // What importing into `target` does with each accepted candidate: update the match
+    // at the index, counting the ones added before it, or add it (None)
+    fn plan(&self, target: &[Match]) -> Vec<(&Match, Option<usize>)> {
+        let mut steps = Vec::new();
+        let mut added: Vec<&Match> = Vec::new();
+        for m in &self.candidates {
+            let triggers = m.all_triggers();
+            if self.merge_existing {
+                let existing = target.iter().chain(added.iter().copied()).position(|t| t.all_triggers().iter().any(|t| triggers.contains(t)));
+                if existing.is_some() {
+                    steps.push((m, existing));
+                    continue;
+                }
+            }
+            if self.skip_collisions && triggers.iter().any(|t| self.is_colliding(t)) {
+                continue;
+            }
+            added.push(m);
+            steps.push((m, None));
+        }
+        steps
+    }
+
+    // How many matches of `target` an import would (update, add), without importing
+    pub fn outcome(&self, target: &[Match]) -> (usize, usize) {
+        let steps = self.plan(target);
+        let updated = steps.iter().filter(|(_, step)| step.is_some()).count();
+        (updated, steps.len() - updated)
+    }
+
+    // Writes the accepted matches into `target`, merging into existing definitions when
+    // `merge_existing` is set. Returns how many were (updated, added).
+    pub fn apply(&self, target: &mut Vec<Match>) -> (usize, usize) {
+        let (mut updated, mut added) = (0, 0);
+        for (m, step) in self.plan(target) {
+            match step {
+                Some(index) => {
+                    let existing = &mut target[index];
+                    existing.replace = m.replace.clone();
+                    if m.label.is_some() {
+                        existing.label = m.label.clone();
+                    }
+                    updated += 1;
+                }
+                None => {
+                    target.push(m.clone());
+                    added += 1;
+                }
+            }
+        }
+        (updated, added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(trigger: &str, replace: &str) -> Match {
+        Match { trigger: trigger.to_string(), replace: replace.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn outcome_matches_what_apply_does() {
+        let target = vec![plain(":a", "A"), plain(":b", "B")];
+        let existing = HashMap::from([(":a".to_string(), vec!["base.yml".to_string()])]);
+        let candidates = vec![plain(":a", "New A"), plain(":c", "C"), plain(":c", "Other C")];
+        let mut preview = ImportPreview::new("import.yml".to_string(), candidates, &existing);
+        for (skip_collisions, merge_existing) in [(true, false), (false, false), (true, true), (false, true)] {
+            preview.skip_collisions = skip_collisions;
+            preview.merge_existing = merge_existing;
+            let mut applied = target.clone();
+            let counts = preview.apply(&mut applied);
+            assert_eq!(preview.outcome(&target), counts, "skip {} merge {}", skip_collisions, merge_existing);
+        }
+        // Repeated triggers update the one added first
+        let mut applied = target.clone();
+        assert_eq!(preview.apply(&mut applied), (2, 1));
+        assert_eq!(applied[2].replace, "Other C");
+    }
+}
@@ -0,0 +1,199 @@
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+
+use crate::yaml_io::MatchFile;
+use crate::{Match, Var};
+
+// Keys of an espanso 1 match file that only said where it belongs; espanso 2 has no use for them
+const DROPPED_KEYS: [&str; 2] = ["name", "parent"];
+
+// Keys espanso 2 reads from app-specific config files under `config/`
+const FILTER_KEYS: [&str; 4] = ["filter_title", "filter_class", "filter_exec", "exclude_default_entries"];
+
+// Keys of a file espanso 2 reads as a match file
+const MATCH_FILE_KEYS: [&str; 4] = ["matches", "global_vars", "imports", "anchors"];
+
+// Settings espanso 1 read from `default.yml` next to its matches
+const CONFIG_KEYS: [&str; 10] = [
+    "toggle_key",
+    "backend",
+    "enable_passive",
+    "passive_key",
+    "passive_match_regex",
+    "passive_arg_delimiter",
+    "passive_arg_escape",
+    "auto_restart",
+    "ipc_server_port",
+    "word_separators",
+];
+
+// An espanso 1 file rewritten for espanso 2
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub matches: Vec<Match>,
+    // Variables every match of the file could use, to be added where the matches go
+    pub global_vars: Vec<Var>,
+    // What couldn't be converted or now behaves differently, one line each
+    pub notes: Vec<String>,
+}
+
+// Converts `contents` when it uses anything only espanso 1 understood; None for files
+// that read the same in both versions or don't parse at all
+pub fn convert(contents: &str) -> Option<Conversion> {
+    let Ok(Value::Mapping(mut root)) = serde_yaml::from_str::<Value>(contents) else { return None };
+    if !is_legacy(&root) {
+        return None;
+    }
+    let mut notes = Vec::new();
+    for key in DROPPED_KEYS {
+        root.remove(key);
+    }
+    for key in FILTER_KEYS {
+        if let Some(value) = root.remove(key) {
+            notes.push(format!("{}: {} has to go into an app-specific config file under config/", key, scalar(&value)));
+        }
+    }
+    let others: Vec<String> = root.keys().filter_map(Value::as_str).filter(|key| !MATCH_FILE_KEYS.contains(key)).map(str::to_string).collect();
+    for key in others {
+        root.remove(key.as_str());
+        notes.push(format!("{} is a setting, it belongs in config/default.yml", key));
+    }
+    for var in root.get_mut("global_vars").and_then(Value::as_sequence_mut).into_iter().flatten() {
+        convert_var(var);
+    }
+    for entry in root.get_mut("matches").and_then(Value::as_sequence_mut).into_iter().flatten() {
+        if let Value::Mapping(m) = entry {
+            convert_match(m, &mut notes);
+        }
+    }
+    let converted = MatchFile::parse(&serde_yaml::to_string(&root).ok()?);
+    Some(Conversion { matches: converted.matches(), global_vars: converted.global_vars(), notes })
+}
+
+fn is_legacy(root: &Mapping) -> bool {
+    let keys = DROPPED_KEYS.iter().chain(&FILTER_KEYS).chain(&CONFIG_KEYS);
+    if keys.into_iter().any(|key| root.contains_key(*key)) {
+        return true;
+    }
+    let matches = root.get("matches").and_then(Value::as_sequence).into_iter().flatten();
+    let vars = matches.clone().filter_map(|m| m.get("vars")?.as_sequence()).flatten().chain(root.get("global_vars").and_then(Value::as_sequence).into_iter().flatten());
+    matches.clone().any(|m| m.get("passive_only").is_some()) || vars.into_iter().any(|var| var.get("type").and_then(Value::as_str) == Some("dummy"))
+}
+
+// `dummy` variables are called `echo` in espanso 2
+fn convert_var(var: &mut Value) {
+    if let Some(var_type) = var.get_mut("type").filter(|t| t.as_str() == Some("dummy")) {
+        *var_type = "echo".into();
+    }
+}
+
+// Passive matches expanded on the passive key and took arguments like `:greet/Ann/`,
+// placed at `$0$`, `$1$`, …. Espanso 2 has no passive mode: those with arguments become
+// regex matches with a capture group per argument, the rest plain ones.
+fn convert_match(m: &mut Mapping, notes: &mut Vec<String>) {
+    for var in m.get_mut("vars").and_then(Value::as_sequence_mut).into_iter().flatten() {
+        convert_var(var);
+    }
+    let Some(passive) = m.remove("passive_only") else { return };
+    if passive.as_bool() != Some(true) {
+        return;
+    }
+    let trigger = m.get("trigger").and_then(Value::as_str).unwrap_or_default().to_string();
+    let replace = m.get("replace").and_then(Value::as_str).unwrap_or_default().to_string();
+    let argument = Regex::new(r"\$(\d+)\$").expect("valid regex");
+    let count = argument.captures_iter(&replace).filter_map(|c| c[1].parse::<usize>().ok()).max().map_or(0, |last| last + 1);
+    if count == 0 {
+        notes.push(format!("{} was passive, it now expands as soon as it is typed", trigger));
+        return;
+    }
+    if trigger.is_empty() || m.contains_key("triggers") {
+        notes.push("A passive match with several triggers takes arguments; convert it by hand".to_string());
+        return;
+    }
+    let groups: String = (0..count).map(|n| format!("(?P<arg{}>[^/]*)/", n)).collect();
+    m.remove("trigger");
+    m.insert("regex".into(), format!("{}/{}", regex::escape(&trigger), groups).into());
+    m.insert("replace".into(), argument.replace_all(&replace, "{{arg$1}}").into_owned().into());
+    notes.push(format!("{} takes arguments and is a regex match now: it expands once {}/…/ is typed, without the passive key", trigger, trigger));
+}
+
+fn scalar(value: &Value) -> String {
+    serde_yaml::to_string(value).map(|text| text.trim().to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converted(contents: &str) -> Conversion {
+        convert(contents).expect("an espanso 1 file")
+    }
+
+    #[test]
+    fn passive_match_with_arguments_becomes_a_regex() {
+        let conversion = converted("matches:\n  - trigger: \":greet\"\n    replace: \"Hi $0$ and $1$\"\n    passive_only: true\n");
+        let greet = &conversion.matches[0];
+        assert_eq!(greet.regex.as_deref(), Some(":greet/(?P<arg0>[^/]*)/(?P<arg1>[^/]*)/"));
+        assert_eq!((greet.trigger.as_str(), greet.replace.as_str()), ("", "Hi {{arg0}} and {{arg1}}"));
+        assert!(conversion.notes[0].starts_with(":greet takes arguments"), "{:?}", conversion.notes);
+    }
+
+    #[test]
+    fn passive_match_without_arguments_stays_plain() {
+        let conversion = converted("matches:\n  - trigger: \":sig\"\n    replace: \"Best\"\n    passive_only: true\n");
+        assert_eq!(conversion.matches[0], Match { trigger: ":sig".to_string(), replace: "Best".to_string(), ..Default::default() });
+        assert_eq!(conversion.notes, [":sig was passive, it now expands as soon as it is typed"]);
+    }
+
+    #[test]
+    fn passive_match_with_aliases_is_left_for_hand_conversion() {
+        let conversion = converted("matches:\n  - triggers: [\":hi\", \":hey\"]\n    replace: \"Hi $0$\"\n    passive_only: true\n");
+        assert_eq!(conversion.matches[0].regex, None);
+        assert_eq!(conversion.notes, ["A passive match with several triggers takes arguments; convert it by hand"]);
+    }
+
+    #[test]
+    fn dummy_vars_become_echo() {
+        let contents = concat!(
+            "global_vars:\n",
+            "  - name: company\n",
+            "    type: dummy\n",
+            "    params:\n",
+            "      echo: ACME\n",
+            "matches:\n",
+            "  - trigger: \":co\"\n",
+            "    replace: \"{{name}} at {{company}}\"\n",
+            "    vars:\n",
+            "      - name: name\n",
+            "        type: dummy\n",
+            "        params:\n",
+            "          echo: Ann\n",
+        );
+        let conversion = converted(contents);
+        assert_eq!(conversion.matches[0].vars[0].var_type, "echo");
+        assert_eq!(conversion.global_vars.len(), 1);
+        assert_eq!((conversion.global_vars[0].name.as_str(), conversion.global_vars[0].var_type.as_str()), ("company", "echo"));
+        assert!(conversion.notes.is_empty(), "{:?}", conversion.notes);
+    }
+
+    #[test]
+    fn filter_and_config_keys_are_noted() {
+        let contents = "name: slack\nparent: default\nfilter_title: Slack\nexclude_default_entries: true\nbackend: Clipboard\nmatches:\n  - trigger: \":ok\"\n    replace: \"OK\"\n";
+        let conversion = converted(contents);
+        assert_eq!(
+            conversion.notes,
+            [
+                "filter_title: Slack has to go into an app-specific config file under config/",
+                "exclude_default_entries: true has to go into an app-specific config file under config/",
+                "backend is a setting, it belongs in config/default.yml",
+            ]
+        );
+        assert_eq!(conversion.matches.len(), 1);
+    }
+
+    #[test]
+    fn espanso_2_files_are_left_alone() {
+        assert_eq!(convert("matches:\n  - trigger: \":ok\"\n    replace: \"OK\"\n"), None);
+        assert_eq!(convert("matches: [unclosed"), None);
+    }
+}
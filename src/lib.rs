@@ -0,0 +1,30 @@
+//! Reading, editing and writing espanso match files, as used by Espanso Helper.
+//!
+//! [`yaml_io::MatchFile`] keeps everything it doesn't edit as it was: the header
+//! comment, other YAML documents, and the comments and formatting of every match
+//! that didn't change. Only changed matches are written in a new formatting.
+//!
+//! ```
+//! use rust_mit_cursor::model::Match;
+//! use rust_mit_cursor::yaml_io::MatchFile;
+//!
+//! let file = MatchFile::parse("# Greetings\nmatches:\n  # Short\n  - trigger: \":hi\"\n    replace: \"Hello\"\n");
+//! let mut matches = file.matches();
+//! matches.push(Match { trigger: ":bye".to_string(), replace: "Goodbye".to_string(), ..Default::default() });
+//! let output = file.render(&matches).unwrap();
+//! assert!(output.starts_with("# Greetings\n"));
+//! assert!(output.contains("  # Short\n  - trigger: \":hi\"\n    replace: \"Hello\"\n"));
+//! assert_eq!(MatchFile::parse(&output).matches(), matches);
+//! ```
+//!
+//! `model` and `yaml_io` follow semantic versioning: until 1.0 breaking changes only
+//! come with a new minor version. The other modules are shared with the app and may
+//! change at any time.
+
+pub mod model;
+pub mod yaml_io;
+
+#[doc(hidden)]
+pub mod text;
+#[doc(hidden)]
+pub mod vars;
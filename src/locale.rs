@@ -0,0 +1,77 @@
+use chrono::{DateTime, Datelike, Local};
+use std::fmt::Write;
+
+// Languages the date preview knows month and weekday names for; anything else is
+// shown in English, like espanso does for locales it lacks
+const NAMES: &[(&str, [&str; 12], [&str; 7])] = &[
+    ("en", ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+        ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"]),
+    ("de", ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"],
+        ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"]),
+    ("fr", ["janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"],
+        ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"]),
+    ("es", ["enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre", "noviembre", "diciembre"],
+        ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"]),
+    ("it", ["gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre", "novembre", "dicembre"],
+        ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"]),
+    ("nl", ["januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober", "november", "december"],
+        ["maandag", "dinsdag", "woensdag", "donderdag", "vrijdag", "zaterdag", "zondag"]),
+    ("pt", ["janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro", "outubro", "novembro", "dezembro"],
+        ["segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo"]),
+    ("sv", ["januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti", "september", "oktober", "november", "december"],
+        ["måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag"]),
+    ("pl", ["styczeń", "luty", "marzec", "kwiecień", "maj", "czerwiec", "lipiec", "sierpień", "wrzesień", "październik", "listopad", "grudzień"],
+        ["poniedziałek", "wtorek", "środa", "czwartek", "piątek", "sobota", "niedziela"]),
+];
+
+// Offered in the settings; `de-DE` and `de-AT` name the same months
+pub const KNOWN: &[&str] = &["en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "it-IT", "nl-NL", "pt-BR", "sv-SE", "pl-PL"];
+
+// The user's locale from the environment as `de-DE`, falling back to `en-US`
+pub fn system() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .map(|value| value.split(['.', '@']).next().unwrap_or_default().replace('_', "-"))
+        .filter(|locale| !locale.is_empty())
+        .unwrap_or_else(|| "en-US".to_string())
+}
+
+// `format` in chrono's syntax, which espanso uses too, with month and weekday names in
+// the language of `locale`. None when the format is invalid.
+pub fn format_date(date: DateTime<Local>, format: &str, locale: &str) -> Option<String> {
+    let language = locale.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    let (_, months, weekdays) = NAMES.iter().find(|(code, _, _)| *code == language).unwrap_or(&NAMES[0]);
+    let month = months[date.month0() as usize];
+    let weekday = weekdays[date.weekday().num_days_from_monday() as usize];
+    let short = |name: &str| name.chars().take(3).collect::<String>();
+    // Names are swapped in before chrono sees the format, escaped so a `%` in them stays literal
+    let mut localized = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        let name = match chars.next() {
+            Some('B') => month.to_string(),
+            Some('b') | Some('h') => short(month),
+            Some('A') => weekday.to_string(),
+            Some('a') => short(weekday),
+            Some(other) => {
+                localized.push('%');
+                localized.push(other);
+                continue;
+            }
+            None => {
+                localized.push('%');
+                continue;
+            }
+        };
+        localized.push_str(&name.replace('%', "%%"));
+    }
+    let mut output = String::new();
+    write!(output, "{}", date.format(&localized)).ok()?;
+    Some(output)
+}
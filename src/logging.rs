@@ -0,0 +1,96 @@
+use chrono::Local;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::app_config_dir;
+
+const LOG_FILE: &str = "espanso-helper.log";
+
+// The log starts over in a new file beyond this size; two older files are kept
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const KEPT_LOGS: usize = 2;
+
+// Writes log records to a file in the app config dir, since a release build on
+// Windows has no console and output to stdout is lost
+struct FileLogger {
+    file: Mutex<Option<(File, u64)>>,
+}
+
+pub fn log_path() -> PathBuf {
+    app_config_dir().join("logs").join(LOG_FILE)
+}
+
+// `espanso-helper.log` becomes `espanso-helper.1.log` and so on, dropping the oldest
+fn rotate() {
+    let path = log_path();
+    let numbered = |n: usize| path.with_file_name(format!("espanso-helper.{}.log", n));
+    let _ = fs::remove_file(numbered(KEPT_LOGS));
+    for n in (1..KEPT_LOGS).rev() {
+        let _ = fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = fs::rename(&path, numbered(1));
+}
+
+fn open() -> Option<(File, u64)> {
+    let path = log_path();
+    fs::create_dir_all(path.parent()?).ok()?;
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        rotate();
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path).ok()?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or_default();
+    Some((file, size))
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // Dependencies only get to report problems, egui and winit are chatty otherwise
+        metadata.level() <= log::Level::Warn || metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("{} {:<5} {}: {}\n", Local::now().format("%Y-%m-%d %H:%M:%S"), record.level(), record.target(), record.args());
+        let Ok(mut guard) = self.file.lock() else { return };
+        if guard.as_ref().is_some_and(|(_, size)| *size >= MAX_LOG_BYTES) {
+            *guard = None;
+            rotate();
+        }
+        if guard.is_none() {
+            *guard = open();
+        }
+        if let Some((file, size)) = guard.as_mut() {
+            if file.write_all(line.as_bytes()).is_ok() {
+                *size += line.len() as u64;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut guard) = self.file.lock() {
+            if let Some((file, _)) = guard.as_mut() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+// Installs the file logger and records panics, which would otherwise vanish along
+// with the missing console
+pub fn init() {
+    let logger = FileLogger { file: Mutex::new(open()) };
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return;
+    }
+    log::set_max_level(log::LevelFilter::Info);
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("{}", info);
+        default_hook(info);
+    }));
+    log::info!("Espanso Helper {} started", env!("CARGO_PKG_VERSION"));
+}
@@ -1,294 +1,5995 @@
+// Release builds on Windows start without a console window; diagnostics go to the log file
+#![cfg_attr(all(target_os = "windows", not(debug_assertions)), windows_subsystem = "windows")]
+
 use eframe::egui;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
-use serde::{Deserialize, Serialize};
-use std::rc::Rc;
-use std::cell::RefCell;
 use std::process::Command;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use rust_mit_cursor::model::{Match, Var};
+use rust_mit_cursor::{text, vars, yaml_io};
+use tasks::{RunningTask, Task, TaskControl};
+
+mod aliases;
+mod assets;
+mod autohotkey;
+mod backups;
+mod beeftext;
+mod clipboard;
+mod compact;
+mod diagnostics;
+mod docs;
+mod espanso;
+mod espanso_config;
+mod filter;
+mod forms;
+mod generated;
+mod global_vars;
+mod health;
+mod highlight;
+mod import;
+mod legacy;
+mod locale;
+mod logging;
+mod merge;
+mod metadata;
+mod packages;
+mod per_file;
+mod platforms;
+mod presets;
+mod quick_add;
+mod regex_builder;
+mod remote;
+mod sandbox;
+mod search;
+mod risk;
+mod settings;
+mod shortcuts;
+mod table;
+mod tasks;
+mod templates;
+mod tour;
+#[cfg(all(feature = "tray", target_os = "linux"))]
+mod tray;
+#[cfg(test)]
+mod ui_snapshots;
+mod update_check;
+mod usage;
+mod validation;
+mod wrap;
+
+const TRIM_RULES_FILE: &str = "whitespace.yml";
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+
+// Width of the drag handle between two columns of the match table
+const COLUMN_HANDLE: f32 = 6.0;
+
+// How often the background validator re-reads the match files
+const VALIDATION_INTERVAL: Duration = Duration::from_secs(10);
+
+// How often the match folder is checked for changes made by other programs
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+// How often the clipboard is read while collecting copied text
+const CLIPBOARD_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Espanso,
+    Standalone,
+    Beeftext,
+    AutoHotkey,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    const ALL: [ExportFormat; 6] = [
+        ExportFormat::Espanso,
+        ExportFormat::Standalone,
+        ExportFormat::Beeftext,
+        ExportFormat::AutoHotkey,
+        ExportFormat::Csv,
+        ExportFormat::Json,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Espanso => "Espanso YAML",
+            ExportFormat::Standalone => "Standalone YAML",
+            ExportFormat::Beeftext => "Beeftext JSON",
+            ExportFormat::AutoHotkey => "AutoHotkey",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Espanso | ExportFormat::Standalone => ".yml",
+            ExportFormat::Beeftext | ExportFormat::Json => ".json",
+            ExportFormat::AutoHotkey => ".ahk",
+            ExportFormat::Csv => ".csv",
+        }
+    }
+
+    // Whether `m` ends up in the exported file; the other formats skip what isn't plain text
+    fn writes(self, m: &Match) -> bool {
+        match self {
+            ExportFormat::Espanso | ExportFormat::Standalone => true,
+            ExportFormat::Beeftext | ExportFormat::AutoHotkey => m.plain_text_issue().is_none(),
+            ExportFormat::Csv | ExportFormat::Json => m.regex.is_none() && m.content_key().is_none(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MatchColumn {
+    Trigger,
+    Replacement,
+    Label,
+    Flags,
+    File,
+}
+
+impl MatchColumn {
+    const ALL: [MatchColumn; 5] = [MatchColumn::Trigger, MatchColumn::Replacement, MatchColumn::Label, MatchColumn::Flags, MatchColumn::File];
+
+    fn label(self) -> &'static str {
+        match self {
+            MatchColumn::Trigger => "Trigger",
+            MatchColumn::Replacement => "Replacement",
+            MatchColumn::Label => "Label",
+            MatchColumn::Flags => "Flags",
+            MatchColumn::File => "File",
+        }
+    }
+}
+
+// Contents of a match file as read from disk
+#[derive(Debug, Clone)]
+enum LoadedFile {
+    Full(Box<yaml_io::MatchFile>, Vec<Match>),
+    Compact(Arc<compact::CompactMatches>),
+}
+
+// File operation waiting for a name or a confirmation
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileAction {
+    Create,
+    Rename,
+    Duplicate,
+    Delete,
+}
+
+// Other matches whose replacement mentions a trigger that was just renamed
+#[derive(Debug, Clone)]
+struct RenameReferences {
+    old: String,
+    new: String,
+    indices: Vec<usize>,
+}
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct Match {
+// A deleted match kept for the rest of the session so it can be restored
+#[derive(Debug, Clone)]
+struct TrashedMatch {
+    file: String,
+    m: Match,
+    meta: Option<metadata::MatchMeta>,
+    deleted: String,
+}
+
+// A row of the match list being edited in place
+#[derive(Debug, Clone)]
+struct InlineEdit {
+    file: String,
+    index: usize,
+    // The match as it was, to notice it changed in the meantime
+    original: Match,
+    // The trigger, or the pattern of a regex match
     trigger: String,
-    replace: String,
+    // None when the replacement doesn't fit on one line and stays read-only
+    replace: Option<String>,
+    focus: bool,
+}
+
+// What a click on a match in the list or the details asked for, applied once the panel
+// is drawn since its widgets are laid out from the matches themselves
+#[derive(Debug, Clone, PartialEq)]
+enum MatchAction {
+    Click(usize, egui::Modifiers),
+    Drag(usize),
+    Edit(usize),
+    EditInline(usize),
+    SaveInline,
+    CancelInline,
+    Try(usize),
+    Transfer(Vec<usize>, String, bool),
+    Delete(usize),
+    DetachAlias(usize, String),
+    // The match at the second index becomes an alias of the first
+    MakeAlias(usize, usize),
+    ApplyFix(usize, espanso_config::Fix),
+    SaveMetadata,
+}
+
+// Where the user left off in a file, restored when switching back to it
+#[derive(Debug, Clone, Default)]
+struct FileView {
+    scroll_offset: f32,
+    selected_index: Option<usize>,
+    selection: BTreeSet<usize>,
+    // Replacement characters of `selection`, for the statistics while another file is open
+    selection_chars: usize,
+}
+
+// An open file with its own filter, editor and selection. The active tab lives in the
+// helper's fields; the others are parked here until they are switched to.
+#[derive(Debug, Clone, Default)]
+struct EditTab {
+    file: String,
+    document: yaml_io::MatchFile,
+    matches: Vec<Match>,
+    compact: Option<Arc<compact::CompactMatches>>,
+    loaded_mtime: Option<SystemTime>,
+    filter_text: String,
+    view: FileView,
+    editing_index: Option<usize>,
+    regex_mode: bool,
+    new_trigger: String,
+    new_replacement: String,
+    new_aliases: String,
+    new_regex: String,
+    new_word: bool,
+    new_label: String,
+    new_propagate_case: bool,
+    new_force_clipboard: bool,
+    new_vars: Vec<Var>,
+    new_form: String,
+    new_form_fields: Vec<forms::FormField>,
+    raw_yaml: Option<RawDraft>,
+    // The editor held changes that weren't added or updated yet
+    unsaved: bool,
 }
 
+// What saving the selected file would change, held back until the user accepts it
 #[derive(Debug, Clone)]
+struct SaveReview {
+    file: String,
+    lines: Vec<(text::DiffOp, String)>,
+}
+
+// The selected file as YAML source, edited by hand. `base` is what it looked like when
+// opened, to tell whether anything changed.
+#[derive(Debug, Clone, Default)]
+struct RawDraft {
+    text: String,
+    base: String,
+}
+
+#[derive(Debug)]
 struct EspansoHelper {
     config_dir: PathBuf,
     selected_file: String,
     files: Vec<String>,
     new_trigger: String,
     new_replacement: String,
+    new_aliases: String,
+    new_regex: String,
+    new_word: bool,
+    new_label: String,
+    new_propagate_case: bool,
+    new_force_clipboard: bool,
+    new_vars: Vec<Var>,
+    new_form: String,
+    new_form_fields: Vec<forms::FormField>,
+    regex_mode: bool,
+    regex_builder: regex_builder::RegexBuilder,
     matches: Vec<Match>,
-    yaml_indent: String,
+    document: yaml_io::MatchFile,
     filter_text: String,
     editing_index: Option<usize>,
+    rename_references: Option<RenameReferences>,
+    conflict: Option<merge::ConflictSource>,
+    file_action: Option<FileAction>,
+    tabs: Vec<EditTab>,
+    active_tab: usize,
+    file_action_name: String,
+    file_preset: presets::FilePreset,
+    // While the tour runs, `matches` holds sample data that is never saved
+    tour: Option<tour::Tour>,
+    merge_session: Option<merge::MergeSession>,
+    selected_index: Option<usize>,
+    // Matches picked with Ctrl/Shift-click, in addition to `selected_index`
+    selection: BTreeSet<usize>,
+    file_views: HashMap<String, FileView>,
+    platform_drafts: Option<(usize, Vec<(platforms::Os, String)>)>,
+    list_scroll_offset: f32,
+    restore_scroll_offset: Option<f32>,
+    templates: templates::Templates,
+    metadata: metadata::MetadataStore,
+    trim_rules: per_file::PerFile<text::TrimOptions>,
+    wrap_modes: wrap::WrapModes,
+    recording: bool,
+    safe_mode: bool,
+    show_audit: bool,
+    // Search over every match file, packages included
+    show_search: bool,
+    search_query: String,
+    focus_search: bool,
+    search_index: Option<Vec<search::Entry>>,
+    pending_search_index: Option<Task<Vec<search::Entry>>>,
+    // Dense grid of triggers only instead of the detailed list
+    trigger_grid: bool,
+    // Matches under a header per tag instead of the table, while the file has tags
+    group_by_tag: bool,
+    // Share of the table width per column of the match list
+    match_columns: [f32; 5],
+    // Column the match list is sorted by, and whether descending
+    match_sort: Option<(MatchColumn, bool)>,
+    dictionary: Option<Arc<risk::Dictionary>>,
+    risk_findings: Vec<risk::RiskFinding>,
+    asset_report: Option<assets::AssetReport>,
+    pending_asset_scan: Option<Task<assets::AssetReport>>,
+    installed_packages: Option<Vec<packages::InstalledPackage>>,
+    pending_package_diff: Option<Task<packages::PackageDiff>>,
+    package_diff: Option<packages::PackageDiff>,
+    pending_risk_scan: Option<Task<(Arc<risk::Dictionary>, Vec<risk::RiskFinding>)>>,
+    problems: Arc<validation::Report>,
+    pending_validation: Option<Task<(Arc<risk::Dictionary>, validation::Report)>>,
+    // `None` makes the next frame validate again, e.g. after a save
+    last_validation: Option<Instant>,
+    pending_load: Option<Task<LoadedFile>>,
+    // Modification time of the selected file when it was last read or written
+    loaded_mtime: Option<SystemTime>,
+    // Large generated files are shown read-only from a compact store instead of `matches`
+    compact: Option<Arc<compact::CompactMatches>>,
+    show_import: bool,
+    import_path: String,
+    import_preview: Option<import::ImportPreview>,
+    import_url: String,
+    pending_fetch: Option<Task<String>>,
+    pending_import: Option<Task<import::ImportPreview>>,
+    show_export: bool,
+    show_shortcuts: bool,
+    show_sandbox: bool,
+    sandbox_input: String,
+    shortcuts: Vec<shortcuts::Shortcut>,
+    export_file_name: String,
+    export_format: ExportFormat,
+    export_all_files: bool,
+    export_selected_only: bool,
+    export_remove_sources: bool,
+    // The selection row asks before deleting several matches at once
+    confirm_bulk_delete: bool,
+    // Prefix and suffix for wrapping the selected replacements, while that form is open
+    bulk_wrap: Option<(String, String)>,
+    // Match waiting for the delete confirmation, with its trigger to notice a reload
+    pending_delete: Option<(usize, String)>,
+    trash: Vec<TrashedMatch>,
+    show_trash: bool,
+    // Matches being dragged onto a file in the sidebar
+    dragged_matches: Option<Vec<usize>>,
+    status: String,
+    session_log: Vec<String>,
+    pending_update_check: Option<Task<update_check::Release>>,
+    pending_update_download: Option<Task<PathBuf>>,
+    available_update: Option<update_check::Release>,
+    daemon_state: espanso::DaemonState,
+    espanso_config: espanso_config::GlobalConfig,
+    show_backups: bool,
+    // The backup being looked at and its line diff against the file on disk
+    backup_preview: Option<(backups::Backup, Vec<(text::DiffOp, String)>)>,
+    raw_yaml: Option<RawDraft>,
+    pending_review: Option<SaveReview>,
+    settings: settings::Settings,
+    // As last written, to notice changes that need saving
+    saved_settings: settings::Settings,
+    // Changes to the selected file not written yet because autosave is off
+    unsaved_file: bool,
+    applied_theme: Option<settings::Theme>,
+    window_geometry: Option<settings::WindowGeometry>,
+    error_dialog: Option<String>,
+    // espanso's own match folder, used unless the settings name another one
+    detected_config_dir: PathBuf,
+    // Folder shown in the folder picker and the path typed into it
+    folder_picker: Option<(PathBuf, String)>,
+    show_lint: bool,
+    // Move the keyboard focus to the new match form on the next frame
+    focus_new_match: bool,
+    focus_filter: bool,
+    inline_edit: Option<InlineEdit>,
+    // The selection moved with the keyboard and should come into view
+    scroll_to_selected: bool,
+    applied_zoom: Option<f32>,
+    // Draft of the replacement while it's edited on its own, filling the window
+    focus_editor: Option<String>,
+    // Whether the window was maximized before the focused editor maximized it
+    restore_maximized: Option<bool>,
+    show_global_vars: bool,
+    global_var_definitions: Vec<global_vars::Definition>,
+    // Global variable being renamed and the new name typed so far
+    global_var_rename: Option<(String, String)>,
+    global_var_plan: Option<global_vars::Rename>,
+    health: health::HealthLog,
+    // The weekly summary while it is shown
+    health_summary: Option<health::Summary>,
+    usage: usage::Usage,
+    show_usage: bool,
+    clipboard: clipboard::Watcher,
+    // None while the clipboard isn't watched
+    last_clipboard_poll: Option<Instant>,
+    last_daemon_poll: Option<Instant>,
+    last_watch: Option<Instant>,
+    // A save was held back because the file changed on disk since it was loaded
+    external_change: bool,
+    pending_daemon_status: Option<Task<espanso::DaemonState>>,
+    pending_daemon_restart: Option<Task<()>>,
 }
 
 impl Default for EspansoHelper {
     fn default() -> Self {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_default()
-            .join("espanso")
-            .join("match");
+        Self::new(false)
+    }
+}
+
+impl EspansoHelper {
+    // In safe mode nothing is loaded or polled automatically and nothing gets written
+    fn new(safe_mode: bool) -> Self {
+        let settings = settings::Settings::load();
+        let detected_config_dir = default_config_dir();
+        let config_dir = settings.config_dir.clone().unwrap_or_else(|| detected_config_dir.clone());
         let files = list_yaml_files(&config_dir);
         let selected_file = files.first().cloned().unwrap_or_default();
+        let espanso_config = espanso_config::GlobalConfig::load(config_dir.parent().unwrap_or(&config_dir));
         let mut helper = Self {
             config_dir,
             selected_file,
             files,
             new_trigger: String::new(),
             new_replacement: String::new(),
+            new_aliases: String::new(),
+            new_regex: String::new(),
+            new_word: false,
+            new_label: String::new(),
+            new_propagate_case: false,
+            new_force_clipboard: false,
+            new_vars: Vec::new(),
+            new_form: String::new(),
+            new_form_fields: Vec::new(),
+            regex_mode: false,
+            regex_builder: regex_builder::RegexBuilder::default(),
             matches: Vec::new(),
-            yaml_indent: "  ".to_string(),
+            document: yaml_io::MatchFile::default(),
             filter_text: String::new(),
             editing_index: None,
+            rename_references: None,
+            conflict: None,
+            file_action: None,
+            // The active tab is described by the fields above
+            tabs: vec![EditTab::default()],
+            active_tab: 0,
+            file_action_name: String::new(),
+            file_preset: presets::FilePreset::default(),
+            tour: None,
+            merge_session: None,
+            selected_index: None,
+            selection: BTreeSet::new(),
+            file_views: HashMap::new(),
+            platform_drafts: None,
+            list_scroll_offset: 0.0,
+            restore_scroll_offset: None,
+            templates: templates::Templates::load(templates::TEMPLATES_FILE),
+            metadata: metadata::MetadataStore::load(),
+            trim_rules: per_file::PerFile::load(TRIM_RULES_FILE),
+            wrap_modes: wrap::WrapModes::load(),
+            recording: false,
+            safe_mode,
+            show_audit: false,
+            show_search: false,
+            search_query: String::new(),
+            focus_search: false,
+            search_index: None,
+            pending_search_index: None,
+            trigger_grid: false,
+            group_by_tag: false,
+            match_columns: [0.25, 0.35, 0.15, 0.13, 0.12],
+            match_sort: None,
+            dictionary: None,
+            risk_findings: Vec::new(),
+            asset_report: None,
+            pending_asset_scan: None,
+            installed_packages: None,
+            pending_package_diff: None,
+            package_diff: None,
+            pending_risk_scan: None,
+            problems: Arc::default(),
+            pending_validation: None,
+            last_validation: None,
+            pending_load: None,
+            loaded_mtime: None,
+            compact: None,
+            show_import: false,
+            import_path: String::new(),
+            import_preview: None,
+            import_url: String::new(),
+            pending_fetch: None,
+            pending_import: None,
+            show_export: false,
+            show_shortcuts: false,
+            show_sandbox: false,
+            sandbox_input: String::new(),
+            shortcuts: shortcuts::load(),
+            export_file_name: String::new(),
+            export_format: ExportFormat::Espanso,
+            export_all_files: false,
+            export_selected_only: false,
+            export_remove_sources: false,
+            confirm_bulk_delete: false,
+            bulk_wrap: None,
+            pending_delete: None,
+            trash: Vec::new(),
+            show_trash: false,
+            dragged_matches: None,
+            status: String::new(),
+            session_log: Vec::new(),
+            pending_update_check: None,
+            pending_update_download: None,
+            available_update: None,
+            daemon_state: espanso::DaemonState::Unknown,
+            espanso_config,
+            show_backups: false,
+            backup_preview: None,
+            raw_yaml: None,
+            pending_review: None,
+            detected_config_dir,
+            folder_picker: None,
+            show_lint: false,
+            focus_new_match: false,
+            focus_filter: false,
+            inline_edit: None,
+            scroll_to_selected: false,
+            applied_zoom: None,
+            focus_editor: None,
+            restore_maximized: None,
+            show_global_vars: false,
+            global_var_definitions: Vec::new(),
+            global_var_rename: None,
+            global_var_plan: None,
+            health: health::HealthLog::load(),
+            health_summary: None,
+            usage: usage::Usage::load(),
+            show_usage: false,
+            clipboard: clipboard::Watcher::default(),
+            last_clipboard_poll: None,
+            saved_settings: settings.clone(),
+            settings,
+            unsaved_file: false,
+            applied_theme: None,
+            window_geometry: None,
+            error_dialog: None,
+            last_daemon_poll: None,
+            last_watch: None,
+            external_change: false,
+            pending_daemon_status: None,
+            pending_daemon_restart: None,
         };
-        helper.load_matches();
+        if safe_mode {
+            helper.settings.daemon_poll_interval_secs = 0;
+            helper.status = "Safe mode: matches are not loaded automatically and editing is disabled".to_string();
+            return helper;
+        }
+        // The first frame shows a placeholder list while the file is read in the background
+        helper.start_loading();
+        helper.reset_editor();
+        if tour::is_first_run() {
+            helper.start_tour();
+        }
+        if helper.settings.check_updates_on_startup {
+            helper.check_for_updates();
+        }
         helper
     }
 }
 
 impl EspansoHelper {
-    fn refresh(&mut self) {
-        // Clear all input fields
-        self.new_trigger.clear();
-        self.new_replacement.clear();
-        self.filter_text.clear();
-        self.editing_index = None;
+    // Destructive actions bail out early in safe mode
+    fn allow_destructive(&mut self) -> bool {
+        if self.safe_mode {
+            self.status = "Safe mode: changes are disabled, restart without --safe-mode to edit".to_string();
+        }
+        !self.safe_mode
+    }
 
+    // Picks up changes made outside the helper; the filter, editor and selection
+    // survive unless the file they belong to is gone
+    fn refresh(&mut self) {
         // Reload the directory contents
         self.files = self.list_yaml_files();
+        let files = self.files.clone();
+        self.file_views.retain(|name, _| files.contains(name));
+        self.espanso_config = espanso_config::GlobalConfig::load(&self.espanso_dir());
+        self.last_validation = None;
 
         // If the currently selected file no longer exists, select the first available file
         if !self.files.contains(&self.selected_file) {
+            if self.hold_unsaved_changes("is gone") {
+                return;
+            }
+            self.status = format!("{} is gone", self.selected_file);
             self.selected_file = self.files.first().cloned().unwrap_or_default();
+            self.load_matches();
+            self.reset_editor();
+            return;
+        }
+        if self.pending_load.is_some() || self.tour.is_some() {
+            return;
         }
+        if self.current_mtime() != self.loaded_mtime && self.hold_unsaved_changes("changed on disk") {
+            return;
+        }
+        self.status = if self.reload_if_changed() {
+            format!("Reloaded {}, it changed on disk", self.selected_file)
+        } else {
+            "Nothing changed on disk".to_string()
+        };
+    }
+
+    // Switches to another match folder, or back to espanso's own with None
+    fn set_config_dir(&mut self, dir: Option<PathBuf>) {
+        if !self.flush_unsaved() {
+            return;
+        }
+        let path = dir.clone().unwrap_or_else(|| self.detected_config_dir.clone());
+        if !path.is_dir() {
+            self.status = format!("{} is not a folder", display_path(&path));
+            return;
+        }
+        self.settings.config_dir = dir;
+        self.config_dir = path;
+        // Other tabs show files of the previous folder
+        self.tabs = vec![EditTab::default()];
+        self.active_tab = 0;
+        self.file_views.clear();
+        self.files = self.list_yaml_files();
+        self.selected_file = self.files.first().cloned().unwrap_or_default();
+        self.espanso_config = espanso_config::GlobalConfig::load(&self.espanso_dir());
+        self.last_validation = None;
+        self.load_matches();
+        self.reset_editor();
+        self.status = format!("Showing the matches in {}", display_path(&self.config_dir));
+    }
 
-        // Reload matches from the selected file
+    // Reloads matches from the selected file if it was written since it was read. The
+    // selection and the editor follow their matches to where they are in the new list;
+    // an editor whose match is gone is emptied, so Update can't hit another one.
+    fn reload_if_changed(&mut self) -> bool {
+        if self.current_mtime() == self.loaded_mtime {
+            return false;
+        }
+        let before = std::mem::take(&mut self.matches);
+        let (selected_index, selection, editing_index) = (self.selected_index, self.selection.clone(), self.editing_index);
         self.load_matches();
+        let follow = |index: usize| before.get(index).and_then(|m| relocate(m, &self.matches, index));
+        self.selected_index = selected_index.and_then(follow);
+        self.selection = selection.into_iter().filter_map(follow).collect();
+        match editing_index.map(|index| (index, follow(index))) {
+            Some((_, Some(index))) => self.editing_index = Some(index),
+            Some((index, None)) => {
+                self.reset_editor();
+                let trigger = before.get(index).map(Match::display_trigger).unwrap_or_default();
+                self.status = format!("{} is no longer in {}, the editor was emptied", trigger, self.selected_file);
+            }
+            None => {}
+        }
+        true
+    }
+
+    fn current_mtime(&self) -> Option<SystemTime> {
+        fs::metadata(self.config_dir.join(&self.selected_file)).and_then(|m| m.modified()).ok()
+    }
+
+    // Empties the editor and presets it from the selected file's template
+    fn reset_editor(&mut self) {
+        self.new_trigger.clear();
+        self.new_replacement.clear();
+        self.new_aliases.clear();
+        self.new_regex.clear();
+        self.new_word = self.templates.for_file(&self.selected_file).word;
+        self.new_label.clear();
+        self.new_propagate_case = false;
+        self.new_force_clipboard = false;
+        self.new_vars.clear();
+        self.new_form.clear();
+        self.new_form_fields.clear();
+        self.editing_index = None;
     }
 
     fn list_yaml_files(&self) -> Vec<String> {
-        fs::read_dir(&self.config_dir)
-            .into_iter()
-            .flatten()
-            .filter_map(|entry| {
-                let path = entry.ok()?.path();
-                if path.extension()?.to_str()? == "yml" {
-                    Some(path.file_name()?.to_str()?.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect()
+        list_yaml_files(&self.config_dir)
+    }
+
+    // Files offered in the sidebar and the selector; the selected one always shows
+    fn shown_files(&self) -> Vec<String> {
+        let hide = self.settings.hide_included_only;
+        self.files.iter().filter(|file| !hide || !is_included_only(file) || **file == self.selected_file).cloned().collect()
     }
 
     fn load_matches(&mut self) {
-        let file_path = self.config_dir.join(&self.selected_file);
-        self.matches = if let Ok(contents) = fs::read_to_string(file_path) {
-            if let Ok(data) = serde_yaml::from_str::<serde_yaml::Value>(&contents) {
-                if let Some(matches) = data.get("matches").and_then(|m| m.as_sequence()) {
-                    matches.iter().filter_map(|m| {
-                        let trigger = m.get("trigger")?.as_str()?.to_string();
-                        let replace = m.get("replace")?.as_str()?.to_string();
-                        Some(Match { trigger, replace })
-                    }).collect()
-                } else {
-                    Vec::new()
+        self.pending_load = None;
+        let loaded = read_match_file(&self.config_dir.join(&self.selected_file), true);
+        self.apply_loaded(loaded);
+    }
+
+    // Loads a large file in full so it can be edited, at the cost of memory
+    fn load_for_editing(&mut self) {
+        let loaded = read_match_file(&self.config_dir.join(&self.selected_file), false);
+        self.apply_loaded(loaded);
+    }
+
+    fn apply_loaded(&mut self, loaded: LoadedFile) {
+        self.tour = None;
+        match loaded {
+            LoadedFile::Full(document, matches) => {
+                if let Some(error) = document.error() {
+                    log::warn!("{} could not be read in full: {}", self.selected_file, error);
+                }
+                self.document = *document;
+                self.matches = matches;
+                self.compact = None;
+                if self.metadata.reconcile(&self.selected_file, &self.matches) {
+                    self.save_metadata();
                 }
-            } else {
-                Vec::new()
             }
-        } else {
-            Vec::new()
+            LoadedFile::Compact(compact) => {
+                self.document = yaml_io::MatchFile::default();
+                self.matches = Vec::new();
+                self.compact = Some(compact);
+            }
+        }
+        self.selected_index = None;
+        self.selection.clear();
+        self.platform_drafts = None;
+        self.rename_references = None;
+        self.merge_session = None;
+        self.pending_review = None;
+        self.unsaved_file = false;
+        self.loaded_mtime = self.current_mtime();
+        self.external_change = false;
+        self.conflict = merge::detect(&self.config_dir.join(&self.selected_file));
+    }
+
+    fn start_tour(&mut self) {
+        self.pending_load = None;
+        self.document = yaml_io::MatchFile::default();
+        self.matches = tour::sample_matches();
+        self.compact = None;
+        self.selected_index = None;
+        self.selection.clear();
+        self.reset_editor();
+        self.tour = Some(tour::Tour::default());
+        self.status = "Tour: you are looking at sample matches".to_string();
+    }
+
+    fn end_tour(&mut self) {
+        tour::mark_done();
+        self.tour = None;
+        self.load_matches();
+        self.reset_editor();
+        self.status = "Tour finished, your own matches are loaded again".to_string();
+    }
+
+    fn open_merge(&mut self) {
+        let Some(source) = self.conflict.clone() else { return };
+        let path = self.config_dir.join(&self.selected_file);
+        match merge::MergeSession::open(&path, source, &self.document) {
+            Ok(session) => self.merge_session = Some(session),
+            Err(error) => self.status = format!("Opening the conflict failed: {}", error),
+        }
+    }
+
+    fn apply_merge(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let Some(session) = self.merge_session.take() else { return };
+        self.document = session.document.clone();
+        self.document.mark_modified();
+        self.matches = session.resolve();
+        self.selected_index = None;
+        self.selection.clear();
+        self.editing_index = None;
+        // Written right away, past autosave and review: the conflict copy may only go once
+        // the merged version is on disk
+        self.unsaved_file = true;
+        let file = self.selected_file.clone();
+        self.track_usage(|usage| usage.record_edit(&file));
+        if let Some(reason) = self.save_blocker() {
+            self.status = reason;
+            return;
+        }
+        if !self.commit_matches() {
+            return;
+        }
+        if let merge::ConflictSource::SyncFile(copy) = &session.source {
+            if let Err(error) = fs::remove_file(copy) {
+                self.status = format!("Merged, but removing {} failed: {}", display_path(copy), error);
+                return;
+            }
+        }
+        self.conflict = merge::detect(&self.config_dir.join(&self.selected_file));
+        self.status = format!("Merged {} ({})", self.selected_file, session.source.describe());
+    }
+
+    fn start_loading(&mut self) {
+        let path = self.config_dir.join(&self.selected_file);
+        self.pending_load = Some(Task::spawn(format!("Loading {}", self.selected_file), move |_| {
+            Ok(read_match_file(&path, true))
+        }));
+    }
+
+    fn poll_load(&mut self) {
+        let Some(result) = self.pending_load.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_load = None;
+        match result {
+            Ok(loaded) => self.apply_loaded(loaded),
+            Err(error) => self.status = format!("Loading {} failed: {}", self.selected_file, error),
+        }
+    }
+
+    fn select_file(&mut self, file: String) {
+        if file == self.selected_file || !self.flush_unsaved() {
+            return;
+        }
+        self.file_views.insert(self.selected_file.clone(), FileView {
+            scroll_offset: self.list_scroll_offset,
+            selected_index: self.selected_index,
+            selection: self.selection.clone(),
+            selection_chars: self.selection_chars(),
+        });
+        self.selected_file = file;
+        self.inline_edit = None;
+        self.load_matches();
+        self.reset_editor();
+        if self.raw_yaml.is_some() {
+            self.open_raw_yaml();
+        }
+
+        let view = self.file_views.get(&self.selected_file).cloned().unwrap_or_default();
+        self.selected_index = view.selected_index.filter(|&i| i < self.matches.len());
+        self.selection = view.selection.into_iter().filter(|&i| i < self.matches.len()).collect();
+        self.restore_scroll_offset = Some(view.scroll_offset);
+    }
+
+    // The editor differs from the match being edited, or holds a match not added yet
+    fn has_unsaved_edits(&self) -> bool {
+        let editor = match self.editing_index.and_then(|i| self.matches.get(i)) {
+            Some(stored) => self.editor_match() != *stored,
+            None => {
+                !self.new_trigger.is_empty() || !self.new_regex.is_empty() || !self.new_replacement.is_empty() || !self.new_form.is_empty()
+            }
         };
+        editor || self.raw_yaml.as_ref().is_some_and(|raw| raw.text != raw.base)
     }
 
-    fn save_matches(&self) {
-        let file_path = self.config_dir.join(&self.selected_file);
-        let data = serde_yaml::to_string(&serde_yaml::Value::Mapping(serde_yaml::Mapping::from_iter(vec![
-            (serde_yaml::Value::String("matches".to_string()), serde_yaml::Value::Sequence(
-                self.matches.iter().map(|m| serde_yaml::Value::Mapping(serde_yaml::Mapping::from_iter(vec![
-                    (serde_yaml::Value::String("trigger".to_string()), serde_yaml::Value::String(m.trigger.clone())),
-                    (serde_yaml::Value::String("replace".to_string()), serde_yaml::Value::String(m.replace.clone())),
-                ]))).collect()
-            )),
-        ]))).unwrap();
-        fs::write(file_path, data).unwrap();
-    }
-
-    fn show_match_dialog(&mut self, match_to_edit: Option<Match>) {
-        // Implementiere den Dialog zum Hinzufügen/Bearbeiten von Matches
-        // Beispiel:
-        if let Some(match_item) = match_to_edit {
-            println!("Editing match: {:?}", match_item);
-        } else {
-            println!("Adding new match");
+    fn stash_tab(&self) -> EditTab {
+        EditTab {
+            file: self.selected_file.clone(),
+            document: self.document.clone(),
+            matches: self.matches.clone(),
+            compact: self.compact.clone(),
+            loaded_mtime: self.loaded_mtime,
+            filter_text: self.filter_text.clone(),
+            view: FileView {
+                scroll_offset: self.list_scroll_offset,
+                selected_index: self.selected_index,
+                selection: self.selection.clone(),
+                selection_chars: self.selection_chars(),
+            },
+            editing_index: self.editing_index,
+            regex_mode: self.regex_mode,
+            new_trigger: self.new_trigger.clone(),
+            new_replacement: self.new_replacement.clone(),
+            new_aliases: self.new_aliases.clone(),
+            new_regex: self.new_regex.clone(),
+            new_word: self.new_word,
+            new_label: self.new_label.clone(),
+            new_propagate_case: self.new_propagate_case,
+            new_force_clipboard: self.new_force_clipboard,
+            new_vars: self.new_vars.clone(),
+            new_form: self.new_form.clone(),
+            new_form_fields: self.new_form_fields.clone(),
+            raw_yaml: self.raw_yaml.clone(),
+            unsaved: self.has_unsaved_edits(),
         }
     }
 
-    fn delete_match(&mut self, index: usize) {
-        // Implementiere das Löschen von Matches mit Bestätigung
-        // Beispiel:
-        if index < self.matches.len() {
-            self.matches.remove(index);
-            self.save_matches();
+    fn restore_tab(&mut self, tab: EditTab) {
+        self.pending_load = None;
+        self.selected_file = tab.file;
+        self.document = tab.document;
+        self.matches = tab.matches;
+        self.compact = tab.compact;
+        self.loaded_mtime = tab.loaded_mtime;
+        self.filter_text = tab.filter_text;
+        self.list_scroll_offset = tab.view.scroll_offset;
+        self.restore_scroll_offset = Some(tab.view.scroll_offset);
+        self.selected_index = tab.view.selected_index;
+        self.selection = tab.view.selection;
+        self.editing_index = tab.editing_index;
+        self.regex_mode = tab.regex_mode;
+        self.new_trigger = tab.new_trigger;
+        self.new_replacement = tab.new_replacement;
+        self.new_aliases = tab.new_aliases;
+        self.new_regex = tab.new_regex;
+        self.new_word = tab.new_word;
+        self.new_label = tab.new_label;
+        self.new_propagate_case = tab.new_propagate_case;
+        self.new_force_clipboard = tab.new_force_clipboard;
+        self.new_vars = tab.new_vars;
+        self.new_form = tab.new_form;
+        self.new_form_fields = tab.new_form_fields;
+        self.raw_yaml = tab.raw_yaml;
+        self.platform_drafts = None;
+        self.rename_references = None;
+        self.merge_session = None;
+        self.conflict = merge::detect(&self.config_dir.join(&self.selected_file));
+        self.external_change = false;
+        // Another tab may have saved the same file in the meantime
+        self.reload_if_changed();
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() || self.tour.is_some() || !self.flush_unsaved() {
+            return;
         }
+        self.tabs[self.active_tab] = self.stash_tab();
+        let tab = self.tabs[index].clone();
+        self.active_tab = index;
+        self.restore_tab(tab);
     }
 
-    fn filtered_matches(&self) -> Vec<Match> {
-        self.matches.iter().filter(|m| {
-            m.trigger.to_lowercase().contains(&self.filter_text.to_lowercase()) ||
-            m.replace.to_lowercase().contains(&self.filter_text.to_lowercase())
-        }).cloned().collect()
+    // Opens the selected file again in a new tab, where another file can be picked
+    fn open_tab(&mut self) {
+        if self.tour.is_some() || !self.flush_unsaved() {
+            return;
+        }
+        self.tabs[self.active_tab] = self.stash_tab();
+        self.tabs.push(EditTab::default());
+        self.active_tab = self.tabs.len() - 1;
+        self.filter_text.clear();
+        self.selected_index = None;
+        self.selection.clear();
+        self.restore_scroll_offset = Some(0.0);
+        self.reset_editor();
     }
 
-    fn add_or_update_match(&mut self) {
-        if !self.new_trigger.is_empty() && !self.new_replacement.is_empty() {
-            let new_match = Match {
-                trigger: self.new_trigger.clone(),
-                replace: self.new_replacement.clone(),
-            };
-            
-            if let Some(index) = self.editing_index {
-                if index < self.matches.len() {
-                    self.matches[index] = new_match;
-                }
-            } else {
-                self.matches.push(new_match);
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() < 2 || index >= self.tabs.len() || self.tour.is_some() {
+            return;
+        }
+        if index == self.active_tab && !self.flush_unsaved() {
+            return;
+        }
+        self.tabs.remove(index);
+        if index == self.active_tab {
+            self.active_tab = index.min(self.tabs.len() - 1);
+            let tab = self.tabs[self.active_tab].clone();
+            self.restore_tab(tab);
+        } else if index < self.active_tab {
+            self.active_tab -= 1;
+        }
+    }
+
+    // Plain clicks select a single match, Ctrl toggles one and Shift extends over the visible list
+    fn click_match(&mut self, index: usize, modifiers: egui::Modifiers, visible: &[usize]) {
+        self.confirm_bulk_delete = false;
+        if modifiers.command {
+            if let Some(anchor) = self.selected_index {
+                self.selection.insert(anchor);
             }
-            
-            self.new_trigger.clear();
-            self.new_replacement.clear();
-            self.editing_index = None;
-            self.save_matches();
+            if !self.selection.remove(&index) {
+                self.selection.insert(index);
+            }
+        } else if modifiers.shift {
+            let anchor = self.selected_index.and_then(|a| visible.iter().position(|&i| i == a)).unwrap_or(0);
+            let target = visible.iter().position(|&i| i == index).unwrap_or(anchor);
+            let range = anchor.min(target)..=anchor.max(target);
+            self.selection.extend(visible[range].iter().copied());
+        } else {
+            self.selection = BTreeSet::from([index]);
         }
+        self.selected_index = Some(index);
     }
 
-    fn open_config_folder(&self) {
-        #[cfg(target_os = "windows")]
-        {
-            Command::new("explorer")
-                .arg(self.config_dir.to_str().unwrap())
-                .spawn()
-                .expect("failed to execute process");
+    fn apply_match_action(&mut self, action: MatchAction, visible: &[usize]) {
+        match action {
+            MatchAction::Click(index, modifiers) => self.click_match(index, modifiers, visible),
+            MatchAction::Drag(index) => self.start_match_drag(index),
+            MatchAction::Edit(index) => self.start_editing(index),
+            MatchAction::EditInline(index) => self.start_inline_edit(index),
+            MatchAction::SaveInline => self.commit_inline_edit(),
+            MatchAction::CancelInline => self.inline_edit = None,
+            MatchAction::Try(index) => self.try_match(index),
+            MatchAction::Transfer(indices, file, copy) => self.transfer_matches(&indices, &file, copy),
+            MatchAction::Delete(index) => self.confirm_delete(index),
+            MatchAction::DetachAlias(index, alias) => self.detach_alias(index, &alias),
+            MatchAction::MakeAlias(canonical, alias) => self.make_aliases(canonical, &[alias]),
+            MatchAction::ApplyFix(index, fix) => self.apply_fix(index, fix),
+            MatchAction::SaveMetadata => self.save_metadata(),
         }
-        #[cfg(target_os = "macos")]
-        {
-            Command::new("open")
-                .arg(self.config_dir.to_str().unwrap())
-                .spawn()
-                .expect("failed to execute process");
+    }
+
+    fn selection_chars(&self) -> usize {
+        self.selection.iter().filter_map(|&i| self.matches.get(i)).map(|m| m.replace.chars().count()).sum()
+    }
+
+    // Aggregate over the selections in all files, once more than one match is selected
+    fn selection_summary(&self) -> Option<String> {
+        let mut count = self.selection.len();
+        let mut chars = self.selection_chars();
+        let mut files: Vec<&str> = Vec::new();
+        if !self.selection.is_empty() {
+            files.push(&self.selected_file);
         }
-        #[cfg(target_os = "linux")]
-        {
-            Command::new("xdg-open")
-                .arg(self.config_dir.to_str().unwrap())
-                .spawn()
-                .expect("failed to execute process");
+        for (file, view) in &self.file_views {
+            if *file != self.selected_file && !view.selection.is_empty() {
+                count += view.selection.len();
+                chars += view.selection_chars;
+                files.push(file);
+            }
+        }
+        if count < 2 {
+            return None;
         }
+        files.sort();
+        Some(format!("{} matches selected · {} characters · {}", count, chars, files.join(", ")))
     }
-}
 
-impl eframe::App for EspansoHelper {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut temp_self = self.clone();
-        let self_rc = Rc::new(RefCell::new(&mut temp_self));
-        
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Espanso Helper");
-            
-            ui.horizontal(|ui| {
-                if ui.button("Refresh").clicked() {
-                    self_rc.borrow_mut().refresh();
-                }
-                if ui.button("Open Config Folder").clicked() {
-                    self_rc.borrow().open_config_folder();
-                }
-            });
-            
-            let selected_file = self_rc.borrow().selected_file.clone();
-            let files = self_rc.borrow().files.clone();
-            
-            egui::ComboBox::from_label("Select YAML file")
-                .selected_text(&selected_file)
-                .show_ui(ui, |ui| {
-                    for file in &files {
-                        if ui.selectable_value(&mut self_rc.borrow_mut().selected_file, file.clone(), file).changed() {
-                            self_rc.borrow_mut().load_matches();
-                        }
+    // Turns user input into a file name inside the match folder
+    fn new_file_name(&mut self, name: &str) -> Option<String> {
+        let name = name.trim();
+        let name = if name.ends_with(".yml") || name.ends_with(".yaml") { name.to_string() } else { format!("{}.yml", name) };
+        let outside = Path::new(&name).components().any(|c| !matches!(c, std::path::Component::Normal(_)));
+        let empty = name.rsplit('/').next().is_some_and(|file| file == ".yml" || file == ".yaml");
+        let problem = if empty || name.contains('\\') || outside {
+            Some("Enter a file name, optionally in a subfolder like work/mail.yml".to_string())
+        } else if self.config_dir.join(&name).exists() {
+            Some(format!("{} already exists", name))
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => {
+                self.status = problem;
+                None
+            }
+            None => Some(name),
+        }
+    }
+
+    fn run_file_action(&mut self) {
+        let Some(action) = self.file_action else { return };
+        if !self.allow_destructive() {
+            return;
+        }
+        let current = self.config_dir.join(&self.selected_file);
+        let target = if action == FileAction::Delete {
+            None
+        } else {
+            let name = match action {
+                FileAction::Create => self.file_preset.file_name(self.file_action_name.trim()),
+                _ => self.file_action_name.clone(),
+            };
+            let Some(name) = self.new_file_name(&name) else { return };
+            Some(name)
+        };
+        let result = match (action, &target) {
+            (FileAction::Create, Some(name)) => {
+                let header = match self.file_preset {
+                    presets::FilePreset::EmojiPack => generated::comment(&generated::Generator::EmojiPack),
+                    _ => String::new(),
+                };
+                create_parent(&self.config_dir.join(name)).and_then(|()| fs::write(self.config_dir.join(name), header + self.file_preset.contents()))
+            }
+            (FileAction::Rename, Some(name)) => create_parent(&self.config_dir.join(name)).and_then(|()| fs::rename(&current, self.config_dir.join(name))),
+            (FileAction::Duplicate, Some(name)) => {
+                create_parent(&self.config_dir.join(name)).and_then(|()| fs::copy(&current, self.config_dir.join(name)).map(|_| ()))
+            }
+            _ => fs::remove_file(&current),
+        };
+        if let Err(error) = result {
+            self.status = format!("File operation failed: {}", error);
+            return;
+        }
+        let companion = match (action, &target) {
+            (FileAction::Create, Some(name)) => self.file_preset.companion(name),
+            _ => None,
+        };
+        self.status = match (action, &target) {
+            (FileAction::Create, Some(name)) => match companion {
+                Some((config_name, contents)) => {
+                    let path = self.espanso_dir().join("config").join(&config_name);
+                    let written = if path.exists() {
+                        Ok(())
+                    } else {
+                        fs::create_dir_all(self.espanso_dir().join("config")).and_then(|_| fs::write(&path, contents))
+                    };
+                    match written {
+                        Ok(()) => format!("Created {}, set the application filter in config/{}", name, config_name),
+                        Err(error) => format!("Created {}, but writing config/{} failed: {}", name, config_name, error),
                     }
-                });
-            
-            ui.horizontal(|ui| {
-                ui.label("Filter:");
-                if ui.text_edit_singleline(&mut self_rc.borrow_mut().filter_text).changed() {
-                    // Filter has changed, you might want to update the filtered matches here
                 }
-            });
-            
-            ui.horizontal(|ui| {
-                ui.label("New Trigger:");
-                ui.text_edit_singleline(&mut self_rc.borrow_mut().new_trigger);
-            });
-            
-            ui.label("New Replacement:");
-            ui.text_edit_multiline(&mut self_rc.borrow_mut().new_replacement);
-            
-            if ui.button(if self_rc.borrow().editing_index.is_some() { "Update Match" } else { "Add Match" }).clicked() {
-                self_rc.borrow_mut().add_or_update_match();
+                None => format!("Created {}", name),
+            },
+            (FileAction::Rename, Some(name)) => format!("Renamed {} to {}", self.selected_file, name),
+            (FileAction::Duplicate, Some(name)) => format!("Copied {} to {}", self.selected_file, name),
+            _ => format!("Deleted {}", self.selected_file),
+        };
+        self.file_action = None;
+        self.file_action_name.clear();
+        self.files = self.list_yaml_files();
+        self.last_validation = None;
+        // Other tabs on the file follow a rename and close with a delete
+        let (old, active) = (self.selected_file.clone(), self.active_tab);
+        match (action, &target) {
+            (FileAction::Rename, Some(name)) => {
+                self.tabs.iter_mut().filter(|tab| tab.file == old).for_each(|tab| tab.file = name.clone());
             }
-            
-            let filtered_matches = self_rc.borrow().filtered_matches();
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for (index, match_item) in filtered_matches.iter().enumerate() {
-                    ui.horizontal(|ui| {
-                        ui.label(&match_item.trigger);
-                        if ui.button("Edit").clicked() {
-                            let mut borrowed = self_rc.borrow_mut();
-                            borrowed.new_trigger = match_item.trigger.clone();
-                            borrowed.new_replacement = match_item.replace.clone();
-                            borrowed.editing_index = Some(index);
-                        }
-                        if ui.button("Delete").clicked() {
-                            self_rc.borrow_mut().delete_match(index);
-                        }
-                    });
-                    ui.label(&match_item.replace);
-                    ui.separator();
-                }
-            });
-        });
-        
-        // Move the changes back to self
-        *self = temp_self;
+            (FileAction::Delete, _) => {
+                let closed_before = self.tabs[..active].iter().filter(|tab| tab.file == old).count();
+                self.tabs = std::mem::take(&mut self.tabs)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, tab)| *index == active || tab.file != old)
+                    .map(|(_, tab)| tab)
+                    .collect();
+                self.active_tab = active - closed_before;
+            }
+            _ => {}
+        }
+        if action == FileAction::Rename {
+            if let (Some(name), Some(view)) = (&target, self.file_views.remove(&self.selected_file)) {
+                self.file_views.insert(name.clone(), view);
+            }
+            // Same contents under a new name, so the loaded state stays valid
+            self.selected_file = target.unwrap_or_default();
+        } else if let Some(name) = target {
+            self.select_file(name);
+        } else {
+            self.file_views.remove(&self.selected_file);
+            self.selected_file = self.files.first().cloned().unwrap_or_default();
+            self.load_matches();
+            self.reset_editor();
+        }
     }
-}
 
-fn list_yaml_files(dir: &Path) -> Vec<String> {
-    fs::read_dir(dir)
-        .into_iter()
-        .flatten()
-        .filter_map(|entry| {
-            let path = entry.ok()?.path();
-            if path.extension()?.to_str()? == "yml" {
-                Some(path.file_name()?.to_str()?.to_string())
-            } else {
-                None
-            }
-        })
-        .collect()
-}
+    // Writes the selected file, or only notes that it needs writing when autosave is off
+    fn save_matches(&mut self) {
+        let file = self.selected_file.clone();
+        self.track_usage(|usage| usage.record_edit(&file));
+        if self.tour.is_some() {
+            self.save_now();
+            return;
+        }
+        // Stays set until a write succeeds
+        self.unsaved_file = true;
+        if self.settings.autosave {
+            self.save_now();
+        }
+    }
 
-fn main() -> Result<(), eframe::Error> {
+    fn save_now(&mut self) -> bool {
+        if self.current_mtime() != self.loaded_mtime && self.tour.is_none() {
+            self.external_change = true;
+            self.status = format!("{} changed on disk since it was loaded, reload or overwrite it", self.selected_file);
+            return false;
+        }
+        self.write_matches()
+    }
+
+    fn refresh_global_vars(&mut self) {
+        self.global_var_definitions = global_vars::definitions(&self.config_dir, &self.files);
+    }
+
+    // Works out what renaming a global variable changes, for the preview
+    fn plan_global_var_rename(&mut self) {
+        let Some((old, new)) = self.global_var_rename.clone() else { return };
+        // The rename reads the files from disk
+        if !self.flush_unsaved() {
+            return;
+        }
+        match global_vars::plan(&self.config_dir, &self.files, &old, new.trim()) {
+            Ok(plan) => self.global_var_plan = Some(plan),
+            Err(error) => self.status = error,
+        }
+    }
+
+    fn apply_global_var_rename(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let Some(plan) = self.global_var_plan.take() else { return };
+        if let Err(error) = global_vars::apply(&self.config_dir, &plan, self.settings.backup_count) {
+            return self.report_error(format!("Renaming {} failed: {}", plan.old, error));
+        }
+        let files = plan.files();
+        self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::RenameGlobalVar));
+        self.status = format!("Renamed {} to {} in {} files", plan.old, plan.new, files.len());
+        if files.contains(&self.selected_file.as_str()) {
+            self.load_matches();
+        }
+        self.global_var_rename = None;
+        self.last_validation = None;
+        self.refresh_global_vars();
+    }
+
+    // Writes held-back changes before the selected file is left. False while they are
+    // still unsaved, waiting for a review for instance; the status says why.
+    fn flush_unsaved(&mut self) -> bool {
+        if self.unsaved_file {
+            self.save_now();
+        }
+        !self.unsaved_file
+    }
+
+    fn save_settings(&mut self) {
+        if self.safe_mode || self.settings == self.saved_settings {
+            return;
+        }
+        match self.settings.save() {
+            Ok(()) => self.saved_settings = self.settings.clone(),
+            Err(error) => {
+                self.status = format!("Saving the settings failed: {}", error);
+                // Not again every frame
+                self.saved_settings = self.settings.clone();
+            }
+        }
+    }
+
+    // Saves even if the file changed on disk in the meantime
+    fn overwrite_matches(&mut self) {
+        self.external_change = false;
+        self.write_matches();
+    }
+
+    // Why the selected file can't be written right now, if anything stands in the way
+    fn save_blocker(&self) -> Option<String> {
+        // Saving before the file has been read would overwrite it with an empty list
+        if self.pending_load.is_some() {
+            return Some(format!("{} is still loading, try again in a moment", self.selected_file));
+        }
+        if self.compact.is_some() {
+            return Some(format!("{} is open read-only, load it for editing first", self.selected_file));
+        }
+        if self.tour.is_some() {
+            return Some("Tour: changes to the sample matches are not saved".to_string());
+        }
+        None
+    }
+
+    // True once the file is written; a pending review keeps the changes unsaved
+    fn write_matches(&mut self) -> bool {
+        if let Some(reason) = self.save_blocker() {
+            self.status = reason;
+            return false;
+        }
+        let rules = self.trim_rules.for_file(&self.selected_file);
+        if rules.is_active() {
+            for m in self.matches.iter_mut() {
+                m.replace = rules.apply(&m.replace);
+            }
+        }
+        let path = self.config_dir.join(&self.selected_file);
+        if self.settings.review_saves {
+            let on_disk = fs::read_to_string(&path).unwrap_or_default();
+            let rendered = match self.document.render(&self.matches) {
+                Ok(rendered) => rendered,
+                Err(error) => {
+                    self.report_error(format!("Saving {} failed: {}", self.selected_file, error));
+                    return false;
+                }
+            };
+            if on_disk != rendered {
+                self.pending_review = Some(SaveReview { file: self.selected_file.clone(), lines: text::line_diff(&on_disk, &rendered) });
+                self.unsaved_file = true;
+                self.status = format!("Review the changes to {} before they are saved", self.selected_file);
+                return false;
+            }
+        }
+        self.commit_matches()
+    }
+
+    fn commit_matches(&mut self) -> bool {
+        self.pending_review = None;
+        let path = self.config_dir.join(&self.selected_file);
+        if let Err(error) = backups::create(&self.config_dir, &self.selected_file, self.settings.backup_count) {
+            self.status = format!("Backing up {} failed: {}", self.selected_file, error);
+        }
+        if let Err(error) = self.document.save(&path, &self.matches) {
+            self.report_error(format!("Saving {} failed: {}", self.selected_file, error));
+            return false;
+        }
+        self.unsaved_file = false;
+        self.loaded_mtime = self.current_mtime();
+        self.last_validation = None;
+        if self.settings.restart_after_save && self.pending_daemon_restart.is_none() {
+            self.restart_daemon();
+        }
+        true
+    }
+
+    fn open_raw_yaml(&mut self) {
+        if self.compact.is_some() || self.selected_file.is_empty() {
+            self.raw_yaml = None;
+            return;
+        }
+        // A file that didn't parse is shown as it is on disk, so it can be fixed here
+        let text = match self.document.error() {
+            Some(_) => fs::read_to_string(self.config_dir.join(&self.selected_file)).map_err(|e| e.to_string()),
+            None => self.document.render(&self.matches),
+        };
+        match text {
+            Ok(text) => self.raw_yaml = Some(RawDraft { base: text.clone(), text }),
+            Err(error) => {
+                self.raw_yaml = None;
+                self.status = format!("Showing {} as YAML failed: {}", self.selected_file, error);
+            }
+        }
+    }
+
+    // Writes the hand-edited source as is and reads the file back, so the match list
+    // shows what was typed
+    fn save_raw_yaml(&mut self) {
+        let Some(raw) = self.raw_yaml.clone() else { return };
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Some(reason) = self.save_blocker() {
+            self.status = reason;
+            return;
+        }
+        if let Some(error) = yaml_io::parse_error(&raw.text) {
+            self.status = format!("Not saved, the YAML doesn't parse: {}", error);
+            return;
+        }
+        if self.current_mtime() != self.loaded_mtime {
+            self.status = format!("{} changed on disk since it was loaded, revert to see the new version", self.selected_file);
+            return;
+        }
+        let path = self.config_dir.join(&self.selected_file);
+        if let Err(error) = backups::create(&self.config_dir, &self.selected_file, self.settings.backup_count) {
+            self.status = format!("Backing up {} failed: {}", self.selected_file, error);
+        }
+        if let Err(error) = fs::write(&path, &raw.text) {
+            return self.report_error(format!("Saving {} failed: {}", self.selected_file, error));
+        }
+        self.load_matches();
+        self.editing_index = None;
+        self.reset_editor();
+        self.raw_yaml = Some(RawDraft { base: raw.text.clone(), text: raw.text });
+        self.last_validation = None;
+        self.status = format!("Saved {}, {} matches", self.selected_file, self.matches.len());
+        if self.settings.restart_after_save && self.pending_daemon_restart.is_none() {
+            self.restart_daemon();
+        }
+    }
+
+    fn accept_review(&mut self) {
+        if self.pending_review.as_ref().is_none_or(|review| review.file != self.selected_file) {
+            return;
+        }
+        // The review stays open until the file can actually be written
+        if let Some(reason) = self.save_blocker() {
+            self.status = reason;
+            return;
+        }
+        // The file may have been written by something else while the dialog was open
+        if self.current_mtime() != self.loaded_mtime {
+            self.pending_review = None;
+            self.external_change = true;
+            self.status = format!("{} changed on disk since it was loaded, reload or overwrite it", self.selected_file);
+            return;
+        }
+        if self.commit_matches() {
+            self.status = format!("Saved {}", self.selected_file);
+        }
+    }
+
+    // Drops the reviewed changes by reading the file back from disk
+    fn discard_review(&mut self) {
+        let Some(review) = self.pending_review.take() else { return };
+        if review.file == self.selected_file {
+            self.load_matches();
+            self.reset_editor();
+            self.status = format!("Discarded the unsaved changes to {}", review.file);
+        }
+    }
+
+    // Matches the whitespace rules of the selected file would change on the next save
+    fn trim_preview(&self) -> Vec<String> {
+        let rules = self.trim_rules.for_file(&self.selected_file);
+        self.matches.iter()
+            .filter(|m| rules.apply(&m.replace) != m.replace)
+            .map(Match::display_trigger)
+            .collect()
+    }
+
+    // Failures that lose work if overlooked get a dialog besides the status line
+    fn report_error(&mut self, message: String) {
+        log::error!("{}", message);
+        self.status = message.clone();
+        self.error_dialog = Some(message);
+    }
+
+    fn set_soft_wrap(&mut self, hint: &str, soft_wrap: bool) {
+        self.wrap_modes.set(hint, soft_wrap);
+        if let Err(error) = self.wrap_modes.save() {
+            self.status = format!("Saving the wrap setting failed: {}", error);
+        }
+    }
+
+    fn save_metadata(&mut self) {
+        if let Err(error) = self.metadata.save() {
+            self.status = format!("Saving notes and tags failed: {}", error);
+        }
+    }
+
+    // Drags the whole selection when the dragged row is part of it
+    fn start_match_drag(&mut self, index: usize) {
+        self.dragged_matches = Some(if self.selection.contains(&index) { self.selection.iter().copied().collect() } else { vec![index] });
+    }
+
+    fn confirm_delete(&mut self, index: usize) {
+        if let Some(m) = self.matches.get(index) {
+            self.pending_delete = Some((index, m.display_trigger()));
+        }
+    }
+
+    fn delete_match(&mut self, index: usize) {
+        self.pending_delete = None;
+        if !self.allow_destructive() {
+            return;
+        }
+        if index < self.matches.len() {
+            let removed = self.matches.remove(index);
+            self.status = format!("Deleted {}, it can be restored from the trash", removed.display_trigger());
+            self.move_to_trash(vec![removed]);
+            self.platform_drafts = None;
+            self.selected_index = match self.selected_index {
+                Some(selected) if selected == index => None,
+                Some(selected) if selected > index => Some(selected - 1),
+                other => other,
+            };
+            self.selection.clear();
+            self.rename_references = None;
+            self.save_matches();
+        }
+    }
+
+    fn delete_matches(&mut self, indices: &BTreeSet<usize>) {
+        self.confirm_bulk_delete = false;
+        if !self.allow_destructive() || indices.is_empty() {
+            return;
+        }
+        let mut index = 0;
+        let mut removed = Vec::new();
+        self.matches.retain(|m| {
+            index += 1;
+            let keep = !indices.contains(&(index - 1));
+            if !keep {
+                removed.push(m.clone());
+            }
+            keep
+        });
+        self.status = format!("Deleted {} matches, they can be restored from the trash", removed.len());
+        if removed.len() > 1 {
+            self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::Delete));
+        }
+        self.move_to_trash(removed);
+        self.platform_drafts = None;
+        self.selected_index = None;
+        self.selection.clear();
+        self.editing_index = None;
+        self.rename_references = None;
+        self.risk_findings.clear();
+        self.save_matches();
+    }
+
+    // Keeps deleted matches and their notes for the session; sample matches of the
+    // tour are simply dropped
+    fn move_to_trash(&mut self, removed: Vec<Match>) {
+        if self.tour.is_some() {
+            return;
+        }
+        let deleted = chrono::Local::now().format("%H:%M:%S").to_string();
+        for m in removed {
+            let meta = self.metadata.get(&m).cloned();
+            self.metadata.remove(&m);
+            self.trash.push(TrashedMatch { file: self.selected_file.clone(), m, meta, deleted: deleted.clone() });
+        }
+        self.save_metadata();
+    }
+
+    // Puts a trashed match back at the end of the file it was deleted from, or of the
+    // selected file when that one is gone
+    fn restore_from_trash(&mut self, position: usize) {
+        if !self.allow_destructive() || position >= self.trash.len() {
+            return;
+        }
+        let file = if self.files.contains(&self.trash[position].file) { self.trash[position].file.clone() } else { self.selected_file.clone() };
+        if file == self.selected_file {
+            if let Some(reason) = self.save_blocker() {
+                self.status = reason;
+                return;
+            }
+        }
+        let entry = self.trash.remove(position);
+        if let Some(meta) = entry.meta.clone() {
+            *self.metadata.get_mut(&file, &entry.m) = meta;
+            self.save_metadata();
+        }
+        self.status = format!("Restored {} to {}", entry.m.display_trigger(), file);
+        if file == self.selected_file {
+            self.matches.push(entry.m);
+            self.save_matches();
+        } else {
+            let path = self.config_dir.join(&file);
+            let mut document = yaml_io::MatchFile::load(&path);
+            let mut matches = document.matches();
+            matches.push(entry.m.clone());
+            if let Err(error) = document.save(&path, &matches) {
+                self.trash.insert(position, entry);
+                return self.report_error(format!("Restoring into {} failed: {}", file, error));
+            }
+            self.last_validation = None;
+        }
+    }
+
+    fn filter_query(&self) -> filter::Query {
+        filter::Query::new(&self.filter_text, self.settings.filter_mode)
+    }
+
+    // Orders list positions by the column picked in the table header
+    fn sort_matches(&self, indices: &mut [usize]) {
+        let Some((column, descending)) = self.match_sort else { return };
+        indices.sort_by_cached_key(|&index| {
+            let m = &self.matches[index];
+            match column {
+                MatchColumn::Trigger => m.display_trigger().to_lowercase(),
+                MatchColumn::Replacement => m.replace.to_lowercase(),
+                MatchColumn::Label => m.label.clone().unwrap_or_default().to_lowercase(),
+                MatchColumn::Flags => match_flags(m).join(", "),
+                MatchColumn::File => String::new(),
+            }
+        });
+        if descending {
+            indices.reverse();
+        }
+    }
+
+    // Indices into `self.matches` of the matches passing the filter. Fuzzy results come
+    // best first, until a column of the table sorts them.
+    fn filtered_matches(&self) -> Vec<usize> {
+        let query = self.filter_query();
+        if let filter::Query::Empty = query {
+            return (0..self.matches.len()).collect();
+        }
+        let mut scored: Vec<(usize, i64)> = self
+            .matches
+            .iter()
+            .enumerate()
+            .filter_map(|(index, m)| Some((index, match_score(&query, self.settings.filter_scope, m)?)))
+            .collect();
+        if let filter::Query::Fuzzy(_) = query {
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        }
+        scored.into_iter().map(|(index, _)| index).collect()
+    }
+
+    // Writes `<file>.md` next to the selected file, documenting each of its matches
+    fn export_documentation(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let stem = Path::new(&self.selected_file).file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let target = self.config_dir.join(format!("{}.md", stem));
+        let contents = docs::render(&self.selected_file, &self.document.header, &self.matches, &self.preview_locale());
+        self.status = match fs::write(&target, contents) {
+            Ok(()) => format!("Documentation written to {}", display_path(&target)),
+            Err(error) => format!("Writing {} failed: {}", display_path(&target), error),
+        };
+    }
+
+    // Writes everything the filter currently shows, or just the selected matches, into
+    // a new file
+    fn export_filter_result(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let mut file_name = self.export_file_name.trim().to_string();
+        if file_name.is_empty() {
+            self.status = "Enter a name for the exported file".to_string();
+            return;
+        }
+        // The name is joined onto a folder and must not lead out of it
+        let outside = Path::new(&file_name).components().any(|c| !matches!(c, std::path::Component::Normal(_)));
+        if outside || file_name.contains(['/', '\\']) {
+            self.status = "Enter a file name without a folder".to_string();
+            return;
+        }
+        if !file_name.ends_with(self.export_format.extension()) {
+            file_name.push_str(self.export_format.extension());
+        }
+        // Only a new match file belongs in the config dir, everything else is meant to be
+        // shared and lands in the download folder
+        let target = match self.export_format {
+            ExportFormat::Espanso => self.config_dir.join(&file_name),
+            _ => dirs::download_dir().or_else(dirs::home_dir).unwrap_or_default().join(&file_name),
+        };
+        if target.exists() {
+            self.status = format!("{} already exists", file_name);
+            return;
+        }
+
+        let selected_only = self.export_selected_only && !self.selection.is_empty();
+        let source_files = if self.export_all_files && !selected_only { self.files.clone() } else { vec![self.selected_file.clone()] };
+        let mut exported = Vec::new();
+        let mut global_vars: Vec<Var> = Vec::new();
+        let mut remaining = Vec::new();
+        let query = self.filter_query();
+        for file in &source_files {
+            let path = self.config_dir.join(file);
+            let mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            let (document, matches) = if *file == self.selected_file {
+                (self.document.clone(), self.matches.clone())
+            } else {
+                let document = yaml_io::MatchFile::load(&path);
+                let matches = document.matches();
+                (document, matches)
+            };
+            let (mut taken, mut kept) = (Vec::new(), Vec::new());
+            for (index, m) in matches.into_iter().enumerate() {
+                let wanted = if selected_only { self.selection.contains(&index) } else { match_score(&query, self.settings.filter_scope, &m).is_some() };
+                // Matches the format leaves out stay where they are
+                if wanted && !self.export_format.writes(&m) {
+                    kept.push(m.clone());
+                }
+                if wanted { taken.push(m) } else { kept.push(m) }
+            }
+            if taken.is_empty() {
+                continue;
+            }
+            for var in document.global_vars() {
+                if !global_vars.iter().any(|v| v.name == var.name) {
+                    global_vars.push(var);
+                }
+            }
+            exported.extend(taken);
+            remaining.push((file.clone(), document, kept, mtime));
+        }
+
+        let (contents, skipped) = match self.export_format {
+            ExportFormat::Espanso => match yaml_io::MatchFile::default().render(&exported) {
+                Ok(contents) => (contents, 0),
+                Err(error) => return self.report_error(format!("Exporting failed: {}", error)),
+            },
+            ExportFormat::Standalone => (yaml_io::render_standalone(&self.document.header, &global_vars, &exported), 0),
+            ExportFormat::Beeftext => beeftext::render(&exported),
+            ExportFormat::AutoHotkey => autohotkey::render(&exported),
+            ExportFormat::Csv => table::render_csv(&exported),
+            ExportFormat::Json => table::render_json(&exported),
+        };
+        if let Err(error) = fs::write(&target, contents) {
+            self.status = format!("Writing {} failed: {}", display_path(&target), error);
+            return;
+        }
+        self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::Export));
+        // Sources are only touched once the export is safely written
+        if self.export_remove_sources {
+            for (file, mut document, kept, mtime) in remaining {
+                if file == self.selected_file {
+                    self.matches = kept;
+                    self.selected_index = None;
+                    self.selection.clear();
+                    self.editing_index = None;
+                    self.save_matches();
+                    continue;
+                }
+                // Other files are written like the selected one: not over changes made
+                // elsewhere, and not without a backup
+                let path = self.config_dir.join(&file);
+                if fs::metadata(&path).and_then(|m| m.modified()).ok() != mtime {
+                    self.status = format!("Exported, but {} changed on disk meanwhile and still has the exported matches", file);
+                    return;
+                }
+                if let Err(error) = backups::create(&self.config_dir, &file, self.settings.backup_count) {
+                    self.status = format!("Backing up {} failed: {}", file, error);
+                }
+                if let Err(error) = document.save(&path, &kept) {
+                    return self.report_error(format!("Exported, but removing the matches from {} failed: {}", file, error));
+                }
+                self.last_validation = None;
+            }
+        }
+        // Other expanders get plain text only, so the matches they miss are named
+        let flagged: Vec<String> = match self.export_format {
+            ExportFormat::Beeftext | ExportFormat::AutoHotkey => exported
+                .iter()
+                .filter_map(|m| Some(format!("{} ({})", m.display_trigger(), m.plain_text_issue()?)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for entry in &flagged {
+            log::warn!("Not exported to {}: {}", display_path(&target), entry);
+        }
+        self.status = if !flagged.is_empty() {
+            let more = if flagged.len() > 5 { format!(" and {} more", flagged.len() - 5) } else { String::new() };
+            format!(
+                "Exported {} matches to {}; left out {}{}",
+                exported.len() - skipped,
+                display_path(&target),
+                flagged.iter().take(5).cloned().collect::<Vec<_>>().join(", "),
+                more
+            )
+        } else if skipped > 0 {
+            format!(
+                "Exported {} matches to {} ({} regex or non-text matches skipped)",
+                exported.len() - skipped,
+                display_path(&target),
+                skipped
+            )
+        } else {
+            format!("Exported {} matches to {}", exported.len(), display_path(&target))
+        };
+        self.files = self.list_yaml_files();
+        self.export_file_name.clear();
+        self.show_export = false;
+    }
+
+    fn start_editing(&mut self, index: usize) {
+        if let Some(m) = self.matches.get(index) {
+            self.new_trigger = m.trigger.clone();
+            self.new_aliases = m.aliases.join("\n");
+            self.new_regex = m.regex.clone().unwrap_or_default();
+            self.regex_mode = m.regex.is_some();
+            self.new_word = m.word;
+            self.new_label = m.label.clone().unwrap_or_default();
+            self.new_propagate_case = m.propagate_case;
+            self.new_force_clipboard = m.force_clipboard;
+            self.new_vars = m.vars.clone();
+            (self.new_form, self.new_form_fields) = forms::read(&m.extra);
+            self.new_replacement = m.replace.clone();
+            self.editing_index = Some(index);
+            self.selected_index = Some(index);
+        }
+    }
+
+    fn editor_vars(&self) -> Result<Vec<Var>, String> {
+        vars::check(&self.new_vars).map(|_| self.new_vars.clone())
+    }
+
+    // The match as it currently stands in the editor
+    fn editor_match(&self) -> Match {
+        let edited = self.editing_index.and_then(|i| self.matches.get(i));
+        let label = self.new_label.trim();
+        // Keys without an editor field survive editing
+        let mut extra = edited.map(|m| m.extra.clone()).unwrap_or_default();
+        forms::write(&mut extra, &self.new_form, &self.new_form_fields);
+        let base = Match {
+            replace: self.new_replacement.clone(),
+            word: self.new_word,
+            label: (!label.is_empty()).then(|| label.to_string()),
+            propagate_case: self.new_propagate_case,
+            force_clipboard: self.new_force_clipboard,
+            vars: self.editor_vars().unwrap_or_default(),
+            extra,
+            ..Default::default()
+        };
+        if self.regex_mode {
+            Match { regex: Some(self.new_regex.clone()), ..base }
+        } else {
+            Match {
+                trigger: self.new_trigger.clone(),
+                aliases: self.new_aliases.lines().filter(|l| !l.is_empty()).map(str::to_string).collect(),
+                ..base
+            }
+        }
+    }
+
+    // Triggers of `m` that other files (or other matches of this file) already define,
+    // with where they are defined
+    fn duplicate_definitions(&self, m: &Match) -> Vec<(String, Vec<(String, usize)>)> {
+        if self.tour.is_some() {
+            return Vec::new();
+        }
+        let mut conflicts = Vec::new();
+        for trigger in m.all_triggers().into_iter().filter(|t| !t.is_empty()) {
+            let definitions: Vec<(String, usize)> = self.problems
+                .definitions(&trigger)
+                .iter()
+                .filter(|(file, index)| *file != self.selected_file || Some(*index) != self.editing_index)
+                .cloned()
+                .collect();
+            if !definitions.is_empty() {
+                conflicts.push((trigger, definitions));
+            }
+        }
+        conflicts
+    }
+
+    // Switches to the file defining a match and opens it in the editor
+    fn open_match(&mut self, file: &str, index: usize) {
+        if self.tour.is_some() {
+            return;
+        }
+        self.select_file(file.to_string());
+        if self.selected_file != file {
+            return;
+        }
+        if self.compact.is_some() {
+            self.status = format!("{} is open read-only, load it for editing to change match #{}", file, index + 1);
+        } else if index < self.matches.len() {
+            self.start_editing(index);
+        }
+    }
+
+    // Jumps to the first definition of a trigger anywhere in the config
+    fn go_to_definition(&mut self, trigger: &str) {
+        match self.problems.definitions(trigger).first().cloned() {
+            Some((file, index)) => self.open_match(&file, index),
+            None => self.status = format!("{} is not defined in any match file", trigger),
+        }
+    }
+
+    fn add_or_update_match(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Err(error) = self.editor_vars() {
+            self.status = error;
+            return;
+        }
+        let has_trigger = if self.regex_mode { !self.new_regex.is_empty() } else { !self.new_trigger.is_empty() };
+        let has_content = !self.new_replacement.is_empty() || self.editor_match().content_key().is_some();
+        if has_trigger && has_content {
+            let mut new_match = self.editor_match();
+            let duplicates = self.duplicate_definitions(&new_match);
+            let rules = &self.settings.lint;
+            let siblings = self.matches.iter().enumerate().filter(|(index, _)| Some(*index) != self.editing_index).map(|(_, m)| m);
+            let mut warnings: Vec<String> = validation::lint_match(&new_match, siblings, rules)
+                .into_iter()
+                .filter(|(kind, _)| rules.get(*kind).enabled)
+                .map(|(_, message)| message)
+                .collect();
+            if self.editing_index.is_none() {
+                new_match.replace = self.templates.for_file(&self.selected_file).apply(&new_match.replace);
+            }
+            
+            let previous = self.editing_index.and_then(|i| self.matches.get(i)).cloned();
+            if self.tour.is_none() {
+                let now = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+                self.metadata.record_edit(&self.selected_file, previous.as_ref(), &new_match, &now);
+                self.save_metadata();
+            }
+
+            if let Some(index) = self.editing_index {
+                if index < self.matches.len() {
+                    let old_trigger = self.matches[index].trigger.clone();
+                    let new_trigger = new_match.trigger.clone();
+                    let renamed = new_match.regex.is_none() && old_trigger != new_trigger;
+                    self.matches[index] = new_match;
+                    if renamed {
+                        self.find_references(index, &old_trigger, &new_trigger);
+                    }
+                }
+            } else {
+                self.matches.push(new_match);
+            }
+            
+            self.reset_editor();
+            self.save_matches();
+            if !duplicates.is_empty() {
+                let triggers: Vec<String> = duplicates.into_iter().map(|(trigger, _)| trigger).collect();
+                warnings.insert(0, format!("espanso may pick any definition of {}", triggers.join(", ")));
+            }
+            if !warnings.is_empty() {
+                self.status = format!("Saved, but {}", warnings.join("; "));
+            }
+        }
+    }
+
+    // Turns the trigger and a one-line replacement of a row into fields. The change goes
+    // through the form, so that must not hold edits of its own.
+    fn start_inline_edit(&mut self, index: usize) {
+        let Some(m) = self.matches.get(index).cloned() else { return };
+        if self.has_unsaved_edits() && self.editing_index != Some(index) {
+            self.status = "Save or discard the changes in the form first".to_string();
+            return;
+        }
+        let trigger = m.regex.clone().unwrap_or_else(|| m.trigger.clone());
+        let one_line = m.content_key().is_none() && !m.replace.contains('\n');
+        let replace = one_line.then(|| m.replace.clone());
+        self.inline_edit = Some(InlineEdit { file: self.selected_file.clone(), index, original: m, trigger, replace, focus: true });
+    }
+
+    fn commit_inline_edit(&mut self) {
+        let Some(edit) = self.inline_edit.take() else { return };
+        if edit.file != self.selected_file || self.matches.get(edit.index) != Some(&edit.original) {
+            self.status = format!("{} changed in the meantime, nothing was saved", edit.original.display_trigger());
+            return;
+        }
+        if edit.trigger.is_empty() || edit.replace.as_ref().is_some_and(|r| r.is_empty()) {
+            self.status = "A match needs a trigger and a replacement".to_string();
+            self.inline_edit = Some(InlineEdit { focus: true, ..edit });
+            return;
+        }
+        self.start_editing(edit.index);
+        if self.regex_mode {
+            self.new_regex = edit.trigger;
+        } else {
+            self.new_trigger = edit.trigger;
+        }
+        if let Some(replace) = edit.replace {
+            self.new_replacement = replace;
+        }
+        self.add_or_update_match();
+    }
+
+    // Matches whose trigger or replacement contain invisible or unnormalized characters
+    fn unicode_audit(&self) -> Vec<(usize, Vec<text::TextIssue>)> {
+        self.matches.iter().enumerate().filter_map(|(index, m)| {
+            let mut issues = text::audit(&m.trigger);
+            for issue in m.aliases.iter().chain([&m.replace]).flat_map(|t| text::audit(t)) {
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+            (!issues.is_empty()).then_some((index, issues))
+        }).collect()
+    }
+
+    fn normalize_match(&mut self, index: usize) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Some(m) = self.matches.get_mut(index) {
+            m.trigger = text::normalize(&m.trigger);
+            m.aliases = m.aliases.iter().map(|a| text::normalize(a)).collect();
+            m.replace = text::normalize(&m.replace);
+            self.save_matches();
+        }
+    }
+
+    fn normalize_all_matches(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        for m in self.matches.iter_mut() {
+            m.trigger = text::normalize(&m.trigger);
+            m.aliases = m.aliases.iter().map(|a| text::normalize(a)).collect();
+            m.replace = text::normalize(&m.replace);
+        }
+        self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::Normalize));
+        self.save_matches();
+    }
+
+    // Scores every plain trigger against the system dictionary, riskiest first
+    fn scan_trigger_risk(&mut self) {
+        let cached = self.dictionary.clone();
+        let matches = self.matches.clone();
+        self.pending_risk_scan = Some(Task::spawn("Trigger risk scan".to_string(), move |control| {
+            let dictionary = cached.unwrap_or_else(|| Arc::new(risk::Dictionary::load_system()));
+            control.set_total(matches.len());
+            let mut findings = Vec::new();
+            for (index, m) in matches.iter().enumerate() {
+                if control.is_cancelled() {
+                    break;
+                }
+                control.advance();
+                if m.regex.is_some() {
+                    continue;
+                }
+                for trigger in m.all_triggers() {
+                    let (risk, reason) = risk::score(&trigger, &dictionary);
+                    if risk > risk::Risk::Low {
+                        findings.push(risk::RiskFinding { index, trigger, risk, reason });
+                    }
+                }
+            }
+            findings.sort_by_key(|f| std::cmp::Reverse(f.risk));
+            Ok((dictionary, findings))
+        }));
+    }
+
+    fn poll_risk_scan(&mut self) {
+        let Some(result) = self.pending_risk_scan.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_risk_scan = None;
+        match result {
+            Ok((dictionary, findings)) => {
+                self.status = if dictionary.is_empty() {
+                    "No hunspell dictionary or word list found, only prefixes are checked".to_string()
+                } else {
+                    format!("{} risky triggers found", findings.len())
+                };
+                self.dictionary = Some(dictionary);
+                self.risk_findings = findings;
+            }
+            Err(error) => self.status = error,
+        }
+    }
+
+    // Picks up files added, removed or written by other programs. The selected file is
+    // reloaded right away unless a save is waiting on the user's decision.
+    fn poll_file_changes(&mut self) {
+        // Safe mode only reads what the user loads by hand
+        if self.safe_mode || self.last_watch.is_some_and(|last| last.elapsed() < WATCH_INTERVAL) {
+            return;
+        }
+        self.last_watch = Some(Instant::now());
+        let files = self.list_yaml_files();
+        let (mut found, mut known) = (files.clone(), self.files.clone());
+        found.sort();
+        known.sort();
+        if found != known {
+            self.files = files;
+            self.last_validation = None;
+            if !self.files.contains(&self.selected_file) && self.tour.is_none() {
+                if self.hold_unsaved_changes("was removed outside the helper") {
+                    return;
+                }
+                self.status = format!("{} was removed outside the helper", self.selected_file);
+                self.selected_file = self.files.first().cloned().unwrap_or_default();
+                self.load_matches();
+                self.reset_editor();
+                return;
+            }
+        }
+        if self.external_change || self.pending_load.is_some() || self.tour.is_some() {
+            return;
+        }
+        if self.current_mtime() != self.loaded_mtime && self.hold_unsaved_changes("changed on disk") {
+            return;
+        }
+        if self.reload_if_changed() {
+            self.last_validation = None;
+            self.status = format!("{} changed on disk and was reloaded", self.selected_file);
+        }
+    }
+
+    // Reloading would drop changes not written yet, held back or under review. Those
+    // stay and the banner offers to reload or overwrite instead; true if there are any.
+    fn hold_unsaved_changes(&mut self, what: &str) -> bool {
+        if !self.unsaved_file {
+            return false;
+        }
+        if !self.external_change {
+            self.external_change = true;
+            self.status = format!("{} {} while it had unsaved changes, reload or overwrite it", self.selected_file, what);
+        }
+        true
+    }
+
+    fn refresh_search_index(&mut self) {
+        if self.pending_search_index.is_some() {
+            return;
+        }
+        self.flush_unsaved();
+        let config_dir = self.config_dir.clone();
+        let files = self.files.clone();
+        self.pending_search_index = Some(Task::spawn("Search index".to_string(), move |control| search::index(&config_dir, &files, control)));
+    }
+
+    fn poll_search_index(&mut self) {
+        let Some(result) = self.pending_search_index.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_search_index = None;
+        match result {
+            Ok(entries) => self.search_index = Some(entries),
+            Err(error) => self.status = error,
+        }
+    }
+
+    // Collects copied text as candidates for new matches while the watcher is on. What
+    // was on the clipboard when it was turned on isn't taken.
+    fn poll_clipboard(&mut self) {
+        if !self.settings.clipboard_watcher {
+            self.last_clipboard_poll = None;
+            return;
+        }
+        if self.last_clipboard_poll.is_some_and(|last| last.elapsed() < CLIPBOARD_INTERVAL) {
+            return;
+        }
+        let current = clipboard::read_text().ok();
+        match (self.last_clipboard_poll, current) {
+            (None, current) => self.clipboard.restart(current),
+            (Some(_), Some(text)) => {
+                self.clipboard.observe(text);
+            }
+            (Some(_), None) => {}
+        }
+        self.last_clipboard_poll = Some(Instant::now());
+    }
+
+    // Starts a new match with `text` as its replacement
+    fn new_match_with_replacement(&mut self, text: String) {
+        if self.tour.is_some() {
+            return;
+        }
+        if self.has_unsaved_edits() {
+            self.status = "Add or clear the match being edited first".to_string();
+            return;
+        }
+        self.reset_editor();
+        self.new_replacement = text;
+        self.focus_new_match = true;
+    }
+
+    fn new_match_from_clipboard(&mut self) {
+        match clipboard::read_text() {
+            Ok(text) if !text.trim().is_empty() => self.new_match_with_replacement(text),
+            Ok(_) => self.status = "The clipboard holds no text".to_string(),
+            Err(error) => self.status = format!("Reading the clipboard failed: {}", error),
+        }
+    }
+
+    // Re-checks every match file in the background every `VALIDATION_INTERVAL`, so
+    // edits made outside the helper show up too. It runs quietly, without an entry
+    // in the activity indicator.
+    fn poll_validation(&mut self) {
+        if let Some(result) = self.pending_validation.as_ref().and_then(|t| t.poll()) {
+            self.pending_validation = None;
+            if let Ok((dictionary, report)) = result {
+                self.dictionary = Some(dictionary);
+                self.problems = Arc::new(report);
+                self.check_health();
+                // The files may have changed since the search read them
+                if self.show_search {
+                    self.refresh_search_index();
+                }
+            }
+        }
+        // Safe mode is for a file that crashes or hangs the helper, so nothing is parsed behind the user's back
+        let due = self.last_validation.is_none_or(|last| last.elapsed() >= VALIDATION_INTERVAL);
+        if due && self.pending_validation.is_none() && !self.safe_mode {
+            self.last_validation = Some(Instant::now());
+            let cached = self.dictionary.clone();
+            let config_dir = self.config_dir.clone();
+            let files = self.files.clone();
+            let rules = self.settings.lint.clone();
+            self.pending_validation = Some(Task::spawn("Validating match files".to_string(), move |control| {
+                let dictionary = cached.unwrap_or_else(|| Arc::new(risk::Dictionary::load_system()));
+                let report = validation::validate(&config_dir, &files, &dictionary, &rules, control);
+                Ok((dictionary, report))
+            }));
+        }
+    }
+
+    // Problems of a match in the selected file, one per line for a tooltip
+    fn match_problems(&self, index: usize) -> Option<(usize, String)> {
+        if self.tour.is_some() {
+            return None;
+        }
+        let problems: Vec<String> = self.problems
+            .for_match(&self.selected_file, index)
+            .map(|p| format!("{}: {}", p.kind.name(), p.message))
+            .collect();
+        (!problems.is_empty()).then(|| (problems.len(), problems.join("\n")))
+    }
+
+    // The espanso directory that holds `match/`, `config/` and usually the images
+    fn espanso_dir(&self) -> PathBuf {
+        self.config_dir.parent().unwrap_or(&self.config_dir).to_path_buf()
+    }
+
+    fn apply_fix(&mut self, index: usize, fix: espanso_config::Fix) {
+        if !self.allow_destructive() {
+            return;
+        }
+        match fix {
+            espanso_config::Fix::ForceClipboard => {
+                if let Some(m) = self.matches.get_mut(index) {
+                    m.force_clipboard = true;
+                    self.save_matches();
+                }
+            }
+            espanso_config::Fix::AutoBackend => self.set_backend(espanso_config::Backend::Auto),
+        }
+    }
+
+    fn set_backend(&mut self, backend: espanso_config::Backend) {
+        if !self.allow_destructive() {
+            return;
+        }
+        self.status = match self.espanso_config.set_backend(backend) {
+            Ok(()) => format!("espanso backend set to {}", backend.name()),
+            Err(error) => format!("Updating {} failed: {}", display_path(&self.espanso_config.path), error),
+        };
+    }
+
+    fn set_show_notifications(&mut self, show: bool) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Err(error) = self.espanso_config.set_show_notifications(show) {
+            self.status = format!("Updating {} failed: {}", display_path(&self.espanso_config.path), error);
+        }
+    }
+
+    fn scan_assets(&mut self) {
+        let root = self.espanso_dir();
+        self.pending_asset_scan = Some(Task::spawn("Image scan".to_string(), move |control| assets::scan(&root, control)));
+    }
+
+    fn poll_asset_scan(&mut self) {
+        let Some(result) = self.pending_asset_scan.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_asset_scan = None;
+        match result {
+            Ok(report) => {
+                self.status = format!("{} unused images, {} missing images", report.orphaned.len(), report.missing.len());
+                self.asset_report = Some(report);
+            }
+            Err(error) => self.status = error,
+        }
+    }
+
+    fn compare_package(&mut self, package: packages::InstalledPackage) {
+        self.package_diff = None;
+        let label = format!("Comparing {} with the hub", package.name);
+        self.pending_package_diff = Some(Task::spawn(label, move |control| packages::compare(package, control)));
+    }
+
+    fn poll_package_diff(&mut self) {
+        let Some(result) = self.pending_package_diff.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_package_diff = None;
+        match result {
+            Ok(diff) => {
+                self.status = format!("{} has {} local changes", diff.package.name, diff.changes.len());
+                self.package_diff = Some(diff);
+            }
+            Err(error) => self.status = format!("Comparing with the hub failed: {}", error),
+        }
+    }
+
+    fn delete_images(&mut self, paths: &[PathBuf]) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let mut deleted = 0;
+        for path in paths {
+            match fs::remove_file(path) {
+                Ok(()) => deleted += 1,
+                Err(error) => self.status = format!("Deleting {} failed: {}", display_path(path), error),
+            }
+        }
+        if let Some(report) = &mut self.asset_report {
+            report.orphaned.retain(|p| p.exists());
+        }
+        if deleted == paths.len() {
+            self.status = format!("Deleted {} unused images", deleted);
+        }
+    }
+
+    fn prefix_trigger(&mut self, index: usize, trigger: &str, prefix: &str) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Some(m) = self.matches.get_mut(index) {
+            for t in std::iter::once(&mut m.trigger).chain(m.aliases.iter_mut()) {
+                if t == trigger {
+                    *t = format!("{}{}", prefix, t);
+                }
+            }
+            self.save_matches();
+            self.find_references(index, trigger, &format!("{}{}", prefix, trigger));
+        }
+        self.risk_findings.retain(|f| f.index != index || f.trigger != trigger);
+    }
+
+    // Turns on word mode for the matches at `indices` where it makes a difference; regex
+    // matches ignore the flag
+    fn enable_word_mode(&mut self, indices: &[usize]) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let mut changed = Vec::new();
+        for &index in indices {
+            if let Some(m) = self.matches.get_mut(index).filter(|m| !m.word && m.regex.is_none()) {
+                m.word = true;
+                changed.push(index);
+            }
+        }
+        if changed.is_empty() {
+            self.status = "Word mode is already on wherever it has an effect".to_string();
+            return;
+        }
+        if self.editing_index.is_some_and(|index| changed.contains(&index)) {
+            self.new_word = true;
+        }
+        // Whole-word matching is exactly what stops a trigger from firing inside words
+        self.risk_findings.retain(|f| f.risk == risk::Risk::High || !changed.contains(&f.index));
+        self.status = format!("Word mode enabled for {} matches", changed.len());
+        self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::WordMode));
+        self.save_matches();
+    }
+
+    // Puts `prefix` before and `suffix` after the replacement of every match in `indices`.
+    // Form and image matches have no replacement text to wrap and are left alone.
+    fn wrap_replacements(&mut self, indices: &[usize], prefix: &str, suffix: &str) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let mut changed = 0;
+        for &index in indices {
+            let Some(m) = self.matches.get(index).filter(|m| !m.replace.is_empty()) else { continue };
+            let wrapped = Match { replace: format!("{}{}{}", prefix, m.replace, suffix), ..m.clone() };
+            if self.tour.is_none() {
+                self.metadata.record_edit(&self.selected_file, Some(m), &wrapped, &now);
+            }
+            if self.editing_index == Some(index) {
+                self.new_replacement = wrapped.replace.clone();
+            }
+            self.matches[index] = wrapped;
+            changed += 1;
+        }
+        if changed == 0 {
+            self.status = "None of the selected matches has a replacement text to wrap".to_string();
+            return;
+        }
+        self.save_metadata();
+        self.bulk_wrap = None;
+        self.status = format!("Wrapped the replacements of {} matches", changed);
+        self.track_usage(|usage| usage.record_bulk(usage::BulkOperation::Wrap));
+        self.save_matches();
+    }
+
+    // Matches with a trigger that is a dictionary word and no word mode yet
+    fn dictionary_word_matches(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .risk_findings
+            .iter()
+            .filter(|f| f.risk == risk::Risk::High && self.matches.get(f.index).is_some_and(|m| !m.word))
+            .map(|f| f.index)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    // Looks for replacements that mention a renamed trigger, e.g. cheat-sheet snippets,
+    // and offers to update them
+    fn find_references(&mut self, renamed: usize, old: &str, new: &str) {
+        let indices: Vec<usize> = self.matches.iter().enumerate()
+            .filter(|(index, m)| *index != renamed && text::replace_whole(&m.replace, old, new) != m.replace)
+            .map(|(index, _)| index)
+            .collect();
+        self.rename_references = (!indices.is_empty()).then(|| RenameReferences {
+            old: old.to_string(),
+            new: new.to_string(),
+            indices,
+        });
+    }
+
+    fn update_references(&mut self) {
+        let Some(references) = self.rename_references.take() else { return };
+        if !self.allow_destructive() {
+            return;
+        }
+        for &index in &references.indices {
+            if let Some(m) = self.matches.get_mut(index) {
+                m.replace = text::replace_whole(&m.replace, &references.old, &references.new);
+            }
+        }
+        self.save_matches();
+        self.status = format!("Updated {} references to {}", references.indices.len(), references.new);
+    }
+
+    // Folds the matches at `aliases` into `canonical` and saves once
+    fn make_aliases(&mut self, canonical: usize, aliases: &[usize]) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let mut aliases = aliases.to_vec();
+        aliases.sort_unstable();
+        aliases.dedup();
+        aliases.retain(|&alias| alias != canonical);
+        // In file order, so the aliases keep it; each merge moves the later matches up by one
+        let mut merged = None;
+        for (removed, alias) in aliases.into_iter().enumerate() {
+            if let Some(index) = aliases::merge_into(&mut self.matches, alias - removed, merged.unwrap_or(canonical)) {
+                merged = Some(index);
+            }
+        }
+        if let Some(canonical) = merged {
+            self.selected_index = Some(canonical);
+            self.selection.clear();
+            self.editing_index = None;
+            self.risk_findings.clear();
+            self.save_matches();
+            self.status = format!("Merged into {}", self.matches[canonical].trigger);
+        }
+    }
+
+    // Folds spellings of one trigger into aliases of the first one; all of them have to
+    // be defined in the selected file
+    fn merge_spellings(&mut self, group: &[String]) {
+        let find = |trigger: &String| self.matches.iter().position(|m| m.all_triggers().contains(trigger));
+        let Some(canonical) = find(&group[0]) else {
+            self.status = format!("Open the file defining {} to merge it", group[0]);
+            return;
+        };
+        let mut aliases = Vec::new();
+        for variant in &group[1..] {
+            let Some(alias) = find(variant) else {
+                self.status = format!("Open the file defining {} to merge it", variant);
+                return;
+            };
+            if alias != canonical {
+                aliases.push(alias);
+            }
+        }
+        self.make_aliases(canonical, &aliases);
+    }
+
+    // Opens the sandbox with the match's trigger typed into some sample text
+    fn try_match(&mut self, index: usize) {
+        if let Some(m) = self.matches.get(index) {
+            self.sandbox_input = sandbox::seed(m);
+            self.show_sandbox = true;
+        }
+    }
+
+    fn detach_alias(&mut self, index: usize, alias: &str) {
+        if !self.allow_destructive() {
+            return;
+        }
+        aliases::detach(&mut self.matches, index, alias);
+        self.editing_index = None;
+        self.risk_findings.clear();
+        self.save_matches();
+    }
+
+    // Default versions of all snippets with platform variants
+    fn platform_defaults(&self) -> Vec<Match> {
+        if self.selected_file == platforms::VARIANTS_FILE {
+            self.matches.clone()
+        } else {
+            read_matches(&self.config_dir.join(platforms::VARIANTS_FILE))
+        }
+    }
+
+    // Platform variants of the match at `index`, loaded once per selection
+    fn platform_drafts(&mut self, index: usize) -> &mut Vec<(platforms::Os, String)> {
+        if self.platform_drafts.as_ref().map(|(i, _)| *i) != Some(index) {
+            let trigger = self.matches.get(index).map(|m| m.trigger.clone()).unwrap_or_default();
+            let variants = platforms::load(&self.config_dir, &self.platform_defaults());
+            self.platform_drafts = Some((index, platforms::for_trigger(&variants, &trigger)));
+        }
+        &mut self.platform_drafts.get_or_insert((index, Vec::new())).1
+    }
+
+    // Writes the drafted variants; the match moves into the variants file if needed
+    fn save_platform_variants(&mut self, index: usize) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let Some((_, drafts)) = self.platform_drafts.take() else { return };
+        let Some(m) = self.matches.get(index).cloned() else { return };
+
+        let defaults = if self.selected_file == platforms::VARIANTS_FILE {
+            self.matches.clone()
+        } else {
+            let variants_path = self.config_dir.join(platforms::VARIANTS_FILE);
+            let mut document = yaml_io::MatchFile::load(&variants_path);
+            let mut defaults = document.matches();
+            if !defaults.iter().any(|d| d.trigger == m.trigger) {
+                defaults.push(m.clone());
+            }
+            if let Err(error) = document.save(&variants_path, &defaults) {
+                return self.report_error(format!("Saving {} failed: {}", platforms::VARIANTS_FILE, error));
+            }
+            self.matches.remove(index);
+            self.selected_index = None;
+            self.selection.clear();
+            self.editing_index = None;
+            self.save_matches();
+            self.files = self.list_yaml_files();
+            defaults
+        };
+
+        let mut variants = platforms::load(&self.config_dir, &defaults);
+        for overrides in variants.values_mut() {
+            overrides.remove(&m.trigger);
+        }
+        for (os, replace) in drafts {
+            variants.entry(os).or_default().insert(m.trigger.clone(), replace);
+        }
+        self.status = match platforms::write(&self.config_dir, &defaults, &variants) {
+            Ok(()) if self.selected_file == platforms::VARIANTS_FILE => format!("Saved platform variants of {}", m.trigger),
+            Ok(()) => format!("Moved {} to {} and saved its platform variants", m.trigger, platforms::VARIANTS_FILE),
+            Err(error) => format!("Saving platform variants failed: {}", error),
+        };
+    }
+
+    // Every trigger in the config directory mapped to the files that define it
+    fn trigger_index(&self) -> HashMap<String, Vec<String>> {
+        build_trigger_index(&self.config_dir, &self.files, &self.selected_file, &self.matches, None)
+    }
+
+    // Reads the import source and indexes the config dir in the background
+    fn preview_import(&mut self) {
+        let path = PathBuf::from(self.import_path.trim());
+        let config_dir = self.config_dir.clone();
+        let files = self.files.clone();
+        let selected_file = self.selected_file.clone();
+        let matches = self.matches.clone();
+        self.pending_import = Some(Task::spawn(format!("Reading {}", path.display()), move |control| {
+            let source = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_lowercase();
+            let read = || fs::read_to_string(&path).map_err(|error| format!("Could not read {}: {}", path.display(), error));
+            // Beeftext exports are JSON too; anything else in JSON is a table of columns
+            let (mut legacy_notes, mut global_vars) = (None, Vec::new());
+            let (candidates, table) = match extension.as_str() {
+                "csv" | "tsv" => {
+                    let bytes = fs::read(&path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+                    let table = table::parse_csv(&bytes).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+                    (Vec::new(), Some(table))
+                }
+                "json" => {
+                    let contents = read()?;
+                    match beeftext::parse(&contents) {
+                        Ok(candidates) => (candidates, None),
+                        Err(error) => {
+                            let table = table::parse_json(&contents).map_err(|_| format!("Could not read {}: {}", path.display(), error))?;
+                            (Vec::new(), Some(table))
+                        }
+                    }
+                }
+                _ => match legacy::convert(&read()?) {
+                    Some(conversion) => {
+                        legacy_notes = Some(conversion.notes);
+                        global_vars = conversion.global_vars;
+                        (conversion.matches, None)
+                    }
+                    None => (read_matches(&path), None),
+                },
+            };
+            let index = build_trigger_index(&config_dir, &files, &selected_file, &matches, Some(control));
+            Ok(match table {
+                Some(table) => {
+                    let mut preview = import::ImportPreview::from_table(source, table, &index);
+                    preview.path = Some(path);
+                    preview
+                }
+                None => {
+                    let mut preview = import::ImportPreview::new(source, candidates, &index);
+                    preview.legacy_notes = legacy_notes;
+                    preview.global_vars = global_vars;
+                    preview
+                }
+            })
+        }));
+    }
+
+    // A file dropped on the window opens in the import preview
+    fn import_dropped_file(&mut self, ctx: &egui::Context) {
+        let Some(path) = ctx.input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone())) else { return };
+        self.import_path = path.display().to_string();
+        self.show_import = true;
+        self.preview_import();
+    }
+
+    fn poll_import_preview(&mut self) {
+        let Some(result) = self.pending_import.as_ref().and_then(|t| t.poll()) else { return };
+        self.pending_import = None;
+        match result {
+            Ok(preview) => self.import_preview = Some(preview),
+            Err(error) => self.status = error,
+        }
+    }
+
+    // Asks `espanso status` for the daemon state every `daemon_poll_interval_secs` (0 disables it)
+    fn poll_daemon(&mut self) {
+        if let Some(state) = self.pending_daemon_status.as_ref().and_then(|t| t.poll()) {
+            self.pending_daemon_status = None;
+            self.daemon_state = state.unwrap_or(espanso::DaemonState::Unknown);
+            self.track_daemon_uptime();
+        }
+        if let Some(result) = self.pending_daemon_restart.as_ref().and_then(|t| t.poll()) {
+            self.pending_daemon_restart = None;
+            self.status = match result {
+                Ok(()) => "espanso restarted".to_string(),
+                Err(error) => format!("Restarting espanso failed: {}", error),
+            };
+            self.last_daemon_poll = None;
+        }
+
+        let interval = Duration::from_secs(self.settings.daemon_poll_interval_secs);
+        let due = self.last_daemon_poll.is_none_or(|last| last.elapsed() >= interval);
+        if self.settings.daemon_poll_interval_secs > 0 && due && self.pending_daemon_status.is_none() {
+            self.last_daemon_poll = Some(Instant::now());
+            self.pending_daemon_status = Some(Task::spawn("espanso status".to_string(), |_| Ok(espanso::status())));
+        }
+    }
+
+    // Counts toward the usage insights, if the user turned them on
+    fn track_usage(&mut self, record: impl FnOnce(&mut usage::Usage)) {
+        if !self.settings.usage_insights || self.safe_mode || self.tour.is_some() {
+            return;
+        }
+        record(&mut self.usage);
+        if let Err(error) = self.usage.save() {
+            log::warn!("Saving the usage insights failed: {}", error);
+        }
+    }
+
+    // Remembers since when the daemon runs, for the weekly summary
+    fn track_daemon_uptime(&mut self) {
+        let running = match self.daemon_state {
+            espanso::DaemonState::Running => true,
+            espanso::DaemonState::Stopped => false,
+            _ => return,
+        };
+        if self.settings.health_summary && self.health.track_daemon(running) {
+            if let Err(error) = self.health.save() {
+                log::warn!("Saving the health log failed: {}", error);
+            }
+        }
+    }
+
+    // Shows the weekly summary once a week after validation; the first time only
+    // records what to compare the next one against
+    fn check_health(&mut self) {
+        if self.safe_mode || !self.settings.health_summary || self.tour.is_some() || self.health_summary.is_some() || !self.health.due() {
+            return;
+        }
+        let files = health::stats(&self.config_dir, &self.files);
+        let duplicates = health::duplicates(&self.problems);
+        match self.health.summarize(&files, &duplicates) {
+            Some(summary) => self.health_summary = Some(summary),
+            None => self.record_health(),
+        }
+    }
+
+    // Takes the folder as it is now as the baseline for next week's summary
+    fn record_health(&mut self) {
+        self.health_summary = None;
+        self.health.record(health::stats(&self.config_dir, &self.files), health::duplicates(&self.problems));
+        if let Err(error) = self.health.save() {
+            log::warn!("Saving the health log failed: {}", error);
+        }
+    }
+
+    fn restart_daemon(&mut self) {
+        self.pending_daemon_restart = Some(Task::spawn("Restarting espanso".to_string(), |_| espanso::restart()));
+    }
+
+    // Keeps every status message of the session with a timestamp, for diagnostics
+    fn log_status(&mut self) {
+        let last = self.session_log.last().and_then(|l| l.split_once(' ')).map(|(_, m)| m);
+        if !self.status.is_empty() && last != Some(self.status.as_str()) {
+            self.session_log.push(format!("{} {}", chrono::Local::now().format("%H:%M:%S"), self.status));
+            log::info!("{}", self.status);
+        }
+    }
+
+    fn preview_backup(&mut self, backup: backups::Backup) {
+        let current = fs::read_to_string(self.config_dir.join(&self.selected_file)).unwrap_or_default();
+        match fs::read_to_string(&backup.path) {
+            Ok(contents) => self.backup_preview = Some((backup, text::line_diff(&contents, &current))),
+            Err(error) => self.status = format!("Reading {} failed: {}", display_path(&backup.path), error),
+        }
+    }
+
+    // Puts a backup in place of the selected file; the current version is backed up
+    // first so the restore can be undone the same way
+    fn restore_backup(&mut self, backup: &backups::Backup) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let path = self.config_dir.join(&self.selected_file);
+        let restored = backups::create(&self.config_dir, &self.selected_file, self.settings.backup_count.max(1)).and_then(|()| fs::copy(&backup.path, &path));
+        if let Err(error) = restored {
+            self.status = format!("Restoring the backup of {} failed: {}", backup.taken, error);
+            return;
+        }
+        self.backup_preview = None;
+        self.show_backups = false;
+        self.load_matches();
+        self.reset_editor();
+        self.last_validation = None;
+        self.status = format!("Restored {} from the backup of {}", self.selected_file, backup.taken);
+    }
+
+    fn preview_locale(&self) -> String {
+        if self.settings.date_locale.is_empty() {
+            locale::system()
+        } else {
+            self.settings.date_locale.clone()
+        }
+    }
+
+    // Runs the tray in a process of its own, so it outlives this window
+    #[cfg(all(feature = "tray", target_os = "linux"))]
+    fn start_tray(&mut self) {
+        let started = std::env::current_exe().and_then(|exe| std::process::Command::new(exe).arg("--tray").spawn());
+        self.status = match started {
+            Ok(mut tray) => {
+                // Its errors, like a shortcut taken by another app, end up in the log
+                std::thread::spawn(move || tray.wait());
+                format!("Tray started, {} opens quick add", self.settings.quick_add_shortcut)
+            }
+            Err(error) => format!("Starting the tray failed: {}", error),
+        };
+    }
+
+    // Options of this session in `name: value` form
+    fn settings_summary(&self) -> String {
+        format!(
+            "config_dir: {}\nsafe_mode: {}\nyaml_indent: {}\ntheme: {}\nautosave: {}\nkeep_typography: {}\ndaemon_poll_interval_secs: {}\ncheck_updates_on_startup: {}\nrestart_after_save: {}\nbackup_count: {}\ndate_locale: {}\n",
+            display_path(&self.config_dir),
+            self.safe_mode,
+            self.settings.yaml_indent,
+            self.settings.theme.label(),
+            self.settings.autosave,
+            self.settings.keep_typography,
+            self.settings.daemon_poll_interval_secs,
+            self.settings.check_updates_on_startup,
+            self.settings.restart_after_save,
+            self.settings.backup_count,
+            self.settings.date_locale
+        )
+    }
+
+    fn export_diagnostics(&mut self) {
+        self.status = match diagnostics::export_bundle(&self.config_dir, &self.files, &self.session_log, &self.settings_summary()) {
+            Ok(path) => format!("Diagnostic bundle written to {}", path.display()),
+            Err(error) => format!("Exporting the diagnostic bundle failed: {}", error),
+        };
+    }
+
+    // Everything currently running in the background, for the activity indicator
+    fn running_tasks(&self) -> Vec<&dyn RunningTask> {
+        let mut running: Vec<&dyn RunningTask> = Vec::new();
+        if let Some(task) = &self.pending_load {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_fetch {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_import {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_risk_scan {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_asset_scan {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_package_diff {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_update_check {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_update_download {
+            running.push(task);
+        }
+        if let Some(task) = &self.pending_daemon_restart {
+            running.push(task);
+        }
+        running
+    }
+
+    fn fetch_import(&mut self) {
+        let url = remote::raw_url(&self.import_url);
+        self.status = format!("Downloading {}…", url);
+        self.pending_fetch = Some(remote::spawn_fetch(url));
+    }
+
+    fn poll_import_fetch(&mut self) {
+        let Some(fetch) = &self.pending_fetch else { return };
+        let Some(result) = fetch.poll() else { return };
+        let url = fetch.label.clone();
+        self.pending_fetch = None;
+        match result {
+            Ok(contents) => {
+                let candidates = yaml_io::MatchFile::parse(&contents).matches();
+                let source = url.rsplit('/').next().unwrap_or_default().to_string();
+                self.status = format!("Downloaded {} matches from {}", candidates.len(), url);
+                self.import_preview = Some(import::ImportPreview::new(source, candidates, &self.trigger_index()));
+            }
+            Err(error) => self.status = format!("Download of {} failed: {}", url, error),
+        }
+    }
+
+    fn commit_import(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if let Some(preview) = self.import_preview.take() {
+            if let Some(generator) = preview.generator().filter(|_| preview.generated) {
+                self.matches = preview.candidates.clone();
+                self.add_tags(&preview.tags, &preview.candidates);
+                self.document.header = generated::mark(&self.document.header, &generator);
+                self.reset_editor();
+                self.status = format!("Generated {} matches from {}", self.matches.len(), preview.source);
+                self.save_matches();
+                self.import_path.clear();
+                self.show_import = false;
+                return;
+            }
+            let (updated, added) = preview.apply(&mut self.matches);
+            self.add_tags(&preview.tags, &preview.candidates);
+            self.status = format!("Imported {} matches from {}, {} existing matches updated", added, preview.source, updated);
+            let defined = global_vars::definitions(&self.config_dir, &self.files);
+            let (known, new): (Vec<Var>, Vec<Var>) = preview.global_vars.into_iter().partition(|var| defined.iter().any(|d| d.name == var.name));
+            let vars_added = self.document.add_global_vars(&new);
+            if vars_added {
+                self.status.push_str(&format!(", global variables added: {}", new.iter().map(|var| var.name.as_str()).collect::<Vec<_>>().join(", ")));
+            }
+            if !known.is_empty() {
+                self.status.push_str(&format!("; already defined, check they fit: {}", known.iter().map(|var| var.name.as_str()).collect::<Vec<_>>().join(", ")));
+            }
+            self.save_matches();
+            if vars_added {
+                self.refresh_global_vars();
+            }
+            self.import_path.clear();
+            self.show_import = false;
+        }
+    }
+
+    // Tags from a table for the matches that made it into the file, not skipped collisions
+    fn add_tags(&mut self, tags: &HashMap<String, Vec<String>>, candidates: &[Match]) {
+        if tags.is_empty() {
+            return;
+        }
+        for m in self.matches.iter().filter(|m| candidates.iter().any(|c| c.trigger == m.trigger && c.replace == m.replace)) {
+            let Some(tags) = tags.get(&m.trigger) else { continue };
+            let meta = self.metadata.get_mut(&self.selected_file, m);
+            for tag in tags {
+                if !meta.tags.contains(tag) {
+                    meta.tags.push(tag.clone());
+                }
+            }
+        }
+        self.save_metadata();
+    }
+
+    fn file_has_tags(&self) -> bool {
+        self.metadata.entries.values().any(|entry| entry.file == self.selected_file && !entry.meta.tags.is_empty())
+    }
+
+    // Adds `tag` to the matches dropped on its header in the grouped list
+    fn tag_matches(&mut self, indices: &[usize], tag: &str) {
+        if self.tour.is_some() {
+            self.status = "Tour: sample matches can't be tagged".to_string();
+            return;
+        }
+        let mut tagged = 0;
+        for m in indices.iter().filter_map(|&i| self.matches.get(i)) {
+            let meta = self.metadata.get_mut(&self.selected_file, m);
+            if !meta.tags.iter().any(|t| t == tag) {
+                meta.tags.push(tag.to_string());
+                tagged += 1;
+            }
+        }
+        if tagged == 0 {
+            self.status = format!("Already tagged {}", tag);
+            return;
+        }
+        self.save_metadata();
+        self.status = format!("Tagged {} matches with {}", tagged, tag);
+    }
+
+    // Runs the generator recorded in the selected file's header again, replacing its matches
+    fn regenerate(&mut self) {
+        if !self.allow_destructive() {
+            return;
+        }
+        let Some(generator) = generated::read(&self.document.header) else { return };
+        match generator.run() {
+            Ok(output) => {
+                self.matches = output.matches;
+                let matches = self.matches.clone();
+                self.add_tags(&output.tags, &matches);
+                self.reset_editor();
+                self.status = format!("Regenerated {} matches from {}", self.matches.len(), generator.describe());
+                self.save_matches();
+            }
+            Err(error) => self.report_error(format!("Regenerating {} failed: {}", self.selected_file, error)),
+        }
+    }
+
+    fn open_config_folder(&mut self) {
+        if let Err(error) = open_in_system(&self.config_dir.to_string_lossy()) {
+            self.status = format!("Opening {} failed: {}", display_path(&self.config_dir), error);
+        }
+    }
+
+    fn check_for_updates(&mut self) {
+        self.status = "Checking for updates…".to_string();
+        self.pending_update_check = Some(Task::spawn("Update check".to_string(), |_| update_check::latest_release()));
+    }
+
+    fn download_update(&mut self) {
+        if let Some(release) = self.available_update.clone() {
+            let Some(url) = release.asset_url else { return };
+            self.status = format!("Downloading {}…", url);
+            self.pending_update_download = Some(Task::spawn(format!("Download {}", url), move |_| update_check::download_asset(&url, release.asset_sha256.as_deref())));
+        }
+    }
+
+    fn poll_update_tasks(&mut self) {
+        if let Some(result) = self.pending_update_check.as_ref().and_then(|p| p.poll()) {
+            self.pending_update_check = None;
+            match result {
+                Ok(release) if release.is_newer_than_running() => {
+                    self.status = format!("Espanso Helper {} is available", release.version);
+                    self.available_update = Some(release);
+                }
+                Ok(_) => self.status = "Espanso Helper is up to date".to_string(),
+                Err(error) => self.status = format!("Update check failed: {}", error),
+            }
+        }
+        if let Some(result) = self.pending_update_download.as_ref().and_then(|p| p.poll()) {
+            self.pending_update_download = None;
+            self.status = match result {
+                Ok(path) => format!("Downloaded the new version to {}", path.display()),
+                Err(error) => format!("Download failed: {}", error),
+            };
+        }
+    }
+
+    // The replacement alone in the whole window, for writing long templates
+    fn show_focus_editor(&mut self, ctx: &egui::Context) {
+        let Some(mut draft) = self.focus_editor.take() else { return };
+        let mut close = None;
+        egui::TopBottomPanel::top("focus_editor_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let title = match self.editing_index.and_then(|i| self.matches.get(i)) {
+                    Some(m) => format!("Replacement of {}", m.display_trigger()),
+                    None => "Replacement of the new match".to_string(),
+                };
+                ui.strong(title);
+                ui.weak(format!("{} characters, {} lines", draft.chars().count(), draft.lines().count()));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Cancel").on_hover_text("Esc").clicked() {
+                        close = Some(false);
+                    }
+                    if ui.button("Save").on_hover_text("Ctrl+Enter").clicked() {
+                        close = Some(true);
+                    }
+                });
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().id_source("focus_editor_scroll").show(ui, |ui| {
+                let rows = (ui.available_height() / ui.text_style_height(&egui::TextStyle::Body)) as usize;
+                let editor = egui::TextEdit::multiline(&mut draft).id(egui::Id::new("focus_editor")).desired_width(f32::INFINITY).desired_rows(rows);
+                let response = ui.add(editor);
+                if ctx.memory(|memory| memory.focus().is_none()) {
+                    response.request_focus();
+                }
+            });
+        });
+        self.focus_editor = Some(draft);
+        if let Some(save) = close {
+            self.close_focus_editor(save);
+        }
+    }
+
+    fn close_focus_editor(&mut self, save: bool) {
+        let Some(draft) = self.focus_editor.take() else { return };
+        if !save {
+            return;
+        }
+        self.new_replacement = draft;
+        // An existing match is saved right away; a new one still needs its trigger
+        if self.editing_index.is_some() {
+            self.add_or_update_match();
+        }
+    }
+
+    // Fixed keys, after the user's own shortcuts had their chance
+    fn builtin_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.recording {
+            return;
+        }
+        let pressed = |key| ctx.input_mut(|input| input.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, key)));
+        if pressed(egui::Key::S) {
+            self.flush_unsaved();
+        }
+        if pressed(egui::Key::PlusEquals) {
+            self.settings.zoom = ((self.settings.zoom + 0.1) * 10.0).round() / 10.0;
+        }
+        if pressed(egui::Key::Minus) {
+            self.settings.zoom = ((self.settings.zoom - 0.1) * 10.0).round() / 10.0;
+        }
+        if pressed(egui::Key::Num0) {
+            self.settings.zoom = 1.0;
+        }
+        self.settings.zoom = self.settings.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if self.focus_editor.is_some() {
+            if pressed(egui::Key::Enter) {
+                self.close_focus_editor(true);
+            } else if ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.close_focus_editor(false);
+            }
+            return;
+        }
+        // The whole new match form works from the keyboard: Ctrl+N to start, Tab between
+        // fields, Space for checkboxes and Ctrl+Enter to add
+        if pressed(egui::Key::N) && self.tour.is_none() {
+            if self.editing_index.is_some() && !self.has_unsaved_edits() {
+                self.reset_editor();
+            }
+            self.focus_new_match = true;
+        }
+        if pressed(egui::Key::Enter) && self.tour.is_none() && self.raw_yaml.is_none() {
+            self.add_or_update_match();
+        }
+        if pressed(egui::Key::F) {
+            self.focus_filter = true;
+        }
+        if ctx.input_mut(|input| input.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::F))) {
+            self.show_search = true;
+            self.focus_search = true;
+        }
+        // Plain keys work the list while no text field has the focus; Escape leaves one
+        if self.tour.is_some() || self.pending_delete.is_some() || ctx.memory(|memory| memory.focus().is_some()) {
+            return;
+        }
+        let key = |key| ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, key));
+        if key(egui::Key::ArrowUp) {
+            self.move_selection(-1);
+        }
+        if key(egui::Key::ArrowDown) {
+            self.move_selection(1);
+        }
+        if key(egui::Key::Enter) {
+            if let Some(index) = self.selected_index {
+                self.start_editing(index);
+            }
+        }
+        if key(egui::Key::Delete) {
+            if self.selection.len() > 1 {
+                self.confirm_bulk_delete = true;
+            } else if let Some(index) = self.selected_index {
+                self.confirm_delete(index);
+            }
+        }
+    }
+
+    // Selects the next (1) or previous (-1) match in the list as it is shown
+    fn move_selection(&mut self, step: isize) {
+        let mut visible = self.filtered_matches();
+        self.sort_matches(&mut visible);
+        let Some(last) = visible.len().checked_sub(1) else { return };
+        let position = match self.selected_index.and_then(|selected| visible.iter().position(|&i| i == selected)) {
+            Some(position) => position.saturating_add_signed(step).min(last),
+            None if step > 0 => 0,
+            None => last,
+        };
+        self.selected_index = Some(visible[position]);
+        self.selection = BTreeSet::from([visible[position]]);
+        self.confirm_bulk_delete = false;
+        self.scroll_to_selected = true;
+    }
+
+    fn run_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.recording {
+            return;
+        }
+        for shortcut in self.shortcuts.clone() {
+            let Some(keys) = shortcut.parse_keys() else { continue };
+            if !ctx.input_mut(|input| input.consume_shortcut(&keys)) {
+                continue;
+            }
+            match shortcut.action {
+                shortcuts::ShortcutAction::InsertText => self.insert_into_replacement(ctx, &shortcut.argument),
+                shortcuts::ShortcutAction::MoveToFile => match self.selected_index {
+                    Some(index) => self.transfer_matches(&[index], &shortcut.argument, false),
+                    None => self.status = "Select a match to move first".to_string(),
+                },
+            }
+        }
+    }
+
+    // Inserts at the replacement editor's cursor, or appends when it was never focused
+    fn insert_into_replacement(&mut self, ctx: &egui::Context, inserted: &str) {
+        let id = egui::Id::new("new_replacement");
+        let mut state = egui::widgets::text_edit::TextEditState::load(ctx, id).unwrap_or_default();
+        let char_count = self.new_replacement.chars().count();
+        let cursor = state.ccursor_range().map_or(char_count, |range| range.primary.index.min(char_count));
+        let byte_index = self.new_replacement.char_indices().nth(cursor).map_or(self.new_replacement.len(), |(i, _)| i);
+        self.new_replacement.insert_str(byte_index, inserted);
+        let after = egui::text::CCursor::new(cursor + inserted.chars().count());
+        state.set_ccursor_range(Some(egui::text::CCursorRange::one(after)));
+        state.store(ctx, id);
+        ctx.memory_mut(|memory| memory.request_focus(id));
+    }
+
+    // Moves or copies the matches at `indices` to the end of `file`. Nothing is written
+    // unless both files can be saved; the destination goes first so a failure never
+    // loses matches.
+    fn transfer_matches(&mut self, indices: &[usize], file: &str, copy: bool) {
+        if !self.allow_destructive() {
+            return;
+        }
+        if !self.files.iter().any(|f| f == file) {
+            self.status = format!("{} is not a match file", file);
+            return;
+        }
+        let indices: BTreeSet<usize> = indices.iter().copied().filter(|&i| i < self.matches.len()).collect();
+        if file == self.selected_file || indices.is_empty() {
+            return;
+        }
+        if self.tour.is_some() {
+            self.status = "Tour: sample matches can't be moved to your files".to_string();
+            return;
+        }
+        if let Some(reason) = self.save_blocker() {
+            self.status = reason;
+            return;
+        }
+        if !copy && self.current_mtime() != self.loaded_mtime {
+            self.external_change = true;
+            self.status = format!("{} changed on disk since it was loaded, reload or overwrite it", self.selected_file);
+            return;
+        }
+        let target_path = self.config_dir.join(file);
+        let mut target = yaml_io::MatchFile::load(&target_path);
+        let mut target_matches = target.matches();
+        let transferred: Vec<Match> = indices.iter().map(|&i| self.matches[i].clone()).collect();
+        target_matches.extend(transferred.iter().cloned());
+        if let Err(error) = target.save(&target_path, &target_matches) {
+            return self.report_error(format!("Saving {} failed: {}", file, error));
+        }
+        let subject = match transferred.as_slice() {
+            [single] => single.display_trigger(),
+            _ => format!("{} matches", transferred.len()),
+        };
+        let operation = if copy { usage::BulkOperation::Copy } else { usage::BulkOperation::Move };
+        self.track_usage(|usage| {
+            usage.record_edit(file);
+            if transferred.len() > 1 {
+                usage.record_bulk(operation);
+            }
+        });
+        if copy {
+            self.status = format!("Copied {} to {}", subject, file);
+            self.last_validation = None;
+            return;
+        }
+        for m in &transferred {
+            self.metadata.record_move(file, m);
+        }
+        self.save_metadata();
+        let mut index = 0;
+        self.matches.retain(|_| {
+            index += 1;
+            !indices.contains(&(index - 1))
+        });
+        self.status = format!("Moved {} to {}", subject, file);
+        self.selected_index = None;
+        self.selection.clear();
+        self.editing_index = None;
+        self.rename_references = None;
+        self.risk_findings.clear();
+        self.save_matches();
+    }
+
+    // While recording, keyboard input goes straight into the replacement; widgets never
+    // see it, so Tab and Enter end up in the text instead of moving focus or submitting
+    fn record_keystrokes(&mut self, ctx: &egui::Context) {
+        if !self.recording {
+            return;
+        }
+        ctx.input_mut(|input| {
+            input.events.retain(|event| match event {
+                egui::Event::Text(text) | egui::Event::Paste(text) => {
+                    self.new_replacement.push_str(text);
+                    false
+                }
+                egui::Event::Key { key, pressed, .. } => {
+                    if *pressed {
+                        match key {
+                            egui::Key::Enter => self.new_replacement.push('\n'),
+                            egui::Key::Tab => self.new_replacement.push('\t'),
+                            egui::Key::Backspace => {
+                                self.new_replacement.pop();
+                            }
+                            _ => {}
+                        }
+                    }
+                    false
+                }
+                egui::Event::Copy | egui::Event::Cut => false,
+                _ => true,
+            });
+        });
+    }
+
+    // Rewrites clipboard pastes aimed at the replacement field into plain text
+    fn clean_replacement_paste(&self, ctx: &egui::Context, editor_id: egui::Id) {
+        if !ctx.memory(|m| m.has_focus(editor_id)) {
+            return;
+        }
+        ctx.input_mut(|input| {
+            for event in input.events.iter_mut() {
+                if let egui::Event::Paste(pasted) = event {
+                    *pasted = text::to_plain_text(pasted, self.settings.keep_typography);
+                }
+            }
+        });
+    }
+}
+
+impl eframe::App for EspansoHelper {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let info = frame.info();
+        if self.applied_theme != Some(self.settings.theme) {
+            let visuals = match self.settings.theme {
+                settings::Theme::System => info.system_theme.unwrap_or(eframe::Theme::Dark).egui_visuals(),
+                settings::Theme::Light => egui::Visuals::light(),
+                settings::Theme::Dark => egui::Visuals::dark(),
+            };
+            ctx.set_visuals(visuals);
+            self.applied_theme = Some(self.settings.theme);
+        }
+        match (self.focus_editor.is_some(), self.restore_maximized) {
+            (true, None) => {
+                self.restore_maximized = Some(info.window_info.maximized);
+                frame.set_maximized(true);
+            }
+            (false, Some(maximized)) => {
+                frame.set_maximized(maximized);
+                self.restore_maximized = None;
+            }
+            _ => {}
+        }
+        if self.applied_zoom != Some(self.settings.zoom) {
+            ctx.set_pixels_per_point(info.native_pixels_per_point.unwrap_or(1.0) * self.settings.zoom);
+            self.applied_zoom = Some(self.settings.zoom);
+        }
+        let window = info.window_info;
+        if let Some(position) = window.position.filter(|_| !window.minimized && !window.maximized && !window.fullscreen) {
+            self.window_geometry =
+                Some(settings::WindowGeometry { x: position.x, y: position.y, width: window.size.x, height: window.size.y });
+        }
+        self.show(ctx);
+        self.save_settings();
+    }
+
+    fn on_close_event(&mut self) -> bool {
+        if !self.flush_unsaved() {
+            // Closing now would lose them; the status says what is in the way
+            self.error_dialog = Some(format!("{} has unsaved changes. {}", self.selected_file, self.status));
+            return false;
+        }
+        if self.window_geometry.is_some() {
+            self.settings.window = self.window_geometry;
+        }
+        self.save_settings();
+        true
+    }
+}
+
+impl EspansoHelper {
+    // One frame of the whole window; kept apart from `update` so the UI snapshot tests
+    // can render it without a native window
+    fn show(&mut self, ctx: &egui::Context) {
+        yaml_io::set_indent(self.settings.yaml_indent);
+        self.poll_load();
+        self.poll_import_fetch();
+        self.poll_import_preview();
+        self.poll_risk_scan();
+        self.poll_asset_scan();
+        self.poll_package_diff();
+        self.poll_update_tasks();
+        self.poll_daemon();
+        self.poll_validation();
+        self.poll_file_changes();
+        self.poll_clipboard();
+        self.poll_search_index();
+        self.record_keystrokes(ctx);
+        self.run_shortcuts(ctx);
+        self.builtin_shortcuts(ctx);
+        self.import_dropped_file(ctx);
+        self.log_status();
+        if !self.running_tasks().is_empty() || self.pending_daemon_status.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else if self.settings.daemon_poll_interval_secs > 0 {
+            ctx.request_repaint_after(Duration::from_secs(self.settings.daemon_poll_interval_secs));
+        }
+        ctx.request_repaint_after(if self.pending_validation.is_some() { Duration::from_millis(100) } else { WATCH_INTERVAL });
+        if self.settings.clipboard_watcher {
+            ctx.request_repaint_after(CLIPBOARD_INTERVAL);
+        }
+        let tour = self.tour.clone();
+        if self.focus_editor.is_some() {
+            self.show_focus_editor(ctx);
+            return;
+        }
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let status = ui.label(&self.status);
+                tour_highlight(ctx, tour.as_ref(), tour::TourStep::Saving, status.rect);
+                if let Some(summary) = self.selection_summary() {
+                    ui.separator();
+                    ui.label(summary);
+                }
+                for task in self.running_tasks() {
+                    ui.separator();
+                    ui.label(task.label());
+                    match task.progress() {
+                        Some(progress) => {
+                            ui.add(egui::ProgressBar::new(progress).desired_width(120.0).show_percentage());
+                        }
+                        None => {
+                            ui.spinner();
+                        }
+                    }
+                    if ui.small_button("Cancel").clicked() {
+                        task.cancel();
+                    }
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.menu_button("Settings", |ui| {
+                        let system = locale::system();
+                        ui.label(format!("Match folder: {}", display_path(&self.config_dir)));
+                        ui.horizontal(|ui| {
+                            if ui.button("Browse…").clicked() {
+                                let dir = self.config_dir.clone();
+                                self.folder_picker = Some((dir.clone(), dir.display().to_string()));
+                                ui.close_menu();
+                            }
+                            if ui.add_enabled(self.settings.config_dir.is_some(), egui::Button::new("Use espanso's Folder")).clicked() {
+                                self.set_config_dir(None);
+                            }
+                            if ui.button("Detect Again").clicked() {
+                                self.detected_config_dir = default_config_dir();
+                                if self.settings.config_dir.is_none() && self.detected_config_dir != self.config_dir {
+                                    self.set_config_dir(None);
+                                }
+                            }
+                        });
+                        ui.weak(format!("espanso's folder: {}", display_path(&self.detected_config_dir)));
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("YAML indent");
+                            ui.add(egui::DragValue::new(&mut self.settings.yaml_indent).clamp_range(2..=8).suffix(" spaces"));
+                        });
+                        ui.weak("Used for entries the helper writes; others keep theirs.");
+                        let autosave = ui.checkbox(&mut self.settings.autosave, "Save every change right away");
+                        if !self.settings.autosave {
+                            ui.weak("Changes are saved with Ctrl+S, when switching files and on exit.");
+                        } else if autosave.changed() {
+                            self.flush_unsaved();
+                        }
+                        ui.checkbox(&mut self.settings.check_updates_on_startup, "Check for updates on startup");
+                        ui.checkbox(&mut self.settings.health_summary, "Weekly summary of the match folder")
+                            .on_hover_text("Grown files, new duplicate triggers, backups taken and espanso's uptime since the last summary");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.settings.usage_insights, "Keep usage insights")
+                                .on_hover_text("Counts edits per file and bulk operations on this computer; nothing is ever sent");
+                            if ui.button("Show…").clicked() {
+                                self.show_usage = true;
+                                ui.close_menu();
+                            }
+                        });
+                        if ui.button("Lint Rules…").clicked() {
+                            self.show_lint = true;
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Date previews in");
+                            let shown = if self.settings.date_locale.is_empty() { format!("System ({})", system) } else { self.settings.date_locale.clone() };
+                            egui::ComboBox::from_id_source("date_locale").selected_text(shown).show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.settings.date_locale, String::new(), format!("System ({})", system));
+                                for known in locale::KNOWN {
+                                    ui.selectable_value(&mut self.settings.date_locale, known.to_string(), *known);
+                                }
+                            });
+                        });
+                        ui.weak("Date variables with their own locale keep it.");
+                        #[cfg(all(feature = "tray", target_os = "linux"))]
+                        {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                ui.label("Quick add shortcut");
+                                ui.add(egui::TextEdit::singleline(&mut self.settings.quick_add_shortcut).desired_width(140.0));
+                                if ui.button("Start Tray").on_hover_text("Tray icon and shortcut for adding a match from any app").clicked() {
+                                    self.start_tray();
+                                    ui.close_menu();
+                                }
+                            });
+                            ui.weak("Start the helper with --tray at login to keep both around.");
+                        }
+                    });
+                    ui.menu_button("View", |ui| {
+                        ui.label("Theme");
+                        for theme in settings::Theme::ALL {
+                            ui.radio_value(&mut self.settings.theme, theme, theme.label());
+                        }
+                        ui.separator();
+                        ui.label("Interface scale");
+                        let mut percent = (self.settings.zoom * 100.0).round();
+                        let slider = egui::Slider::new(&mut percent, MIN_ZOOM * 100.0..=MAX_ZOOM * 100.0).step_by(10.0).suffix(" %");
+                        if ui.add(slider).changed() {
+                            self.settings.zoom = percent / 100.0;
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Reset").clicked() {
+                                self.settings.zoom = 1.0;
+                            }
+                            ui.weak("Ctrl+Plus, Ctrl+Minus, Ctrl+0");
+                        });
+                    });
+                    ui.menu_button("Daemon", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Check espanso every");
+                            ui.add(egui::DragValue::new(&mut self.settings.daemon_poll_interval_secs).clamp_range(0..=3600).suffix(" s"));
+                        });
+                        ui.label("Set to 0 to stop checking.");
+                        if ui.button("Check Now").clicked() {
+                            self.last_daemon_poll = None;
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        let restarting = self.pending_daemon_restart.is_some();
+                        if ui.add_enabled(!restarting, egui::Button::new("Restart espanso")).clicked() {
+                            self.restart_daemon();
+                            ui.close_menu();
+                        }
+                        ui.checkbox(&mut self.settings.restart_after_save, "Restart automatically after saving");
+                        ui.separator();
+                        let current = self.espanso_config.backend;
+                        ui.horizontal(|ui| {
+                            ui.label("Backend:");
+                            for backend in espanso_config::Backend::ALL {
+                                if ui.radio(current == backend, backend.name()).clicked() && current != backend {
+                                    self.set_backend(backend);
+                                }
+                            }
+                        });
+                        let mut show_notifications = self.espanso_config.show_notifications;
+                        if ui.checkbox(&mut show_notifications, "Show espanso notifications").changed() {
+                            self.set_show_notifications(show_notifications);
+                        }
+                    });
+                    if ui.small_button("Export Diagnostic Bundle").on_hover_text("Logs, settings and file statistics for bug reports. Triggers, file names and your home folder are replaced in the logs; other text you typed may still be in them.").clicked() {
+                        self.export_diagnostics();
+                    }
+                    match &self.daemon_state {
+                        espanso::DaemonState::Stopped => {
+                            if self.pending_daemon_restart.is_none() && ui.small_button("Restart").clicked() {
+                                self.restart_daemon();
+                            }
+                            ui.colored_label(egui::Color32::RED, "⚠ espanso is not running");
+                        }
+                        espanso::DaemonState::Unavailable(error) => {
+                            ui.weak("espanso CLI not found").on_hover_text(error);
+                        }
+                        espanso::DaemonState::Running | espanso::DaemonState::Unknown => {}
+                    }
+                    if let Some(release) = &self.available_update {
+                        if !release.page_url.is_empty() && ui.small_button("Release Notes").clicked() {
+                            if let Err(error) = open_in_system(&release.page_url) {
+                                self.status = format!("Opening {} failed: {}", release.page_url, error);
+                            }
+                        }
+                        if release.asset_url.is_some() && release.asset_sha256.is_some()
+                            && self.pending_update_download.is_none()
+                            && ui.small_button(format!("Download {}", release.version)).clicked()
+                        {
+                            self.download_update();
+                        }
+                    } else if self.pending_update_check.is_none() && ui.small_button("Check for Updates").clicked() {
+                        self.check_for_updates();
+                    }
+                });
+            });
+        });
+
+        egui::SidePanel::left("file_sidebar").resizable(true).default_width(150.0).show(ctx, |ui| {
+            ui.heading("Files");
+            let files = self.shown_files();
+            if self.files.iter().any(|file| is_included_only(file)) {
+                ui.checkbox(&mut self.settings.hide_included_only, "Hide _ files").on_hover_text(INCLUDED_ONLY_HINT);
+            }
+            let (released, copy) = ctx.input(|i| (i.pointer.any_released(), i.modifiers.command));
+            let (selected_file, problems, dragging) = (&self.selected_file, &self.problems, self.dragged_matches.is_some());
+            // Drops and clicks are applied once the list is drawn
+            let (mut dropped_on, mut clicked) = (None, None);
+            let mut file_row = |ui: &mut egui::Ui, file: &String| {
+                let name = file.rsplit('/').next().unwrap_or(file);
+                let mut response = ui.selectable_label(file == selected_file, file_label(file, name, problems.for_file(file).len()));
+                if is_included_only(file) {
+                    response = response.on_hover_text(INCLUDED_ONLY_HINT);
+                }
+                if dragging && file != selected_file && ui.rect_contains_pointer(response.rect) {
+                    ui.painter().rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+                    if released {
+                        dropped_on = Some(file.clone());
+                    }
+                }
+                if response.clicked() {
+                    clicked = Some(file.clone());
+                }
+            };
+            // Files in subfolders are grouped per folder, the selected file's folder open
+            let mut folders: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+            for file in &files {
+                folders.entry(file_folder(file)).or_default().push(file);
+            }
+            egui::ScrollArea::vertical().id_source("file_sidebar_list").show(ui, |ui| {
+                for (folder, folder_files) in &folders {
+                    if folder.is_empty() {
+                        folder_files.iter().for_each(|file| file_row(ui, file));
+                        continue;
+                    }
+                    egui::CollapsingHeader::new(format!("📁 {}", folder))
+                        .id_source(("file_folder", *folder))
+                        .default_open(file_folder(selected_file) == *folder)
+                        .show(ui, |ui| folder_files.iter().for_each(|file| file_row(ui, file)));
+                }
+            });
+            if let (Some(file), Some(indices)) = (dropped_on, self.dragged_matches.clone()) {
+                self.transfer_matches(&indices, &file, copy);
+            }
+            if let Some(file) = clicked {
+                self.select_file(file);
+            }
+            if let Some(indices) = &self.dragged_matches {
+                let verb = if copy { "Copy" } else { "Move" };
+                let target = if self.group_by_tag { "a file or tag" } else { "a file" };
+                egui::show_tooltip_at_pointer(ctx, egui::Id::new("dragged_matches"), |ui| {
+                    ui.label(format!("{} {} matches: drop them on {}, hold Ctrl to copy", verb, indices.len(), target));
+                });
+            }
+        });
+
+        egui::SidePanel::right("match_detail").resizable(true).default_width(320.0).show(ctx, |ui| {
+            egui::ScrollArea::vertical().id_source("match_detail_scroll").show(ui, |ui| {
+                ui.heading("Match Details");
+                let mut action = None;
+                let selected = self.selected_index.and_then(|i| Some((i, self.matches.get(i)?)));
+                if let Some((index, match_item)) = selected {
+                    egui::Grid::new("match_detail_fields").num_columns(2).show(ui, |ui| {
+                        ui.label("Trigger:");
+                        if match_item.regex.is_some() {
+                            ui.label(match_item.display_trigger());
+                        } else {
+                            ui.label(&match_item.trigger);
+                        }
+                        ui.end_row();
+                        for alias in &match_item.aliases {
+                            ui.label("Alias:");
+                            ui.horizontal(|ui| {
+                                ui.label(alias);
+                                if ui.small_button("Detach").on_hover_text("Turn this alias into a match of its own").clicked() {
+                                    action = Some(MatchAction::DetachAlias(index, alias.clone()));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                        ui.label("Replacement:");
+                        ui.label(&match_item.replace);
+                        ui.end_row();
+                        let stats = espanso_config::char_classes(&match_item.replace);
+                        if !stats.is_empty() {
+                            ui.label("Characters:");
+                            ui.horizontal_wrapped(|ui| char_stats(ui, &stats));
+                            ui.end_row();
+                        }
+                    });
+                    if let Some((reason, fixes)) = espanso_config::advice(match_item, &self.espanso_config) {
+                        ui.group(|ui| {
+                            ui.colored_label(egui::Color32::YELLOW, reason);
+                            ui.horizontal(|ui| {
+                                for fix in fixes {
+                                    if ui.button(fix.label()).clicked() {
+                                        action = Some(MatchAction::ApplyFix(index, fix));
+                                    }
+                                }
+                            });
+                        });
+                    }
+                    ui.collapsing("YAML", |ui| {
+                        ui.label(egui::RichText::new(yaml_io::match_fragment(match_item)).monospace());
+                    });
+                    ui.collapsing("Notes and Tags", |ui| {
+                        let file = &self.selected_file;
+                        let meta = self.metadata.get(match_item).cloned().unwrap_or_default();
+                        // Tags are split only when the field loses focus, so typing a comma works
+                        let tags_id = ui.make_persistent_id(("tags_draft", index));
+                        let mut tags = ui.data_mut(|d| d.get_temp::<String>(tags_id)).unwrap_or_else(|| meta.tags.join(", "));
+                        let mut note = meta.note.clone();
+                        let tags_edit = ui.add(egui::TextEdit::singleline(&mut tags).hint_text("Tags, separated by commas"));
+                        let note_edit = ui.add(egui::TextEdit::multiline(&mut note).hint_text("Note").desired_rows(2));
+                        if tags_edit.changed() {
+                            ui.data_mut(|d| d.insert_temp(tags_id, tags.clone()));
+                        }
+                        if tags_edit.lost_focus() {
+                            ui.data_mut(|d| d.remove::<String>(tags_id));
+                            let stored = self.metadata.get_mut(file, match_item);
+                            stored.tags = tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect();
+                            action = Some(MatchAction::SaveMetadata);
+                        }
+                        if note_edit.changed() {
+                            self.metadata.get_mut(file, match_item).note = note;
+                        }
+                        if note_edit.lost_focus() {
+                            action = Some(MatchAction::SaveMetadata);
+                        }
+                        if let Some(created) = &meta.created {
+                            ui.weak(format!("Created {}", created));
+                        }
+                        if let Some(modified) = &meta.modified {
+                            ui.weak(format!("Last changed {}", modified));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Edit").clicked() {
+                            action = Some(MatchAction::Edit(index));
+                        }
+                        if ui.button("Delete").clicked() {
+                            action = Some(MatchAction::Delete(index));
+                        }
+                    });
+                    if match_item.regex.is_none() {
+                        ui.collapsing("Platform Variants", |ui| {
+                            let mut added = None;
+                            let drafts = self.platform_drafts(index);
+                            let mut removed = None;
+                            for (position, (os, replace)) in drafts.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.label(os.label());
+                                    if ui.small_button("Remove").clicked() {
+                                        removed = Some(position);
+                                    }
+                                });
+                                ui.text_edit_multiline(replace);
+                            }
+                            if let Some(position) = removed {
+                                drafts.remove(position);
+                            }
+                            ui.horizontal(|ui| {
+                                for os in platforms::Os::ALL {
+                                    if !drafts.iter().any(|(o, _)| *o == os) && ui.button(format!("+ {}", os.label())).clicked() {
+                                        added = Some(os);
+                                    }
+                                }
+                            });
+                            if let Some(os) = added {
+                                let replace = self.matches[index].replace.clone();
+                                self.platform_drafts(index).push((os, replace));
+                            }
+                            if ui.button("Save Variants").clicked() {
+                                self.save_platform_variants(index);
+                            }
+                        });
+                        // The other matches are only listed while the list is open
+                        egui::ComboBox::from_label("Make alias of")
+                            .selected_text("Choose match…")
+                            .show_ui(ui, |ui| {
+                                for (other, m) in self.matches.iter().enumerate().filter(|(i, m)| *i != index && m.regex.is_none()) {
+                                    if ui.selectable_label(false, &m.trigger).clicked() {
+                                        action = Some(MatchAction::MakeAlias(other, index));
+                                    }
+                                }
+                            });
+                    }
+                } else {
+                    ui.label("Select a match in the list to see its details.");
+                }
+                if let Some(action) = action {
+                    self.apply_match_action(action, &[]);
+                }
+
+                ui.separator();
+                ui.heading(if self.editing_index.is_some() { "Edit Match" } else { "New Match" });
+                ui.horizontal(|ui| {
+                    if ui.button("📋 From Clipboard").on_hover_text("Start a new match replacing with the copied text").clicked() {
+                        self.new_match_from_clipboard();
+                    }
+                    ui.checkbox(&mut self.settings.clipboard_watcher, "Collect copied text")
+                        .on_hover_text("Keeps text copied in other apps as candidates for new matches, until the helper closes");
+                });
+                clipboard_candidates(ui, self);
+                ui.checkbox(&mut self.regex_mode, "Regex trigger");
+                if self.regex_mode {
+                    ui.horizontal(|ui| {
+                        ui.label("Regex:");
+                        let field = ui.text_edit_singleline(&mut self.new_regex);
+                        focus_new_match(self, &field);
+                    });
+                    ui.collapsing("Regex Builder", |ui| {
+                        let action = self.regex_builder.ui(ui);
+                        match action {
+                            Some(regex_builder::BuilderAction::UsePattern(pattern)) => self.new_regex = pattern,
+                            Some(regex_builder::BuilderAction::InsertPlaceholders(placeholders)) => {
+                                self.new_replacement.push_str(&placeholders);
+                            }
+                            None => {}
+                        }
+                    });
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.label("Trigger:");
+                        let field = ui.text_edit_singleline(&mut self.new_trigger);
+                        focus_new_match(self, &field);
+                    });
+                    ui.label("Aliases (one per line):");
+                    scroll_to_focus(&ui.add(egui::TextEdit::multiline(&mut self.new_aliases).desired_rows(1)));
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Replacement:");
+                    let recording = self.recording;
+                    let label = if recording { "⏹ Stop Recording" } else { "⏺ Record" };
+                    if ui.button(label).on_hover_text("Capture keystrokes, including Tab and Enter, verbatim").clicked() {
+                        self.recording = !recording;
+                    }
+                    if recording {
+                        ui.colored_label(egui::Color32::RED, "● Recording");
+                    }
+                    let hint = wrap::hint(&self.editor_match());
+                    if let Some(soft_wrap) = wrap_toggle(ui, &self.wrap_modes, hint) {
+                        self.set_soft_wrap(hint, soft_wrap);
+                    }
+                    if ui.button("⛶ Focus").on_hover_text("Edit the replacement alone in a maximized window").clicked() {
+                        self.focus_editor = Some(self.new_replacement.clone());
+                    }
+                });
+                let replacement_id = egui::Id::new("new_replacement");
+                self.clean_replacement_paste(ctx, replacement_id);
+                let soft_wrap = self.wrap_modes.soft_wrap(wrap::hint(&self.editor_match()));
+                if soft_wrap {
+                    scroll_to_focus(&ui.add(egui::TextEdit::multiline(&mut self.new_replacement).id(replacement_id)));
+                } else {
+                    let mut layouter = |ui: &egui::Ui, source: &str, _wrap_width: f32| {
+                        let font = egui::TextStyle::Body.resolve(ui.style());
+                        let job = egui::text::LayoutJob::simple(source.to_string(), font, ui.visuals().text_color(), f32::INFINITY);
+                        ui.fonts(|fonts| fonts.layout_job(job))
+                    };
+                    egui::ScrollArea::horizontal().id_source("replacement_scroll").show(ui, |ui| {
+                        ui.add(egui::TextEdit::multiline(&mut self.new_replacement).id(replacement_id).layouter(&mut layouter));
+                    });
+                }
+                let stored = self.editing_index.and_then(|i| self.matches.get(i)).map(|m| &m.replace);
+                if let Some(stored) = stored.filter(|stored| **stored != self.new_replacement) {
+                    ui.collapsing("Changes", |ui| {
+                        let parts = text::word_diff(stored, &self.new_replacement);
+                        ui.label(diff_job(ui, &parts, egui::TextStyle::Body));
+                    });
+                }
+                let stats = espanso_config::char_classes(&self.new_replacement);
+                if !stats.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        char_stats(ui, &stats);
+                        if self.new_force_clipboard || self.espanso_config.backend == espanso_config::Backend::Clipboard {
+                            ui.weak("pasted through the clipboard");
+                        } else if ui.button("Paste Through Clipboard").on_hover_text("The inject backend may type these characters wrong or drop them").clicked() {
+                            self.new_force_clipboard = true;
+                        }
+                    });
+                }
+                scroll_to_focus(&ui.checkbox(&mut self.settings.keep_typography, "Keep curly quotes and non-breaking spaces on paste"));
+                scroll_to_focus(&ui.checkbox(&mut self.new_word, "Word mode (only expand as a whole word)"));
+                ui.collapsing("More Options", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Label:");
+                        ui.add(egui::TextEdit::singleline(&mut self.new_label).hint_text("Shown in espanso's search bar"));
+                    });
+                    ui.checkbox(&mut self.new_propagate_case, "Propagate case (:Sig expands capitalized)");
+                    ui.checkbox(&mut self.new_force_clipboard, "Always paste through the clipboard");
+                    ui.label("Variables:");
+                    let mut removed = None;
+                    let mut inserted = None;
+                    let locale = self.preview_locale();
+                    for (index, var) in self.new_vars.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut var.name).hint_text("name").desired_width(90.0));
+                                egui::ComboBox::from_id_source(("var_type", index)).selected_text(&var.var_type).show_ui(ui, |ui| {
+                                    for var_type in vars::TYPES {
+                                        ui.selectable_value(&mut var.var_type, var_type.to_string(), *var_type);
+                                    }
+                                });
+                                if ui.button("Insert").on_hover_text(format!("Insert {{{{{}}}}} at the cursor", var.name)).clicked() {
+                                    inserted = Some(format!("{{{{{}}}}}", var.name));
+                                }
+                                if ui.button("🗑").clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                            for param in vars::params(&var.var_type) {
+                                match param {
+                                    vars::Param::Text(key, label) => {
+                                        let mut value = vars::text(var, key);
+                                        if ui.add(egui::TextEdit::singleline(&mut value).hint_text(*label)).changed() {
+                                            vars::set_text(var, key, &value);
+                                        }
+                                    }
+                                    vars::Param::Lines(key, label) => {
+                                        let mut value = vars::lines(var, key);
+                                        if ui.add(egui::TextEdit::multiline(&mut value).hint_text(*label).desired_rows(2)).changed() {
+                                            vars::set_lines(var, key, &value);
+                                        }
+                                    }
+                                    vars::Param::Flag(key, label) => {
+                                        let mut value = vars::flag(var, key);
+                                        if ui.checkbox(&mut value, *label).changed() {
+                                            vars::set_flag(var, key, value);
+                                        }
+                                    }
+                                    vars::Param::Shell => {
+                                        let current = vars::text(var, "shell");
+                                        let shown = if current.is_empty() { "default shell" } else { current.as_str() };
+                                        egui::ComboBox::from_id_source(("var_shell", index)).selected_text(shown).show_ui(ui, |ui| {
+                                            for shell in vars::SHELLS {
+                                                let label = if shell.is_empty() { "default shell" } else { shell };
+                                                if ui.selectable_label(current == *shell, label).clicked() {
+                                                    vars::set_text(var, "shell", shell);
+                                                }
+                                            }
+                                        });
+                                    }
+                                }
+                            }
+                            if var.var_type == "date" {
+                                let preview = docs::example(&format!("{{{{{}}}}}", var.name), std::slice::from_ref(var), &locale);
+                                ui.weak(format!("Preview: {}", preview));
+                            }
+                            if !vars::TYPES.contains(&var.var_type.as_str()) && !var.params.is_empty() {
+                                ui.weak("Params of this type are kept as is");
+                            }
+                        });
+                    }
+                    if let Some(index) = removed {
+                        self.new_vars.remove(index);
+                    }
+                    if let Some(text) = inserted {
+                        self.insert_into_replacement(ctx, &text);
+                    }
+                    if ui.button("Add Variable").clicked() {
+                        let name = format!("var{}", self.new_vars.len() + 1);
+                        let mut var = Var { name, var_type: "date".to_string(), ..Default::default() };
+                        vars::set_text(&mut var, "format", "%Y-%m-%d");
+                        self.new_vars.push(var);
+                    }
+                    if let Err(error) = self.editor_vars() {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
+                    let extra_keys: Vec<String> = self.editor_match().extra.keys()
+                        .filter_map(|k| k.as_str().map(str::to_string))
+                        .filter(|k| k != "form" && k != "form_fields")
+                        .collect();
+                    if !extra_keys.is_empty() {
+                        ui.weak(format!("Also kept as is: {}", extra_keys.join(", ")));
+                    }
+                });
+                ui.collapsing("Form", |ui| {
+                    ui.label("Layout, with [[field]] placeholders:");
+                    let layout = ui.add(egui::TextEdit::multiline(&mut self.new_form)
+                        .code_editor()
+                        .desired_rows(3)
+                        .hint_text("Hi [[name]],\n[[message]]"));
+                    if layout.changed() {
+                        self.new_form_fields = forms::sync_fields(&self.new_form, &self.new_form_fields);
+                    }
+                    for (index, field) in self.new_form_fields.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(&field.name);
+                            egui::ComboBox::from_id_source(("form_field", index)).selected_text(field.kind.name()).show_ui(ui, |ui| {
+                                for kind in forms::FieldKind::ALL {
+                                    ui.selectable_value(&mut field.kind, kind, kind.name());
+                                }
+                            });
+                            ui.add(egui::TextEdit::singleline(&mut field.default).hint_text("default").desired_width(100.0));
+                        });
+                        if field.kind.has_values() {
+                            ui.add(egui::TextEdit::multiline(&mut field.values).hint_text("Values, one per line").desired_rows(2));
+                        }
+                    }
+                    if !self.new_form.trim().is_empty() && !self.new_replacement.is_empty() {
+                        ui.weak("espanso shows the form instead of the replacement text");
+                    }
+                });
+
+                let duplicates = self.duplicate_definitions(&self.editor_match());
+                for (trigger, definitions) in &duplicates {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(egui::Color32::from_rgb(255, 140, 0), format!("⚠ {} is already defined in", trigger));
+                        for (file, index) in definitions {
+                            if ui.link(format!("{} #{}", file, index + 1)).clicked() {
+                                self.open_match(file, *index);
+                            }
+                        }
+                    });
+                }
+                let add_button = ui.button(if self.editing_index.is_some() { "Update Match" } else { "Add Match" }).on_hover_text("Ctrl+Enter");
+                tour_highlight(ctx, tour.as_ref(), tour::TourStep::AddForm, add_button.rect);
+                scroll_to_focus(&add_button);
+                if add_button.clicked() {
+                    self.add_or_update_match();
+                }
+
+                let references = self.rename_references.clone();
+                if let Some(references) = references {
+                    ui.group(|ui| {
+                        let triggers: Vec<String> = references.indices.iter()
+                            .filter_map(|&i| Some(self.matches.get(i)?.display_trigger()))
+                            .collect();
+                        ui.label(format!("{} other matches mention {}: {}", triggers.len(), references.old, triggers.join(", ")));
+                        ui.horizontal(|ui| {
+                            if ui.button(format!("Update to {}", references.new)).clicked() {
+                                self.update_references();
+                            }
+                            if ui.button("Leave As Is").clicked() {
+                                self.rename_references = None;
+                            }
+                        });
+                    });
+                }
+
+                ui.collapsing("Template for New Matches", |ui| {
+                    let file = self.selected_file.clone();
+                    let mut per_file = self.templates.has_override(&file);
+                    if ui.checkbox(&mut per_file, format!("Separate template for {}", file)).changed() {
+                        self.templates.set_override(&file, per_file);
+                    }
+                    let template = self.templates.for_file_mut(&file);
+                    ui.checkbox(&mut template.word, "Word mode");
+                    ui.checkbox(&mut template.strip_trailing_whitespace, "Strip trailing whitespace");
+                    ui.label("Footer appended to the replacement:");
+                    ui.add(egui::TextEdit::multiline(&mut template.footer).desired_rows(2));
+                    if ui.button("Save Template").clicked() {
+                        self.status = match self.templates.save(templates::TEMPLATES_FILE) {
+                            Ok(()) => "Template saved".to_string(),
+                            Err(error) => format!("Saving the template failed: {}", error),
+                        };
+                        self.new_word = self.templates.for_file(&file).word;
+                    }
+                });
+
+                egui::CollapsingHeader::new("YAML Preview").default_open(true).show(ui, |ui| {
+                    let fragment = yaml_io::match_fragment(&self.editor_match());
+                    ui.label(egui::RichText::new(fragment).monospace());
+                });
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Espanso Helper");
+            if self.safe_mode {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, "Safe mode: read-only, nothing is loaded or polled automatically.");
+                    let selected_file = self.selected_file.clone();
+                    if ui.button(format!("Load {}", selected_file)).clicked() {
+                        self.load_matches();
+                    }
+                });
+            }
+            
+            ui.horizontal_wrapped(|ui| {
+                if ui.button("Refresh").clicked() {
+                    self.refresh();
+                }
+                if ui.button("Open Config Folder").clicked() {
+                    self.open_config_folder();
+                }
+                ui.toggle_value(&mut self.show_search, "Search All…").on_hover_text("Ctrl+Shift+F");
+                ui.toggle_value(&mut self.show_audit, "Audit");
+                ui.toggle_value(&mut self.show_import, "Import…");
+                ui.toggle_value(&mut self.show_export, "Export…");
+                ui.toggle_value(&mut self.trigger_grid, "Trigger Grid");
+                if self.file_has_tags() {
+                    ui.toggle_value(&mut self.group_by_tag, "By Tag");
+                }
+                ui.toggle_value(&mut self.show_shortcuts, "Shortcuts…");
+                if ui.toggle_value(&mut self.show_global_vars, "Global Vars…").clicked() {
+                    self.refresh_global_vars();
+                }
+                ui.toggle_value(&mut self.show_sandbox, "Sandbox");
+                let trashed = self.trash.len();
+                if trashed > 0 {
+                    ui.toggle_value(&mut self.show_trash, format!("Trash ({})", trashed));
+                }
+                if tour.is_none() && ui.button("Tour").clicked() {
+                    self.start_tour();
+                }
+            });
+
+            if self.show_sandbox {
+                ui.group(|ui| {
+                    ui.label(format!("Type text to see how the matches of {} expand:", self.selected_file));
+                    ui.add(egui::TextEdit::multiline(&mut self.sandbox_input).desired_rows(2).desired_width(f32::INFINITY));
+                    let expanded = sandbox::expand(&self.sandbox_input, &self.matches, &self.preview_locale());
+                    ui.label(egui::RichText::new(expanded).monospace());
+                });
+            }
+
+            if self.show_trash && !self.trash.is_empty() {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Deleted this session, gone once the helper closes:");
+                        if ui.button("Empty Trash").clicked() {
+                            self.trash.clear();
+                            self.show_trash = false;
+                        }
+                    });
+                    let trash = self.trash.clone();
+                    egui::ScrollArea::vertical().id_source("trash").max_height(150.0).show(ui, |ui| {
+                        for (position, entry) in trash.iter().enumerate().rev() {
+                            ui.horizontal(|ui| {
+                                ui.label(text::reveal_invisible(&entry.m.display_trigger()));
+                                ui.weak(format!("from {} at {}", entry.file, entry.deleted));
+                                ui.add(egui::Label::new(entry.m.summary()).wrap(false));
+                                if ui.button("Restore").clicked() {
+                                    self.restore_from_trash(position);
+                                }
+                            });
+                        }
+                    });
+                });
+            }
+
+            if self.show_global_vars {
+                ui.group(|ui| global_vars_panel(ui, self));
+            }
+
+            if self.show_shortcuts {
+                ui.group(|ui| {
+                    let files = self.files.clone();
+                    let mut removed = None;
+                    for (position, shortcut) in self.shortcuts.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let keys = egui::TextEdit::singleline(&mut shortcut.keys).hint_text("Ctrl+Shift+D").desired_width(110.0);
+                            let response = ui.add(keys);
+                            if !shortcut.keys.is_empty() && shortcut.parse_keys().is_none() {
+                                response.on_hover_text("Use modifiers like Ctrl, Alt and Shift plus a letter, digit or F1–F12");
+                                ui.colored_label(egui::Color32::RED, "⚠");
+                            }
+                            egui::ComboBox::from_id_source(("shortcut_action", position))
+                                .selected_text(shortcut.action.label())
+                                .show_ui(ui, |ui| {
+                                    for action in shortcuts::ShortcutAction::ALL {
+                                        ui.selectable_value(&mut shortcut.action, action, action.label());
+                                    }
+                                });
+                            match shortcut.action {
+                                shortcuts::ShortcutAction::InsertText => {
+                                    ui.add(egui::TextEdit::singleline(&mut shortcut.argument).hint_text("{{date}}"));
+                                }
+                                shortcuts::ShortcutAction::MoveToFile => {
+                                    egui::ComboBox::from_id_source(("shortcut_file", position))
+                                        .selected_text(shortcut.argument.clone())
+                                        .show_ui(ui, |ui| {
+                                            for file in &files {
+                                                ui.selectable_value(&mut shortcut.argument, file.clone(), file);
+                                            }
+                                        });
+                                }
+                            }
+                            if ui.small_button("✖").clicked() {
+                                removed = Some(position);
+                            }
+                        });
+                    }
+                    if let Some(position) = removed {
+                        self.shortcuts.remove(position);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Add Shortcut").clicked() {
+                            self.shortcuts.push(shortcuts::Shortcut::default());
+                        }
+                        if ui.button("Save Shortcuts").clicked() {
+                            self.status = match shortcuts::save(&self.shortcuts) {
+                                Ok(()) => "Shortcuts saved".to_string(),
+                                Err(error) => format!("Saving the shortcuts failed: {}", error),
+                            };
+                        }
+                    });
+                });
+            }
+
+            if self.show_export {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Format:");
+                        for format in ExportFormat::ALL {
+                            ui.radio_value(&mut self.export_format, format, format.label());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("New file name:");
+                        ui.text_edit_singleline(&mut self.export_file_name);
+                        ui.weak(if self.export_format == ExportFormat::Espanso { "in the match folder" } else { "in Downloads" });
+                    });
+                    let selected = self.selection.len();
+                    ui.add_enabled(selected > 0, egui::Checkbox::new(&mut self.export_selected_only, format!("Only the {} selected matches", selected)))
+                        .on_disabled_hover_text("Select matches in the list first");
+                    let selected_only = self.export_selected_only && selected > 0;
+                    ui.add_enabled(!selected_only, egui::Checkbox::new(&mut self.export_all_files, "Include matching entries from all files"));
+                    ui.checkbox(&mut self.export_remove_sources, "Remove exported matches from their source files");
+                    ui.horizontal(|ui| {
+                        if ui.button("Export").clicked() {
+                            self.export_filter_result();
+                        }
+                        if ui.button("Write Documentation").on_hover_text("Markdown overview of the selected file with example expansions").clicked() {
+                            self.export_documentation();
+                        }
+                    });
+                });
+            }
+
+            if self.show_import {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("YAML, Beeftext JSON, CSV or JSON table to import:");
+                        ui.text_edit_singleline(&mut self.import_path);
+                        if ui.button("Preview").clicked() {
+                            self.preview_import();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("…or URL / GitHub path:");
+                        ui.text_edit_singleline(&mut self.import_url);
+                        if self.pending_fetch.is_some() {
+                            ui.spinner();
+                        } else if ui.button("Fetch").clicked() {
+                            self.fetch_import();
+                        }
+                    });
+                    let preview = self.import_preview.clone();
+                    if let Some(mut preview) = preview {
+                        if let Some((table, mapping)) = &preview.table {
+                            let mut mapping = mapping.clone();
+                            let columns = table.column_names(mapping.has_header);
+                            ui.horizontal_wrapped(|ui| {
+                                ui.add_enabled(!table.header_fixed, egui::Checkbox::new(&mut mapping.has_header, "First row is a header"));
+                                for (label, column) in [("Trigger:", &mut mapping.trigger), ("Replacement:", &mut mapping.replace)] {
+                                    ui.label(label);
+                                    egui::ComboBox::from_id_source(label)
+                                        .selected_text(columns.get(*column).cloned().unwrap_or_default())
+                                        .show_ui(ui, |ui| {
+                                            for (index, name) in columns.iter().enumerate() {
+                                                ui.selectable_value(column, index, name);
+                                            }
+                                        });
+                                }
+                                for (label, column) in [("Label:", &mut mapping.label), ("Tags:", &mut mapping.tags)] {
+                                    ui.label(label);
+                                    egui::ComboBox::from_id_source(label)
+                                        .selected_text(column.and_then(|c| columns.get(c).cloned()).unwrap_or_else(|| "None".to_string()))
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(column, None, "None");
+                                            for (index, name) in columns.iter().enumerate() {
+                                                ui.selectable_value(column, Some(index), name);
+                                            }
+                                        });
+                                }
+                            });
+                            let mut delimiter = table.csv.as_ref().map(|csv| csv.delimiter);
+                            if let Some(csv) = &table.csv {
+                                ui.horizontal(|ui| {
+                                    ui.label("Delimiter:");
+                                    for (candidate, name) in table::DELIMITERS {
+                                        ui.radio_value(&mut delimiter, Some(candidate), name);
+                                    }
+                                    ui.separator();
+                                    ui.weak(format!("Read as {}", csv.encoding));
+                                });
+                            }
+                            if delimiter != table.csv.as_ref().map(|csv| csv.delimiter) {
+                                if let Err(error) = preview.set_delimiter(delimiter.unwrap_or(',')) {
+                                    self.status = format!("Reading {} again failed: {}", preview.source, error);
+                                }
+                            } else if Some(&mapping) != preview.table.as_ref().map(|(_, m)| m) {
+                                preview.remap(mapping);
+                            }
+                        }
+                        if let Some(notes) = &preview.legacy_notes {
+                            ui.label(format!("{} is an espanso 1 file and was converted for espanso 2", preview.source));
+                            for note in notes {
+                                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", note));
+                            }
+                            if !preview.global_vars.is_empty() {
+                                let names: Vec<&str> = preview.global_vars.iter().map(|var| var.name.as_str()).collect();
+                                ui.label(format!("Its global variables go into {} as well: {}", self.selected_file, names.join(", ")));
+                            }
+                        }
+                        egui::Grid::new("import_rows").striped(true).show(ui, |ui| {
+                            ui.strong("Trigger");
+                            ui.strong("Replacement");
+                            if !preview.tags.is_empty() {
+                                ui.strong("Tags");
+                            }
+                            ui.end_row();
+                            for m in preview.candidates.iter().take(5) {
+                                ui.label(&m.trigger);
+                                ui.label(text::summarize(&m.replace));
+                                if !preview.tags.is_empty() {
+                                    ui.label(preview.tags.get(&m.trigger).map(|tags| tags.join(", ")).unwrap_or_default());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if preview.candidates.len() > 5 {
+                            ui.weak(format!("…and {} more", preview.candidates.len() - 5));
+                        }
+                        ui.label(format!(
+                            "{} matches found in {}, {} triggers already defined elsewhere",
+                            preview.candidates.len(),
+                            preview.source,
+                            preview.collisions.len()
+                        ));
+                        egui::ScrollArea::vertical().id_source("import_collisions").max_height(150.0).show(ui, |ui| {
+                            for collision in &preview.collisions {
+                                ui.horizontal(|ui| {
+                                    let link = egui::Link::new(egui::RichText::new(&collision.trigger).color(egui::Color32::YELLOW));
+                                    if ui.add(link).on_hover_text("Open the existing definition").clicked() {
+                                        self.go_to_definition(&collision.trigger);
+                                    }
+                                    ui.label(format!("defined in {}", collision.files.join(", ")));
+                                });
+                            }
+                        });
+                        let selected_file = self.selected_file.clone();
+                        if preview.generator().is_some() {
+                            ui.checkbox(&mut preview.generated, format!("Replace all matches in {} and keep the table for Regenerate", selected_file));
+                        }
+                        if !preview.generated {
+                            ui.checkbox(&mut preview.skip_collisions, "Skip colliding triggers");
+                            ui.checkbox(&mut preview.merge_existing, format!("Update matches in {} that have the same trigger", selected_file));
+                        }
+                        let (updated, added) = preview.outcome(&self.matches);
+                        let generated = preview.generated.then_some(preview.candidates.len());
+                        self.import_preview = Some(preview);
+                        ui.horizontal(|ui| {
+                            let label = if let Some(count) = generated {
+                                format!("Generate {} with {} matches", selected_file, count)
+                            } else if updated > 0 {
+                                format!("Import {} and update {} matches in {}", added, updated, selected_file)
+                            } else {
+                                format!("Import {} matches into {}", added, selected_file)
+                            };
+                            if ui.button(label).clicked() {
+                                self.commit_import();
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.import_preview = None;
+                            }
+                        });
+                    }
+                });
+            }
+            
+            let selected_file = self.selected_file.clone();
+            let problems = self.problems.clone();
+
+            ui.horizontal_wrapped(|ui| {
+                let active = self.active_tab;
+                let mut switch_to = None;
+                let mut close = None;
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    let (file, unsaved) = if index == active {
+                        (selected_file.as_str(), self.has_unsaved_edits())
+                    } else {
+                        (tab.file.as_str(), tab.unsaved)
+                    };
+                    let label = if unsaved { format!("{} ●", file) } else { file.to_string() };
+                    let response = ui.selectable_label(index == active, label);
+                    if unsaved {
+                        response.clone().on_hover_text("The editor has changes that were not added yet");
+                    }
+                    if response.clicked() {
+                        switch_to = Some(index);
+                    }
+                    if self.tabs.len() > 1 && ui.small_button("×").on_hover_text("Close tab").clicked() {
+                        close = Some(index);
+                    }
+                }
+                if ui.small_button("+").on_hover_text("Open another tab").clicked() {
+                    self.open_tab();
+                }
+                if let Some(index) = switch_to {
+                    self.switch_tab(index);
+                }
+                if let Some(index) = close {
+                    self.close_tab(index);
+                }
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                let mut chosen = None;
+                let file_selector = egui::ComboBox::from_label("Select YAML file")
+                    .selected_text(&selected_file)
+                    .show_ui(ui, |ui| {
+                        for file in self.shown_files() {
+                            if ui.selectable_label(file == selected_file, file_label(&file, &file, problems.for_file(&file).len())).clicked() {
+                                chosen = Some(file);
+                            }
+                        }
+                    });
+                if let Some(file) = chosen {
+                    self.select_file(file);
+                }
+                tour_highlight(ctx, tour.as_ref(), tour::TourStep::FileSelector, file_selector.response.rect);
+                ui.separator();
+                for (action, label) in [
+                    (FileAction::Create, "New…"),
+                    (FileAction::Rename, "Rename…"),
+                    (FileAction::Duplicate, "Duplicate…"),
+                    (FileAction::Delete, "Delete…"),
+                ] {
+                    let needs_file = action != FileAction::Create;
+                    if ui.add_enabled(!needs_file || !selected_file.is_empty(), egui::Button::new(label)).clicked() {
+                        self.file_action = Some(action);
+                        self.file_action_name = match action {
+                            FileAction::Rename | FileAction::Duplicate => selected_file.clone(),
+                            _ => String::new(),
+                        };
+                    }
+                }
+                ui.separator();
+                if !self.settings.autosave {
+                    let label = if self.unsaved_file { "● Save" } else { "Save" };
+                    if ui.add_enabled(self.unsaved_file, egui::Button::new(label)).on_hover_text("Ctrl+S").clicked() {
+                        self.flush_unsaved();
+                    }
+                }
+                if let Some(generator) = generated::read(&self.document.header) {
+                    let regenerate = ui.button("Regenerate").on_hover_text(format!(
+                        "{} was generated from {}; regenerating replaces its matches, including manual edits",
+                        selected_file,
+                        generator.describe()
+                    ));
+                    if regenerate.clicked() {
+                        self.regenerate();
+                    }
+                }
+                if ui.add_enabled(!selected_file.is_empty(), egui::SelectableLabel::new(self.show_backups, "Backups…")).clicked() {
+                    self.show_backups = !self.show_backups;
+                    self.backup_preview = None;
+                }
+                ui.toggle_value(&mut self.settings.review_saves, "Review Saves")
+                    .on_hover_text("Show what a save changes in the file and wait for confirmation");
+                let raw_open = self.raw_yaml.is_some();
+                let raw_dirty = self.raw_yaml.as_ref().is_some_and(|raw| raw.text != raw.base);
+                let raw_toggle = ui
+                    .add_enabled(!selected_file.is_empty() && self.compact.is_none() && !raw_dirty, egui::SelectableLabel::new(raw_open, "Raw YAML"))
+                    .on_disabled_hover_text("Save or revert the raw edits first");
+                if raw_toggle.clicked() {
+                    if raw_open {
+                        self.raw_yaml = None;
+                    } else {
+                        self.open_raw_yaml();
+                    }
+                }
+            });
+            let file_action = self.file_action;
+            if let Some(action) = file_action {
+                ui.horizontal(|ui| {
+                    let confirm = match action {
+                        FileAction::Delete => {
+                            ui.colored_label(egui::Color32::RED, format!("Delete {} and all its matches?", selected_file));
+                            "Delete"
+                        }
+                        _ => {
+                            ui.label("File name:");
+                            ui.text_edit_singleline(&mut self.file_action_name);
+                            if action == FileAction::Create {
+                                egui::ComboBox::from_id_source("file_preset")
+                                    .selected_text(self.file_preset.label())
+                                    .show_ui(ui, |ui| {
+                                        for preset in presets::FilePreset::ALL {
+                                            ui.selectable_value(&mut self.file_preset, preset, preset.label());
+                                        }
+                                    });
+                            }
+                            match action {
+                                FileAction::Create => "Create",
+                                FileAction::Rename => "Rename",
+                                _ => "Duplicate",
+                            }
+                        }
+                    };
+                    if ui.button(confirm).clicked() {
+                        self.run_file_action();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.file_action = None;
+                    }
+                });
+            }
+            
+            if self.show_backups {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Backups of {}", selected_file));
+                        ui.separator();
+                        ui.label("Keep");
+                        ui.add(egui::DragValue::new(&mut self.settings.backup_count).clamp_range(0..=100));
+                        ui.label("per file (0 turns backups off)");
+                    });
+                    let list = backups::list(&selected_file);
+                    if list.is_empty() {
+                        ui.label("No backups yet, one is taken before each save.");
+                    }
+                    let previewed = self.backup_preview.clone();
+                    egui::ScrollArea::vertical().id_source("backup_list").max_height(120.0).show(ui, |ui| {
+                        for backup in list {
+                            ui.horizontal(|ui| {
+                                let shown = previewed.as_ref().is_some_and(|(b, _)| *b == backup);
+                                if ui.selectable_label(shown, &backup.taken).clicked() {
+                                    self.preview_backup(backup.clone());
+                                }
+                                if ui.button("Restore").clicked() {
+                                    self.restore_backup(&backup);
+                                }
+                            });
+                        }
+                    });
+                    if let Some((backup, parts)) = previewed {
+                        if parts.iter().all(|(op, _)| *op == text::DiffOp::Same) {
+                            ui.label(format!("The backup of {} matches the file on disk.", backup.taken));
+                        } else {
+                            ui.label(format!("Red lines are only in the backup of {}, green lines only in the current file:", backup.taken));
+                            egui::ScrollArea::vertical().id_source("backup_diff").max_height(250.0).show(ui, |ui| {
+                                ui.label(diff_job(ui, &parts, egui::TextStyle::Monospace));
+                            });
+                        }
+                    }
+                });
+            }
+
+            ui.collapsing("File Description", |ui| {
+                ui.add(egui::TextEdit::multiline(&mut self.document.header)
+                    .hint_text("Comment written at the top of the file")
+                    .desired_rows(3));
+                if ui.button("Save Description").clicked() && self.allow_destructive() {
+                    self.save_matches();
+                    self.status = format!("Saved the description of {}", self.selected_file);
+                }
+            });
+
+            ui.collapsing("Whitespace Rules", |ui| {
+                let file = self.selected_file.clone();
+                let mut per_file = self.trim_rules.has_override(&file);
+                if ui.checkbox(&mut per_file, format!("Separate rules for {}", file)).changed() {
+                    self.trim_rules.set_override(&file, per_file);
+                }
+                let rules = self.trim_rules.for_file_mut(&file);
+                ui.checkbox(&mut rules.strip_line_endings, "Strip trailing spaces on each line");
+                ui.checkbox(&mut rules.trim_trailing_newlines, "Trim trailing newlines");
+                ui.checkbox(&mut rules.collapse_blank_lines, "Collapse multiple blank lines");
+                let affected = self.trim_preview();
+                if affected.is_empty() {
+                    ui.label("No matches in this file are affected.");
+                } else {
+                    ui.label(format!("{} matches change on the next save: {}", affected.len(), affected.join(", ")));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Save Rules").clicked() {
+                        self.status = match self.trim_rules.save(TRIM_RULES_FILE) {
+                            Ok(()) => "Whitespace rules saved".to_string(),
+                            Err(error) => format!("Saving the whitespace rules failed: {}", error),
+                        };
+                    }
+                    if !affected.is_empty() && ui.button("Apply Now").clicked() && self.allow_destructive() {
+                        self.save_matches();
+                    }
+                });
+            });
+
+            let document_count = self.document.document_count();
+            if document_count > 1 {
+                ui.label(format!(
+                    "This file contains {} YAML documents; only the one with matches is edited, the others are kept as they are.",
+                    document_count
+                ));
+            }
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Filter:");
+                {
+                    egui::ComboBox::from_id_source("filter_mode").width(70.0).selected_text(self.settings.filter_mode.label()).show_ui(ui, |ui| {
+                        for mode in filter::FilterMode::ALL {
+                            ui.selectable_value(&mut self.settings.filter_mode, mode, mode.label());
+                        }
+                    });
+                    if ui.available_size_before_wrap().x < 110.0 {
+                        ui.end_row();
+                    }
+                    egui::ComboBox::from_id_source("filter_scope").width(70.0).selected_text(self.settings.filter_scope.label()).show_ui(ui, |ui| {
+                        for scope in filter::FilterScope::ALL {
+                            ui.selectable_value(&mut self.settings.filter_scope, scope, scope.label());
+                        }
+                    });
+                }
+                // The field takes the rest of the row, or a row of its own when little is left;
+                // the pickers wrap the same way in a narrow list
+                if ui.available_size_before_wrap().x < 100.0 {
+                    ui.end_row();
+                }
+                // Without the frame's margin on both sides
+                let width = ui.available_size_before_wrap().x - 8.0;
+                let filter = ui.add(egui::TextEdit::singleline(&mut self.filter_text).desired_width(width))
+                    .on_hover_text("Ctrl+F, Escape returns to the list: Up and Down select, Enter edits, Delete deletes");
+                if std::mem::take(&mut self.focus_filter) {
+                    filter.request_focus();
+                }
+                tour_highlight(ctx, tour.as_ref(), tour::TourStep::Filter, filter.rect);
+            });
+            if let Some(error) = self.filter_query().error() {
+                ui.colored_label(egui::Color32::RED, "Invalid regex").on_hover_text(error);
+            }
+            
+            if self.show_audit {
+                let findings = self.unicode_audit();
+                ui.group(|ui| {
+                    ui.strong("Unicode");
+                    if findings.is_empty() {
+                        ui.label("No invisible characters or unnormalized text found.");
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} matches with suspicious characters", findings.len()));
+                            if ui.button("Normalize All").clicked() {
+                                self.normalize_all_matches();
+                            }
+                        });
+                    }
+                    for (index, issues) in &findings {
+                        let trigger = text::reveal_invisible(&self.matches[*index].display_trigger());
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::YELLOW, trigger);
+                            let summary: Vec<String> = issues.iter().map(|i| i.describe()).collect();
+                            ui.label(summary.join(", "));
+                            if ui.button("Normalize").clicked() {
+                                self.normalize_match(*index);
+                            }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.strong("Variants");
+                    let groups = aliases::variant_groups(&self.matches);
+                    if groups.is_empty() {
+                        ui.label("No matches share the same replacement.");
+                    }
+                    for group in groups {
+                        let triggers: Vec<String> = group.iter().map(|&i| self.matches[i].trigger.clone()).collect();
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Same replacement: {}", triggers.join(", ")));
+                            if ui.button(format!("Merge into {}", triggers[0])).clicked() {
+                                self.make_aliases(group[0], &group[1..]);
+                            }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.strong("Spelling Variants");
+                    let problems = self.problems.clone();
+                    let groups = aliases::spelling_groups(problems.triggers.keys());
+                    if groups.is_empty() {
+                        ui.label("No triggers differ only by case, accents or a ':' prefix.");
+                    } else {
+                        ui.label("espanso treats these as different triggers. Merge them into one match with aliases, or keep the lowercase one and enable propagate case.");
+                    }
+                    for group in groups {
+                        ui.horizontal_wrapped(|ui| {
+                            for trigger in &group {
+                                let files: Vec<&str> = problems.definitions(trigger).iter().map(|(f, _)| f.as_str()).collect();
+                                if ui.link(trigger).on_hover_text(files.join(", ")).clicked() {
+                                    self.go_to_definition(trigger);
+                                }
+                            }
+                            let selected_file = self.selected_file.clone();
+                            let local = group.iter().all(|t| problems.definitions(t).iter().all(|(f, _)| *f == selected_file));
+                            let merge = ui.add_enabled(local, egui::Button::new(format!("Merge into {}", group[0])));
+                            if merge.on_disabled_hover_text("Some spellings are defined in other files; move them here first").clicked() {
+                                self.merge_spellings(&group);
+                            }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.strong("Trigger Risk");
+                        if ui.button("Scan").clicked() {
+                            self.scan_trigger_risk();
+                        }
+                    });
+                    let risk_findings = self.risk_findings.clone();
+                    if !risk_findings.is_empty() {
+                        ui.label("Prefix risky triggers with ':' or enable word mode (word: true) so they only fire as whole words.");
+                        let words = self.dictionary_word_matches();
+                        if !words.is_empty() && ui.button(format!("Enable Word Mode for {} Dictionary Words", words.len())).clicked() {
+                            self.enable_word_mode(&words);
+                        }
+                    }
+                    egui::ScrollArea::vertical().id_source("risk_findings").max_height(200.0).show(ui, |ui| {
+                        for finding in &risk_findings {
+                            ui.horizontal(|ui| {
+                                let color = match finding.risk {
+                                    risk::Risk::High => egui::Color32::RED,
+                                    _ => egui::Color32::YELLOW,
+                                };
+                                ui.colored_label(color, &finding.trigger);
+                                ui.label(&finding.reason);
+                                if !risk::has_prefix(&finding.trigger) && ui.button(format!("Rename to :{}", finding.trigger)).clicked() {
+                                    self.prefix_trigger(finding.index, &finding.trigger, ":");
+                                }
+                            });
+                        }
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.strong("Images");
+                        if self.pending_asset_scan.is_none() && ui.button("Scan").clicked() {
+                            self.scan_assets();
+                        }
+                    });
+                    let asset_report = self.asset_report.clone();
+                    if let Some(report) = asset_report {
+                        let root = self.espanso_dir();
+                        if report.orphaned.is_empty() && report.missing.is_empty() {
+                            ui.label("Every image is used and every image_path exists.");
+                        }
+                        if !report.orphaned.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} images are not used by any match", report.orphaned.len()));
+                                if ui.button("Delete All").clicked() {
+                                    self.delete_images(&report.orphaned);
+                                }
+                            });
+                        }
+                        egui::ScrollArea::vertical().id_source("image_assets").max_height(200.0).show(ui, |ui| {
+                            for path in &report.orphaned {
+                                ui.horizontal(|ui| {
+                                    ui.label(path.strip_prefix(&root).unwrap_or(path).display().to_string());
+                                    if ui.button("Delete").clicked() {
+                                        self.delete_images(std::slice::from_ref(path));
+                                    }
+                                });
+                            }
+                            for missing in &report.missing {
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(egui::Color32::RED, &missing.trigger);
+                                    ui.label(format!("in {} refers to missing {}", missing.file, missing.image_path));
+                                });
+                            }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.strong("Packages");
+                        if ui.button("Scan").clicked() {
+                            self.installed_packages = Some(packages::installed(&self.config_dir));
+                        }
+                    });
+                    let installed = self.installed_packages.clone();
+                    if installed.as_ref().is_some_and(Vec::is_empty) {
+                        ui.label("No espanso packages are installed.");
+                    }
+                    let comparing = self.pending_package_diff.is_some();
+                    for package in installed.unwrap_or_default() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} {}", package.name, package.version));
+                            if !package.from_hub {
+                                ui.weak("not installed from the hub");
+                            } else if ui.add_enabled(!comparing, egui::Button::new("Compare with Hub")).clicked() {
+                                self.compare_package(package.clone());
+                            }
+                        });
+                    }
+                    let package_diff = self.package_diff.clone();
+                    if let Some(diff) = package_diff {
+                        ui.strong(format!("{} {} against the hub", diff.package.name, diff.package.version));
+                        if let Some(version) = &diff.newer_version {
+                            ui.label(format!("Version {} is available.", version));
+                        }
+                        if diff.changes.is_empty() {
+                            ui.label("No local modifications, updating loses nothing.");
+                        } else {
+                            ui.colored_label(egui::Color32::YELLOW, "espanso package update would overwrite these local changes:");
+                            for (trigger, change) in &diff.changes {
+                                ui.horizontal(|ui| {
+                                    ui.label(trigger);
+                                    ui.weak(change.label());
+                                });
+                            }
+                            ui.collapsing("File Diff", |ui| {
+                                egui::ScrollArea::vertical().id_source("package_diff").max_height(250.0).show(ui, |ui| {
+                                    ui.label(diff_job(ui, &diff.lines, egui::TextStyle::Monospace));
+                                });
+                            });
+                        }
+                    }
+                });
+            }
+
+            if problems.total() > 0 {
+                egui::CollapsingHeader::new(format!("Problems ({})", problems.total())).id_source("problems").show(ui, |ui| {
+                    if ui.button("Lint Summary…").clicked() {
+                        self.show_lint = true;
+                    }
+                    egui::ScrollArea::vertical().id_source("problem_list").max_height(160.0).show(ui, |ui| {
+                        let mut sorted: Vec<_> = problems.files.iter().collect();
+                        sorted.sort_by_key(|(file, _)| file.as_str());
+                        for (file, file_problems) in sorted {
+                            for problem in file_problems {
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(severity_color(problem.severity), problem.kind.name());
+                                    let location = match problem.index {
+                                        Some(index) => format!("{} #{}", file, index + 1),
+                                        None => file.clone(),
+                                    };
+                                    if ui.link(location).clicked() {
+                                        match problem.index {
+                                            Some(index) => self.open_match(file, index),
+                                            None => self.select_file(file.clone()),
+                                        }
+                                    }
+                                    ui.label(&problem.message);
+                                });
+                            }
+                        }
+                    });
+                });
+            }
+
+            let load_error = self.document.error().map(str::to_string);
+            if let Some(error) = load_error {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(egui::Color32::RED, format!("⚠ {} could not be read in full: {}", selected_file, error));
+                    ui.label("Changes are not saved until it's fixed.");
+                    if self.raw_yaml.is_none() && ui.button("Fix in Raw YAML").clicked() {
+                        self.open_raw_yaml();
+                    }
+                });
+            }
+
+            if self.external_change {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} changed on disk, your last change is not saved", selected_file));
+                    if ui.button("Reload").on_hover_text("Discard your last change and load the file from disk").clicked() {
+                        self.external_change = false;
+                        self.reload_if_changed();
+                        self.status = format!("Reloaded {}", selected_file);
+                    }
+                    if ui.button("Overwrite").on_hover_text("Replace the file on disk with your version").clicked() {
+                        self.overwrite_matches();
+                    }
+                });
+            }
+
+            if let Some(description) = self.conflict.as_ref().map(merge::ConflictSource::describe) {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ Sync conflict: {}", description));
+                    if self.merge_session.is_none() && ui.button("Resolve…").clicked() {
+                        self.open_merge();
+                    }
+                });
+            }
+            if self.merge_session.is_some() {
+                ui.group(|ui| {
+                    let Some(session) = self.merge_session.as_mut() else { return };
+                    let conflicts = session.conflict_count();
+                    ui.label(format!(
+                        "{} triggers, {} changed on both sides. Triggers changed on one side only are preselected.",
+                        session.entries.len(),
+                        conflicts
+                    ));
+                    let describe = |m: &Option<Match>| m.as_ref().map_or("(removed)".to_string(), Match::summary);
+                    egui::ScrollArea::vertical().id_source("merge_entries").max_height(250.0).show(ui, |ui| {
+                        egui::Grid::new("merge_grid").striped(true).show(ui, |ui| {
+                            ui.strong("Trigger");
+                            ui.strong("Base");
+                            ui.strong("Ours");
+                            ui.strong("Theirs");
+                            ui.end_row();
+                            for entry in session.entries.iter_mut().filter(|e| e.ours != e.theirs) {
+                                let key = egui::RichText::new(&entry.key);
+                                ui.label(if entry.is_conflict() { key.color(egui::Color32::YELLOW) } else { key });
+                                ui.label(if entry.base.is_some() { describe(&entry.base) } else { String::new() });
+                                ui.radio_value(&mut entry.choice, merge::Side::Ours, describe(&entry.ours));
+                                ui.radio_value(&mut entry.choice, merge::Side::Theirs, describe(&entry.theirs));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    let mut apply = false;
+                    ui.horizontal(|ui| {
+                        apply = ui.button("Apply Merge").clicked();
+                        if ui.button("Cancel").clicked() {
+                            self.merge_session = None;
+                        }
+                    });
+                    if apply {
+                        self.apply_merge();
+                    }
+                });
+            }
+
+            if self.pending_load.is_some() {
+                // Placeholder rows until the background load delivers the matches
+                for _ in 0..8 {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 18.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+                    ui.separator();
+                }
+                return;
+            }
+
+            if let Some(raw) = self.raw_yaml.as_mut() {
+                let error = yaml_io::parse_error(&raw.text);
+                let dirty = raw.text != raw.base;
+                let (mut save, mut revert, mut toggled) = (false, false, None);
+                ui.horizontal(|ui| {
+                    save = ui.add_enabled(dirty && error.is_none(), egui::Button::new("Save and Sync")).clicked();
+                    revert = ui.add_enabled(dirty, egui::Button::new("Revert")).clicked();
+                    match &error {
+                        Some(error) => ui.colored_label(egui::Color32::RED, format!("⚠ {}", error)),
+                        None => ui.weak("Valid YAML"),
+                    };
+                    toggled = wrap_toggle(ui, &self.wrap_modes, wrap::YAML);
+                });
+                let soft_wrap = toggled.unwrap_or_else(|| self.wrap_modes.soft_wrap(wrap::YAML));
+                let mut layouter = |ui: &egui::Ui, source: &str, wrap_width: f32| {
+                    let mut job = yaml_job(ui, source);
+                    job.wrap.max_width = if soft_wrap { wrap_width } else { f32::INFINITY };
+                    ui.fonts(|fonts| fonts.layout_job(job))
+                };
+                let scroll = if soft_wrap { egui::ScrollArea::vertical() } else { egui::ScrollArea::both() };
+                scroll.id_source("raw_yaml").show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut raw.text)
+                            .code_editor()
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(20)
+                            .layouter(&mut layouter),
+                    );
+                });
+                if let Some(soft_wrap) = toggled {
+                    self.set_soft_wrap(wrap::YAML, soft_wrap);
+                }
+                if save {
+                    self.save_raw_yaml();
+                } else if revert {
+                    self.reload_if_changed();
+                    self.open_raw_yaml();
+                }
+                return;
+            }
+
+            let compact = self.compact.clone();
+            if let Some(compact) = compact {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Large file: {} matches in {:.1} MB, shown read-only",
+                        compact.len(),
+                        compact.memory_bytes() as f64 / (1024.0 * 1024.0)
+                    ));
+                    if ui.button("Load for Editing").clicked() {
+                        self.load_for_editing();
+                    }
+                });
+                let (query, scope) = (self.filter_query(), self.settings.filter_scope);
+                let rows: Vec<usize> = (0..compact.len()).filter(|&i| query.score(scope, compact.trigger(i), compact.replace(i)).is_some()).collect();
+                let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                egui::ScrollArea::vertical().id_source("compact_list").show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for &index in &rows[range] {
+                        ui.horizontal(|ui| {
+                            ui.label(text::reveal_invisible(compact.trigger(index)));
+                            ui.add(egui::Label::new(text::summarize(compact.replace(index))).wrap(false));
+                        });
+                    }
+                });
+                return;
+            }
+
+            let mut visible = self.filtered_matches();
+            self.sort_matches(&mut visible);
+            let selected_index = self.selected_index;
+            if self.selection.len() > 1 {
+                let indices: Vec<usize> = self.selection.iter().copied().collect();
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(format!("{} selected:", indices.len()));
+                    if ui.button("Enable Word Mode").on_hover_text("Only expand these triggers as whole words").clicked() {
+                        self.enable_word_mode(&indices);
+                    }
+                    if let Some((file, copy)) = transfer_menu(ui, &self.files, &self.selected_file) {
+                        self.transfer_matches(&indices, &file, copy);
+                    }
+                    if ui.button("Export…").clicked() {
+                        self.export_selected_only = true;
+                        self.show_export = true;
+                    }
+                    let wrapping = self.bulk_wrap.is_some();
+                    if ui.selectable_label(wrapping, "Wrap…").on_hover_text("Add text before and after each replacement").clicked() {
+                        self.bulk_wrap = if wrapping { None } else { Some(Default::default()) };
+                    }
+                    let confirming = self.confirm_bulk_delete;
+                    if confirming {
+                        ui.colored_label(egui::Color32::YELLOW, format!("Delete {} matches?", indices.len()));
+                        if ui.button("Delete").clicked() {
+                            self.delete_matches(&indices.iter().copied().collect());
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_bulk_delete = false;
+                        }
+                    } else if ui.button("Delete…").clicked() {
+                        self.confirm_bulk_delete = true;
+                    }
+                });
+                if let Some((prefix, suffix)) = self.bulk_wrap.as_mut() {
+                    let (mut wrap, mut cancel) = (false, false);
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Before:");
+                            ui.add(egui::TextEdit::multiline(prefix).desired_rows(1).desired_width(200.0).hint_text("e.g. ```"));
+                            ui.label("After:");
+                            ui.add(egui::TextEdit::multiline(suffix).desired_rows(1).desired_width(200.0).hint_text("e.g. a signature"));
+                        });
+                        let sample = indices.iter().find_map(|&i| self.matches.get(i).filter(|m| !m.replace.is_empty()));
+                        if let Some(sample) = sample.filter(|_| !prefix.is_empty() || !suffix.is_empty()) {
+                            ui.label(format!("{} becomes:", sample.display_trigger()));
+                            let wrapped = format!("{}{}{}", prefix, sample.replace, suffix);
+                            ui.label(diff_job(ui, &text::word_diff(&sample.replace, &wrapped), egui::TextStyle::Monospace));
+                        }
+                        ui.horizontal(|ui| {
+                            let ready = !prefix.is_empty() || !suffix.is_empty();
+                            wrap = ui.add_enabled(ready, egui::Button::new(format!("Wrap {} Replacements", indices.len()))).clicked();
+                            cancel = !wrap && ui.button("Cancel").clicked();
+                        });
+                    });
+                    if wrap {
+                        let (prefix, suffix) = self.bulk_wrap.take().unwrap_or_default();
+                        self.wrap_replacements(&indices, &prefix, &suffix);
+                    } else if cancel {
+                        self.bulk_wrap = None;
+                    }
+                }
+            }
+            let mut list_area = egui::ScrollArea::vertical().id_source("match_list");
+            if let Some(offset) = self.restore_scroll_offset.take() {
+                list_area = list_area.vertical_scroll_offset(offset);
+            }
+            let trigger_grid = self.trigger_grid;
+            let by_tag = self.group_by_tag && self.file_has_tags();
+            // Only the rows in view are laid out, so files with thousands of matches stay responsive
+            let list_output = if by_tag {
+                list_area.show(ui, |ui| tag_groups(ui, self, &visible))
+            } else if trigger_grid {
+                let cell_width = 140.0;
+                let columns = ((ui.available_width() / cell_width) as usize).max(1);
+                let rows = visible.len().div_ceil(columns);
+                let mut action = None;
+                let output = list_area.show_rows(ui, ui.spacing().interact_size.y, rows, |ui, range| {
+                    let selected_row = selected_index.and_then(|s| visible.iter().position(|&i| i == s)).map(|p| p / columns);
+                    scroll_to_row(ui, self, range.start, selected_row, ui.spacing().interact_size.y);
+                    let shown = &visible[range.start * columns..(range.end * columns).min(visible.len())];
+                    egui::Grid::new("trigger_grid").min_col_width(cell_width).max_col_width(cell_width).show(ui, |ui| {
+                        for (position, &index) in shown.iter().enumerate() {
+                            let Some(match_item) = self.matches.get(index) else { continue };
+                            let selected = selected_index == Some(index) || self.selection.contains(&index);
+                            let trigger = text::reveal_invisible(&match_item.display_trigger());
+                            let response = ui.add(egui::SelectableLabel::new(selected, trigger)).on_hover_text(match_item.summary());
+                            let response = response.interact(egui::Sense::drag());
+                            if response.drag_started() {
+                                action = Some(MatchAction::Drag(index));
+                            }
+                            if response.clicked() {
+                                action = Some(MatchAction::Click(index, ui.input(|i| i.modifiers)));
+                            }
+                            if (position + 1) % columns == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+                if let Some(action) = action {
+                    self.apply_match_action(action, &visible);
+                }
+                output
+            } else {
+                match_table_header(ui, self);
+                match_table(ui, self, list_area, &visible)
+            };
+            self.list_scroll_offset = list_output.state.offset.y;
+        });
+        // Files and tag headers had their chance to take the dropped matches
+        if ctx.input(|i| i.pointer.any_released()) {
+            self.dragged_matches = None;
+        }
+        
+        let error_dialog = self.error_dialog.clone();
+        if let Some(message) = error_dialog {
+            egui::Window::new("Error")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.colored_label(egui::Color32::RED, message);
+                    if ui.button("OK").clicked() {
+                        self.error_dialog = None;
+                    }
+                });
+        }
+
+        if self.show_lint {
+            let problems = self.problems.clone();
+            lint_window(ctx, self, &problems);
+        }
+
+        if self.show_search {
+            search_window(ctx, self);
+        } else {
+            // Read again the next time the search opens
+            self.search_index = None;
+        }
+        if self.show_usage {
+            usage_window(ctx, self);
+        }
+
+        let health_summary = self.health_summary.clone();
+        if let Some(summary) = health_summary {
+            egui::Window::new("Weekly Summary")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.weak(format!("Since {}", summary.since));
+                    for line in summary.lines() {
+                        ui.label(line);
+                    }
+                    ui.horizontal(|ui| {
+                        if !summary.new_duplicates.is_empty() && ui.button("Lint Summary…").clicked() {
+                            self.show_lint = true;
+                            self.record_health();
+                        }
+                        if ui.button("OK").clicked() {
+                            self.record_health();
+                        }
+                    });
+                });
+        }
+
+        let folder_picker = self.folder_picker.clone();
+        if let Some((dir, typed)) = folder_picker {
+            folder_picker_window(ctx, self, dir, typed);
+        }
+
+        let pending_delete = self.pending_delete.clone();
+        if let Some((index, trigger)) = pending_delete {
+            egui::Window::new("Delete Match")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!("Delete {} from {}?", trigger, self.selected_file));
+                    ui.weak("It stays in the trash until the helper closes.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            // The list may have been reloaded since the dialog opened
+                            if self.matches.get(index).is_some_and(|m| m.display_trigger() == trigger) {
+                                self.delete_match(index);
+                            } else {
+                                self.pending_delete = None;
+                                self.status = format!("{} changed in the meantime, nothing was deleted", trigger);
+                            }
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.pending_delete = None;
+                        }
+                    });
+                });
+        }
+
+        let pending_review = self.pending_review.clone();
+        if let Some(review) = pending_review.filter(|r| r.file == self.selected_file) {
+            egui::Window::new("Review Changes")
+                .collapsible(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!("Saving rewrites {}. Lines starting with - are removed, lines with + are added:", review.file));
+                    egui::ScrollArea::both().id_source("review_diff").max_height(350.0).show(ui, |ui| {
+                        ui.add(egui::Label::new(diff_job(ui, &text::unified(&review.lines, 3), egui::TextStyle::Monospace)).wrap(false));
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            self.accept_review();
+                        }
+                        if ui.button("Discard Changes").on_hover_text("Load the file from disk again").clicked() {
+                            self.discard_review();
+                        }
+                    });
+                });
+        }
+
+        if let Some(current) = &tour {
+            let step = current.current();
+            egui::Window::new(step.title())
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -40.0])
+                .show(ctx, |ui| {
+                    ui.label(step.text());
+                    ui.horizontal(|ui| {
+                        ui.weak(format!("Step {} of {}", current.step + 1, tour::TourStep::ALL.len()));
+                        if current.step > 0 && ui.button("Back").clicked() {
+                            if let Some(tour) = &mut self.tour {
+                                tour.step -= 1;
+                            }
+                        }
+                        if current.is_last() {
+                            if ui.button("Finish").clicked() {
+                                self.end_tour();
+                            }
+                        } else {
+                            if ui.button("Next").clicked() {
+                                if let Some(tour) = &mut self.tour {
+                                    tour.step += 1;
+                                }
+                            }
+                            if ui.button("Skip Tour").clicked() {
+                                self.end_tour();
+                            }
+                        }
+                    });
+                });
+        }
+    }
+}
+
+// Options set on a match, for the flags column
+fn match_flags(m: &Match) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+    if m.word {
+        flags.push("word");
+    }
+    if m.propagate_case {
+        flags.push("case");
+    }
+    if m.force_clipboard {
+        flags.push("paste");
+    }
+    if !m.vars.is_empty() {
+        flags.push("vars");
+    }
+    flags.extend(m.content_key());
+    flags
+}
+
+// Column titles; a click sorts by the column, ascending, descending, then unsorted,
+// and the handles between them trade width between neighbours
+fn match_table_header(ui: &mut egui::Ui, helper: &mut EspansoHelper) {
+    let height = ui.spacing().interact_size.y;
+    let width = ui.available_width() - ui.spacing().interact_size.y;
+    let (mut columns, mut sort) = (helper.match_columns, helper.match_sort);
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for (position, column) in MatchColumn::ALL.into_iter().enumerate() {
+            let arrow = match sort {
+                Some((sorted, false)) if sorted == column => " ⏶",
+                Some((sorted, true)) if sorted == column => " ⏷",
+                _ => "",
+            };
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width * columns[position] - COLUMN_HANDLE, height), egui::Sense::click());
+            let font = egui::TextStyle::Button.resolve(ui.style());
+            ui.painter_at(rect).text(rect.left_center() + egui::vec2(4.0, 0.0), egui::Align2::LEFT_CENTER, format!("{}{}", column.label(), arrow), font, ui.visuals().strong_text_color());
+            if response.on_hover_text(format!("Sort by {}", column.label().to_lowercase())).clicked() {
+                sort = match sort {
+                    Some((sorted, false)) if sorted == column => Some((column, true)),
+                    Some((sorted, true)) if sorted == column => None,
+                    _ => Some((column, false)),
+                };
+            }
+            let (handle, drag) = ui.allocate_exact_size(egui::vec2(COLUMN_HANDLE, height), egui::Sense::drag());
+            ui.painter().vline(handle.center().x, handle.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+            if position + 1 < columns.len() {
+                if drag.hovered() || drag.dragged() {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                }
+                let shift = (drag.drag_delta().x / width.max(1.0)).clamp(0.05 - columns[position], columns[position + 1] - 0.05);
+                columns[position] += shift;
+                columns[position + 1] -= shift;
+            }
+        }
+    });
+    helper.match_columns = columns;
+    helper.match_sort = sort;
+}
+
+// The visible matches under a collapsible header per tag; a match with several tags
+// shows under each. Matches dropped on a header get its tag.
+fn tag_groups(ui: &mut egui::Ui, helper: &mut EspansoHelper, visible: &[usize]) {
+    let (mut groups, mut untagged) = (BTreeMap::<&str, Vec<usize>>::new(), Vec::new());
+    for &index in visible {
+        let tags = helper.matches.get(index).and_then(|m| helper.metadata.get(m)).map_or(&[][..], |meta| meta.tags.as_slice());
+        if tags.is_empty() {
+            untagged.push(index);
+        }
+        for tag in tags {
+            groups.entry(tag).or_default().push(index);
+        }
+    }
+    let released = ui.input(|i| i.pointer.any_released());
+    let (mut action, mut dropped_on) = (None, None);
+    let mut rows = |ui: &mut egui::Ui, indices: &[usize]| {
+        for &index in indices {
+            let Some(m) = helper.matches.get(index) else { continue };
+            ui.horizontal(|ui| {
+                let selected = helper.selected_index == Some(index) || helper.selection.contains(&index);
+                let response = ui.add(egui::SelectableLabel::new(selected, text::reveal_invisible(&m.display_trigger())));
+                let response = response.interact(egui::Sense::drag());
+                if response.drag_started() {
+                    action = Some(MatchAction::Drag(index));
+                }
+                if response.double_clicked() {
+                    action = Some(MatchAction::Edit(index));
+                } else if response.clicked() {
+                    action = Some(MatchAction::Click(index, ui.input(|i| i.modifiers)));
+                }
+                ui.add(egui::Label::new(egui::RichText::new(m.summary()).weak()).wrap(false));
+            });
+        }
+    };
+    for (tag, indices) in &groups {
+        let group = egui::CollapsingHeader::new(format!("🏷 {} ({})", tag, indices.len()))
+            .id_source(("tag_group", tag))
+            .default_open(true)
+            .show(ui, |ui| rows(ui, indices));
+        let header = group.header_response.rect;
+        if helper.dragged_matches.is_some() && ui.rect_contains_pointer(header) {
+            ui.painter().rect_stroke(header, 2.0, ui.visuals().selection.stroke);
+            if released {
+                dropped_on = Some(tag.to_string());
+            }
+        }
+    }
+    if !untagged.is_empty() {
+        egui::CollapsingHeader::new(format!("Untagged ({})", untagged.len()))
+            .id_source("tag_group_untagged")
+            .default_open(true)
+            .show(ui, |ui| rows(ui, &untagged));
+    }
+    if let Some(action) = action {
+        helper.apply_match_action(action, visible);
+    }
+    if let (Some(tag), Some(indices)) = (dropped_on, helper.dragged_matches.clone()) {
+        helper.tag_matches(&indices, &tag);
+    }
+}
+
+// Scrolls a list laid out with `show_rows` to `row` after the selection moved with the
+// keyboard; the row may be outside the laid out range starting at `first`
+fn scroll_to_row(ui: &mut egui::Ui, helper: &mut EspansoHelper, first: usize, row: Option<usize>, height: f32) {
+    if !std::mem::take(&mut helper.scroll_to_selected) {
+        return;
+    }
+    let Some(row) = row else { return };
+    let top = ui.max_rect().top() + (row as f32 - first as f32) * (height + ui.spacing().item_spacing.y);
+    ui.scroll_to_rect(egui::Rect::from_min_size(egui::pos2(ui.max_rect().left(), top), egui::vec2(1.0, height)), None);
+}
+
+// A field in place of the trigger or replacement cell of the row edited inline; None
+// for cells that stay as they are. Enter saves, Escape cancels.
+fn inline_cell(ui: &mut egui::Ui, edit: &mut InlineEdit, column: MatchColumn, size: egui::Vec2) -> Option<Option<MatchAction>> {
+    let text = match column {
+        MatchColumn::Trigger => &mut edit.trigger,
+        MatchColumn::Replacement => edit.replace.as_mut()?,
+        _ => return None,
+    };
+    let response = ui.add_sized(size, egui::TextEdit::singleline(text));
+    if column == MatchColumn::Trigger && std::mem::take(&mut edit.focus) {
+        response.request_focus();
+    }
+    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+        Some(Some(MatchAction::SaveInline))
+    } else if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+        Some(Some(MatchAction::CancelInline))
+    } else {
+        Some(None)
+    }
+}
+
+// The match list as table rows, laying out only those in view. Clicking a multi-line
+// replacement shows it in full below the row.
+fn match_table(ui: &mut egui::Ui, helper: &mut EspansoHelper, area: egui::ScrollArea, visible: &[usize]) -> egui::scroll_area::ScrollAreaOutput<()> {
+    let height = ui.spacing().interact_size.y;
+    let width = ui.available_width() - height;
+    let file = helper.selected_file.rsplit('/').next().unwrap_or_default().to_string();
+    let inline_edit = helper.inline_edit.as_ref().filter(|edit| edit.file == helper.selected_file).map(|edit| edit.index);
+    let mut action = None;
+    let output = area.show_rows(ui, height, visible.len(), |ui, range| {
+        let selected_row = helper.selected_index.and_then(|s| visible.iter().position(|&i| i == s));
+        scroll_to_row(ui, helper, range.start, selected_row, height);
+        for &index in &visible[range] {
+            let Some(m) = helper.matches.get(index) else { continue };
+            let problems = helper.match_problems(index);
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                let row = egui::Rect::from_min_size(ui.cursor().min, egui::vec2(width, height));
+                if helper.selected_index == Some(index) || helper.selection.contains(&index) {
+                    ui.painter().rect_filled(row, 2.0, ui.visuals().selection.bg_fill);
+                }
+                let suspicious = !text::audit(&m.trigger).is_empty() || !text::audit(&m.replace).is_empty();
+                let text_color = ui.visuals().text_color();
+                let mut flags = match_flags(m).join(", ");
+                if let Some((count, _)) = &problems {
+                    flags = format!("⚠ {} {}", count, flags);
+                }
+                let cells = [
+                    (text::reveal_invisible(&m.display_trigger()), if suspicious { egui::Color32::YELLOW } else { text_color }),
+                    (m.preview(), text_color),
+                    (m.label.clone().unwrap_or_default(), text_color),
+                    (flags, if problems.is_some() { egui::Color32::from_rgb(255, 140, 0) } else { text_color }),
+                    (file.clone(), ui.visuals().weak_text_color()),
+                ];
+                for (position, (text, color)) in cells.into_iter().enumerate() {
+                    let size = egui::vec2(width * helper.match_columns[position], height);
+                    let edited = helper.inline_edit.as_mut().filter(|_| inline_edit == Some(index));
+                    if let Some(done) = edited.and_then(|edit| inline_cell(ui, edit, MatchColumn::ALL[position], size)) {
+                        if done.is_some() {
+                            action = done;
+                        }
+                        continue;
+                    }
+                    let (rect, mut response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+                    let font = egui::TextStyle::Body.resolve(ui.style());
+                    ui.painter_at(rect.shrink2(egui::vec2(4.0, 0.0))).text(rect.left_center() + egui::vec2(4.0, 0.0), egui::Align2::LEFT_CENTER, text, font, color);
+                    match MatchColumn::ALL[position] {
+                        MatchColumn::Trigger if suspicious => {
+                            response = response.on_hover_text("Contains invisible or unnormalized characters, see Unicode Audit");
+                        }
+                        MatchColumn::Flags => {
+                            if let Some((_, details)) = &problems {
+                                response = response.on_hover_text(details);
+                            }
+                        }
+                        _ => {}
+                    }
+                    if response.drag_started() {
+                        action = Some(MatchAction::Drag(index));
+                    }
+                    if response.double_clicked() {
+                        action = Some(MatchAction::EditInline(index));
+                    } else if response.clicked() {
+                        action = Some(MatchAction::Click(index, ui.input(|i| i.modifiers)));
+                    }
+                    if MatchColumn::ALL[position] == MatchColumn::Replacement && m.replace.trim_end().contains('\n') {
+                        let popup = ui.make_persistent_id(("replacement_popup", index));
+                        if response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(popup));
+                        }
+                        egui::popup::popup_below_widget(ui, popup, &response, |ui| {
+                            ui.set_min_width(rect.width().max(240.0));
+                            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| ui.label(&m.replace));
+                        });
+                    }
+                }
+                ui.menu_button("⋯", |ui| {
+                    if ui.button("Edit").clicked() {
+                        action = Some(MatchAction::Edit(index));
+                        ui.close_menu();
+                    }
+                    if ui.button("Try it").on_hover_text("Open the sandbox with this trigger typed").clicked() {
+                        action = Some(MatchAction::Try(index));
+                        ui.close_menu();
+                    }
+                    if let Some((file, copy)) = transfer_menu(ui, &helper.files, &helper.selected_file) {
+                        action = Some(MatchAction::Transfer(vec![index], file, copy));
+                    }
+                    if ui.button("Delete").clicked() {
+                        action = Some(MatchAction::Delete(index));
+                        ui.close_menu();
+                    }
+                });
+            });
+        }
+    });
+    if let Some(action) = action {
+        helper.apply_match_action(action, visible);
+    }
+    output
+}
+
+// How well the trigger or replacement of `m` fits the filter, None if not at all
+fn match_score(query: &filter::Query, scope: filter::FilterScope, m: &Match) -> Option<i64> {
+    query.score(scope, &m.display_trigger(), &m.replace)
+}
+
+// Gives the trigger (or regex) field the keyboard focus after Ctrl+N
+fn focus_new_match(helper: &mut EspansoHelper, field: &egui::Response) {
+    if std::mem::take(&mut helper.focus_new_match) {
+        field.request_focus();
+        field.scroll_to_me(Some(egui::Align::Center));
+    }
+    scroll_to_focus(field);
+}
+
+// Keeps a widget reached with Tab in view in the scrolling detail panel
+fn scroll_to_focus(response: &egui::Response) {
+    if response.gained_focus() {
+        response.scroll_to_me(None);
+    }
+}
+
+fn severity_color(severity: validation::Severity) -> egui::Color32 {
+    match severity {
+        validation::Severity::Error => egui::Color32::RED,
+        validation::Severity::Warning => egui::Color32::YELLOW,
+        validation::Severity::Info => egui::Color32::LIGHT_BLUE,
+    }
+}
+
+// Global variables of all files, with a rename that updates every reference
+fn global_vars_panel(ui: &mut egui::Ui, helper: &mut EspansoHelper) {
+    if helper.global_var_definitions.is_empty() {
+        ui.label("No file defines global variables.");
+    }
+    let (mut preview, mut started) = (false, None);
+    egui::Grid::new("global_vars").striped(true).show(ui, |ui| {
+        for definition in &helper.global_var_definitions {
+            ui.monospace(format!("{{{{{}}}}}", definition.name));
+            ui.label(&definition.var_type);
+            ui.label(&definition.file);
+            if let Some((_, new)) = helper.global_var_rename.as_mut().filter(|(old, _)| *old == definition.name) {
+                let field = ui.add(egui::TextEdit::singleline(new).hint_text("new name").desired_width(120.0));
+                if field.changed() {
+                    helper.global_var_plan = None;
+                }
+                preview = ui.button("Preview").clicked() || (field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+                if ui.button("Cancel").clicked() {
+                    helper.global_var_rename = None;
+                    helper.global_var_plan = None;
+                }
+            } else if ui.button("Rename…").clicked() {
+                started = Some(definition.name.clone());
+            }
+            ui.end_row();
+        }
+    });
+    if preview {
+        helper.plan_global_var_rename();
+    }
+    if let Some(name) = started {
+        helper.global_var_rename = Some((name.clone(), name));
+        helper.global_var_plan = None;
+    }
+    let Some(plan) = &helper.global_var_plan else { return };
+    ui.separator();
+    ui.label(format!("Renaming {} to {} changes {} places in {}:", plan.old, plan.new, plan.changes.len(), plan.files().join(", ")));
+    egui::ScrollArea::vertical().id_source("global_var_rename_preview").max_height(250.0).show(ui, |ui| {
+        for change in &plan.changes {
+            ui.strong(format!("{}: {}", change.file, change.subject));
+            ui.label(diff_job(ui, &text::word_diff(&change.before, &change.after), egui::TextStyle::Monospace));
+        }
+    });
+    if ui.button(format!("Rename in {} Files", plan.files().len())).clicked() {
+        helper.apply_global_var_rename();
+    }
+}
+
+// Characters the inject backend has trouble with, per class with a few examples
+fn char_stats(ui: &mut egui::Ui, stats: &[espanso_config::CharStats]) {
+    for stats in stats {
+        ui.colored_label(egui::Color32::YELLOW, format!("{} {} ({})", stats.count, stats.class.name(), stats.examples))
+            .on_hover_text("Typed key by key, espanso has to emulate these, which fails in some applications");
+    }
+}
+
+// Findings per rule and severity, with the rules' settings; a changed rule re-runs the check
+fn lint_window(ctx: &egui::Context, helper: &mut EspansoHelper, problems: &validation::Report) {
+    let mut open = true;
+    let mut rules = helper.settings.lint.clone();
+    egui::Window::new("Lint Summary").open(&mut open).collapsible(false).show(ctx, |ui| {
+        let counts = problems.counts();
+        egui::Grid::new("lint_rules").striped(true).show(ui, |ui| {
+            ui.strong("Rule");
+            ui.strong("Severity");
+            ui.strong("Found");
+            ui.end_row();
+            for kind in validation::ProblemKind::RULES {
+                let mut setting = rules.get(kind);
+                ui.checkbox(&mut setting.enabled, kind.name());
+                ui.add_enabled_ui(setting.enabled, |ui| {
+                    egui::ComboBox::from_id_source(kind.key()).selected_text(setting.severity.name()).show_ui(ui, |ui| {
+                        for severity in validation::Severity::ALL {
+                            ui.selectable_value(&mut setting.severity, severity, severity.name());
+                        }
+                    });
+                });
+                let found: usize = counts.iter().filter(|((_, name), _)| *name == kind.name()).map(|(_, count)| count).sum();
+                ui.colored_label(if found > 0 { severity_color(setting.severity) } else { ui.visuals().weak_text_color() }, found.to_string());
+                ui.end_row();
+                if setting != rules.get(kind) {
+                    rules.set(kind, setting);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Large replacements start at");
+            ui.add(egui::DragValue::new(&mut rules.large_replacement_kb).clamp_range(1..=1024).suffix(" KB"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Triggers start with");
+            ui.add(egui::TextEdit::singleline(&mut rules.trigger_prefix).desired_width(40.0))
+                .on_hover_text("Leave empty to accept any trigger");
+        });
+        ui.separator();
+        for severity in validation::Severity::ALL {
+            let total: usize = counts.iter().filter(|((s, _), _)| *s == severity).map(|(_, count)| count).sum();
+            ui.colored_label(severity_color(severity), format!("{}: {}", severity.name(), total));
+        }
+        let mut by_file: Vec<(&String, usize)> = problems.files.iter().map(|(file, found)| (file, found.len())).collect();
+        by_file.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        if !by_file.is_empty() {
+            ui.separator();
+            for (file, count) in by_file {
+                ui.horizontal(|ui| {
+                    if ui.link(file).clicked() {
+                        helper.select_file(file.clone());
+                    }
+                    ui.label(format!("{} findings", count));
+                });
+            }
+        }
+    });
+    helper.show_lint = open;
+    if rules != helper.settings.lint {
+        helper.settings.lint = rules;
+        helper.last_validation = None;
+    }
+}
+
+// The usage insights the helper keeps locally, once the user opted in
+fn usage_window(ctx: &egui::Context, helper: &mut EspansoHelper) {
+    let mut open = true;
+    egui::Window::new("Usage Insights").open(&mut open).collapsible(false).show(ctx, |ui| {
+        if !helper.settings.usage_insights {
+            ui.label("Nothing is counted while usage insights are off in the settings.");
+        }
+        ui.weak("Kept on this computer only, nothing is ever sent anywhere.");
+        let Some(since) = &helper.usage.since else { return };
+        ui.label(format!("Since {}", since));
+        ui.separator();
+        ui.strong("Most edited files");
+        egui::Grid::new("usage_files").striped(true).show(ui, |ui| {
+            for (file, count) in helper.usage.most_edited(10) {
+                ui.label(file);
+                ui.label(format!("{} changes", count));
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.strong("Bulk operations");
+        egui::Grid::new("usage_bulk").striped(true).show(ui, |ui| {
+            for operation in usage::BulkOperation::ALL {
+                ui.label(operation.name());
+                ui.label(helper.usage.bulk_count(operation).to_string());
+                ui.end_row();
+            }
+        });
+        if ui.add_enabled(!helper.safe_mode, egui::Button::new("Reset")).clicked() {
+            helper.usage = usage::Usage::default();
+            if let Err(error) = helper.usage.save() {
+                helper.status = format!("Resetting the usage insights failed: {}", error);
+            }
+        }
+    });
+    helper.show_usage = open;
+}
+
+// Finds where a trigger or text comes from across all match files. Hits in the user's
+// own files open in the editor; those in installed packages are only shown.
+fn search_window(ctx: &egui::Context, helper: &mut EspansoHelper) {
+    if helper.search_index.is_none() {
+        helper.refresh_search_index();
+    }
+    let mut open = true;
+    let mut opened = None;
+    egui::Window::new("Search All Files").open(&mut open).default_width(480.0).show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let field = ui.add(egui::TextEdit::singleline(&mut helper.search_query).hint_text("Trigger or text"));
+            if std::mem::take(&mut helper.focus_search) {
+                field.request_focus();
+            }
+            if helper.pending_search_index.is_some() {
+                ui.spinner();
+            }
+        });
+        let Some(entries) = &helper.search_index else { return };
+        let hits = search::search(entries, &helper.search_query);
+        if helper.search_query.trim().is_empty() {
+            ui.weak(format!("{} matches in {} files", entries.len(), helper.files.len()));
+            return;
+        }
+        ui.label(format!("{} results", hits.len()));
+        egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+            egui::Grid::new("search_results").num_columns(3).striped(true).show(ui, |ui| {
+                for (entry, rank) in hits {
+                    let trigger = entry.triggers.first().map_or("", String::as_str);
+                    let preview = text::summarize(&entry.replace);
+                    match &entry.package {
+                        Some(package) => {
+                            ui.label(text::reveal_invisible(trigger)).on_hover_text(&entry.replace);
+                            ui.weak(format!("package {} (read-only)", package));
+                        }
+                        None => {
+                            if ui.link(text::reveal_invisible(trigger)).on_hover_text(&entry.replace).clicked() {
+                                opened = Some((entry.file.clone(), entry.index));
+                            }
+                            ui.label(&entry.file);
+                        }
+                    }
+                    ui.weak(format!("{}: {}", rank.label(), preview));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+    if let Some((file, index)) = opened {
+        helper.open_match(&file, index);
+    }
+    helper.show_search = open;
+}
+
+// Text collected by the clipboard watcher, each one ready to become a match
+fn clipboard_candidates(ui: &mut egui::Ui, helper: &mut EspansoHelper) {
+    let candidates = &helper.clipboard.candidates;
+    if candidates.is_empty() {
+        return;
+    }
+    let (mut used, mut dismissed, mut cleared) = (None, None, false);
+    ui.collapsing(format!("Copied Text ({})", candidates.len()), |ui| {
+        for (index, text) in candidates.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("Use").clicked() {
+                    used = Some(text.clone());
+                }
+                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                    dismissed = Some(index);
+                }
+                ui.add(egui::Label::new(text::summarize(text)).wrap(false)).on_hover_text(text);
+            });
+        }
+        cleared = ui.small_button("Clear").clicked();
+    });
+    if cleared {
+        helper.clipboard.candidates.clear();
+    }
+    if let Some(index) = dismissed {
+        helper.clipboard.candidates.remove(index);
+    }
+    if let Some(text) = used {
+        helper.new_match_with_replacement(text);
+    }
+}
+
+// Browses folders for the match folder, since there is no native dialog to ask
+fn folder_picker_window(ctx: &egui::Context, helper: &mut EspansoHelper, dir: PathBuf, mut typed: String) {
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir() && !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.') && name != ".espanso"))
+        .collect();
+    subdirs.sort();
+    let match_files = list_yaml_files(&dir).len();
+    let mut open = true;
+    let mut next: Option<PathBuf> = None;
+    egui::Window::new("Choose Match Folder")
+        .open(&mut open)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(dir.parent().is_some(), egui::Button::new("⬆ Up")).clicked() {
+                    next = dir.parent().map(Path::to_path_buf);
+                }
+                let field = ui.add(egui::TextEdit::singleline(&mut typed).desired_width(320.0));
+                if field.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    next = Some(PathBuf::from(typed.trim()));
+                }
+            });
+            egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                for subdir in &subdirs {
+                    let name = subdir.file_name().unwrap_or_default().to_string_lossy();
+                    if ui.selectable_label(false, format!("📁 {}", name)).clicked() {
+                        next = Some(subdir.clone());
+                    }
+                }
+                if subdirs.is_empty() {
+                    ui.weak("No folders in here");
+                }
+            });
+            ui.weak(match match_files {
+                0 => "No match files in this folder".to_string(),
+                1 => "1 match file in this folder".to_string(),
+                count => format!("{} match files in this folder", count),
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Use This Folder").clicked() {
+                    helper.folder_picker = None;
+                    helper.set_config_dir(Some(dir.clone()));
+                }
+                if ui.button("Cancel").clicked() {
+                    helper.folder_picker = None;
+                }
+            });
+        });
+    if !open {
+        helper.folder_picker = None;
+    } else if let Some(picker) = helper.folder_picker.as_mut() {
+        match next {
+            Some(next) if next.is_dir() => *picker = (next.clone(), next.display().to_string()),
+            Some(next) => helper.status = format!("{} is not a folder", display_path(&next)),
+            None => picker.1 = typed,
+        }
+    }
+}
+
+// "Move…" menu offering every other match file as destination; the file picked and
+// whether to copy
+fn transfer_menu(ui: &mut egui::Ui, files: &[String], selected_file: &str) -> Option<(String, bool)> {
+    let mut chosen = None;
+    ui.menu_button("Move…", |ui| {
+        let others: Vec<&String> = files.iter().filter(|f| *f != selected_file).collect();
+        if others.is_empty() {
+            ui.label("There is no other match file");
+        }
+        for file in others {
+            ui.horizontal(|ui| {
+                ui.label(file);
+                if ui.button("Move").clicked() {
+                    chosen = Some((file.clone(), false));
+                    ui.close_menu();
+                }
+                if ui.button("Copy").clicked() {
+                    chosen = Some((file.clone(), true));
+                    ui.close_menu();
+                }
+            });
+        }
+    });
+    chosen
+}
+
+// Removed parts struck through in red, added parts highlighted in green
+fn diff_job(ui: &egui::Ui, parts: &[(text::DiffOp, String)], style: egui::TextStyle) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let font = style.resolve(ui.style());
+    for (op, part) in parts {
+        let mut format = egui::TextFormat { font_id: font.clone(), color: ui.visuals().text_color(), ..Default::default() };
+        match op {
+            text::DiffOp::Same => {}
+            text::DiffOp::Removed => {
+                format.color = egui::Color32::from_rgb(230, 90, 90);
+                format.strikethrough = egui::Stroke::new(1.0, format.color);
+            }
+            text::DiffOp::Added => {
+                format.color = egui::Color32::from_rgb(90, 200, 90);
+                format.background = egui::Color32::from_rgba_unmultiplied(90, 200, 90, 40);
+            }
+        }
+        job.append(part, 0.0, format);
+    }
+    job
+}
+
+// Switches an editor between soft wrapping and horizontal scrolling, remembered per hint;
+// the new mode when it was switched
+fn wrap_toggle(ui: &mut egui::Ui, wrap_modes: &wrap::WrapModes, hint: &str) -> Option<bool> {
+    let mut soft_wrap = wrap_modes.soft_wrap(hint);
+    let toggle = ui
+        .toggle_value(&mut soft_wrap, "↩ Wrap")
+        .on_hover_text(format!("Wrap long lines instead of scrolling sideways, remembered for {} editors", hint));
+    toggle.changed().then_some(soft_wrap)
+}
+
+// YAML source colored by `highlight::yaml`, for the raw editor
+fn yaml_job(ui: &egui::Ui, source: &str) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    for (range, token) in highlight::yaml(source) {
+        let color = match token {
+            highlight::Token::Plain => ui.visuals().text_color(),
+            highlight::Token::Comment => egui::Color32::GRAY,
+            highlight::Token::Key => egui::Color32::from_rgb(110, 160, 230),
+            highlight::Token::String => egui::Color32::from_rgb(150, 200, 110),
+            highlight::Token::Variable => egui::Color32::from_rgb(230, 160, 70),
+            highlight::Token::Punctuation => ui.visuals().weak_text_color(),
+        };
+        job.append(&source[range], 0.0, egui::TextFormat { font_id: font.clone(), color, ..Default::default() });
+    }
+    job
+}
+
+// Outlines the widget the tour is currently explaining
+fn tour_highlight(ctx: &egui::Context, tour: Option<&tour::Tour>, step: tour::TourStep, rect: egui::Rect) {
+    if tour.is_some_and(|t| t.current() == step) {
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tour_highlight")));
+        painter.rect_stroke(rect.expand(4.0), 4.0, egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE));
+    }
+}
+
+// espanso's match folder as the installed espanso reports it, else the usual location
+fn default_config_dir() -> PathBuf {
+    espanso::detect_match_dir().unwrap_or_else(|| dirs::config_dir().unwrap_or_default().join("espanso").join("match"))
+}
+
+// Where the helper keeps its own files
+fn app_config_dir() -> PathBuf {
+    dirs::config_dir().unwrap_or_default().join("espanso-helper")
+}
+
+// Shows paths below the home directory as `~/…`, keeping user names out of reports
+fn display_path(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+// Opens a folder or URL with the platform's default handler
+// Errors surface in the status bar; a GUI launch has no console to print them to
+fn open_in_system(target: &str) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    // Linux and the BSDs
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let opener = "xdg-open";
+
+    let mut child = Command::new(opener).arg(target).spawn()?;
+    // Reap the opener in the background so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn build_trigger_index(
+    config_dir: &Path,
+    files: &[String],
+    selected_file: &str,
+    current_matches: &[Match],
+    control: Option<&TaskControl>,
+) -> HashMap<String, Vec<String>> {
+    let mut index: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some(control) = control {
+        control.set_total(files.len());
+    }
+    for file in files {
+        let matches = if file == selected_file {
+            current_matches.to_vec()
+        } else {
+            read_matches(&config_dir.join(file))
+        };
+        for trigger in matches.iter().flat_map(Match::all_triggers) {
+            let files = index.entry(trigger).or_default();
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+        if let Some(control) = control {
+            control.advance();
+        }
+    }
+    index
+}
+
+// Reads a match file, using the compact store for large files when `allow_compact` is set
+fn read_match_file(file_path: &Path, allow_compact: bool) -> LoadedFile {
+    let large = fs::metadata(file_path).is_ok_and(|meta| meta.len() > compact::LARGE_FILE_BYTES);
+    if allow_compact && large {
+        let parsed = fs::read_to_string(file_path).map_err(|e| e.to_string()).and_then(|c| compact::CompactMatches::parse(&c));
+        // Files the compact reader can't handle still open the regular way
+        if let Ok(compact) = parsed {
+            return LoadedFile::Compact(Arc::new(compact));
+        }
+    }
+    let document = yaml_io::MatchFile::load(file_path);
+    let matches = document.matches();
+    LoadedFile::Full(Box::new(document), matches)
+}
+
+fn read_matches(file_path: &Path) -> Vec<Match> {
+    yaml_io::MatchFile::load(file_path).matches()
+}
+
+// Where `m`, found at `index` before a reload, is now: the same match, preferably at the
+// same place, or else the one with the same trigger
+fn relocate(m: &Match, matches: &[Match], index: usize) -> Option<usize> {
+    if matches.get(index) == Some(m) {
+        return Some(index);
+    }
+    matches.iter().position(|other| other == m).or_else(|| matches.iter().position(|other| other.display_trigger() == m.display_trigger()))
+}
+
+// Match files below `dir` as paths relative to it with `/` between folders, like
+// `packages/emoji/package.yml`; espanso loads those in subfolders too. Files at the top
+// come first. Hidden folders are skipped.
+fn list_yaml_files(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((folder, prefix)) = pending.pop() {
+        for path in fs::read_dir(&folder).into_iter().flatten().filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+            if path.is_dir() {
+                if !name.starts_with('.') {
+                    pending.push((path.clone(), format!("{}{}/", prefix, name)));
+                }
+            } else if path.extension().is_some_and(|ext| ext == "yml" || ext == "yaml") {
+                files.push(format!("{}{}", prefix, name));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.contains('/').cmp(&b.contains('/')).then(a.cmp(b)));
+    files
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    path.parent().map_or(Ok(()), fs::create_dir_all)
+}
+
+// espanso skips files whose name starts with `_` unless a config includes them
+fn is_included_only(file: &str) -> bool {
+    file.rsplit('/').next().is_some_and(|name| name.starts_with('_'))
+}
+
+// A file as listed in the sidebar and the file selector, `_` files in italics
+fn file_label(file: &str, shown: &str, problems: usize) -> egui::RichText {
+    let label = egui::RichText::new(if problems > 0 { format!("{}  ⚠ {}", shown, problems) } else { shown.to_string() });
+    if is_included_only(file) { label.italics() } else { label }
+}
+
+const INCLUDED_ONLY_HINT: &str = "espanso only loads files starting with _ when a config file includes them";
+
+// The folder part of a file from `list_yaml_files`, empty at the top
+fn file_folder(file: &str) -> &str {
+    file.rsplit_once('/').map_or("", |(folder, _)| folder)
+}
+
+fn main() -> Result<(), eframe::Error> {
+    logging::init();
+    let safe_mode = std::env::args().skip(1).any(|arg| arg == "--safe-mode");
+    if std::env::args().skip(1).any(|arg| arg == "--quick-add") {
+        return eframe::run_native("Quick Add", quick_add::options(), Box::new(|_cc| Box::new(quick_add::QuickAdd::new())));
+    }
+    #[cfg(all(feature = "tray", target_os = "linux"))]
+    if std::env::args().skip(1).any(|arg| arg == "--tray") {
+        if let Err(error) = tray::run(&settings::Settings::load().quick_add_shortcut) {
+            log::error!("The tray stopped: {}", error);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let window = settings::Settings::load().window;
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(800.0, 600.0)),
+        initial_window_size: Some(window.map_or(egui::vec2(800.0, 600.0), |w| egui::vec2(w.width, w.height))),
+        initial_window_pos: window.map(|w| egui::pos2(w.x, w.y)),
         ..Default::default()
     };
     eframe::run_native(
         "Espanso Helper",
         options,
-        Box::new(|_cc| Box::new(EspansoHelper::default())),
+        Box::new(move |_cc| Box::new(EspansoHelper::new(safe_mode))),
     )
 }
\ No newline at end of file
@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::yaml_io::MatchFile;
+use crate::Match;
+
+// Where the other version of a conflicted match file comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictSource {
+    // A Syncthing `<name>.sync-conflict-<date>-<device>.yml` copy next to the file
+    SyncFile(PathBuf),
+    // git conflict markers inside the file itself
+    Markers,
+}
+
+impl ConflictSource {
+    pub fn describe(&self) -> String {
+        match self {
+            ConflictSource::SyncFile(path) => format!(
+                "Syncthing conflict copy {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            ConflictSource::Markers => "git conflict markers".to_string(),
+        }
+    }
+}
+
+pub fn detect(file_path: &Path) -> Option<ConflictSource> {
+    let contents = fs::read_to_string(file_path).unwrap_or_default();
+    if split_markers(&contents).is_some() {
+        return Some(ConflictSource::Markers);
+    }
+    let stem = file_path.file_stem()?.to_str()?;
+    let prefix = format!("{}.sync-conflict-", stem);
+    let mut copies: Vec<PathBuf> = fs::read_dir(file_path.parent()?)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with(&prefix) && path.extension() == file_path.extension()
+        })
+        .collect();
+    copies.sort();
+    copies.pop().map(ConflictSource::SyncFile)
+}
+
+// The two (or, with diff3-style markers, three) versions interleaved in a conflicted file
+struct Sides {
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+}
+
+fn split_markers(contents: &str) -> Option<Sides> {
+    #[derive(PartialEq)]
+    enum Section {
+        Common,
+        Ours,
+        Base,
+        Theirs,
+    }
+    let mut section = Section::Common;
+    let mut found = false;
+    let mut has_base = false;
+    let (mut ours, mut base, mut theirs) = (String::new(), String::new(), String::new());
+    for line in contents.split_inclusive('\n') {
+        let marker = |m: &str| line.starts_with(m);
+        if marker("<<<<<<<") && section == Section::Common {
+            section = Section::Ours;
+            found = true;
+        } else if marker("|||||||") && section == Section::Ours {
+            section = Section::Base;
+            has_base = true;
+        } else if marker("=======") && matches!(section, Section::Ours | Section::Base) {
+            section = Section::Theirs;
+        } else if marker(">>>>>>>") && section == Section::Theirs {
+            section = Section::Common;
+        } else {
+            match section {
+                Section::Common => {
+                    ours.push_str(line);
+                    base.push_str(line);
+                    theirs.push_str(line);
+                }
+                Section::Ours => ours.push_str(line),
+                Section::Base => base.push_str(line),
+                Section::Theirs => theirs.push_str(line),
+            }
+        }
+    }
+    found.then(|| Sides { ours, base: has_base.then_some(base), theirs })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+// One trigger with its version on each side; `None` means the side doesn't have it
+#[derive(Debug, Clone)]
+pub struct MergeEntry {
+    pub key: String,
+    pub base: Option<Match>,
+    pub ours: Option<Match>,
+    pub theirs: Option<Match>,
+    // Whether the common version of the file is known; `base` is then None for matches
+    // it didn't have
+    pub has_base: bool,
+    pub choice: Side,
+}
+
+impl MergeEntry {
+    // Both sides changed the match differently, or there is no base to tell
+    pub fn is_conflict(&self) -> bool {
+        self.ours != self.theirs && (!self.has_base || (self.ours != self.base && self.theirs != self.base))
+    }
+
+    pub fn chosen(&self) -> Option<&Match> {
+        match self.choice {
+            Side::Ours => self.ours.as_ref(),
+            Side::Theirs => self.theirs.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeSession {
+    pub source: ConflictSource,
+    // Document the result is written into
+    pub document: MatchFile,
+    pub entries: Vec<MergeEntry>,
+}
+
+impl MergeSession {
+    // `current` is the file as loaded, which for marker conflicts doesn't parse
+    pub fn open(file_path: &Path, source: ConflictSource, current: &MatchFile) -> Result<Self, String> {
+        let (document, ours, base, theirs) = match &source {
+            ConflictSource::SyncFile(copy) => {
+                let theirs = MatchFile::load(copy).matches();
+                (current.clone(), current.matches(), None, theirs)
+            }
+            ConflictSource::Markers => {
+                let contents = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+                let sides = split_markers(&contents).ok_or("the conflict markers are gone")?;
+                let document = MatchFile::parse(&sides.ours);
+                let ours = document.matches();
+                let base = sides.base.map(|b| MatchFile::parse(&b).matches());
+                (document, ours, base, MatchFile::parse(&sides.theirs).matches())
+            }
+        };
+        Ok(Self { source, document, entries: entries(base.as_deref(), &ours, &theirs) })
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_conflict()).count()
+    }
+
+    pub fn resolve(&self) -> Vec<Match> {
+        self.entries.iter().filter_map(|e| e.chosen().cloned()).collect()
+    }
+}
+
+// Pairs up the matches of both sides by trigger, in the order of ours followed by
+// what only theirs has, and preselects the side that changed
+fn entries(base: Option<&[Match]>, ours: &[Match], theirs: &[Match]) -> Vec<MergeEntry> {
+    let find = |list: &[Match], key: &str| list.iter().find(|m| m.display_trigger() == key).cloned();
+    let mut keys: Vec<String> = ours.iter().map(Match::display_trigger).collect();
+    for m in theirs {
+        let key = m.display_trigger();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys.into_iter()
+        .map(|key| {
+            let has_base = base.is_some();
+            let base = base.and_then(|b| find(b, &key));
+            let ours = find(ours, &key);
+            let theirs = find(theirs, &key);
+            let theirs_changed = match base.as_ref() {
+                Some(_) => ours == base,
+                None => ours.is_none(),
+            };
+            let choice = if theirs_changed { Side::Theirs } else { Side::Ours };
+            MergeEntry { key, base, ours, theirs, has_base, choice }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(trigger: &str, replace: &str) -> Match {
+        Match { trigger: trigger.to_string(), replace: replace.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn markers_split_into_sides() {
+        let contents = "matches:\n<<<<<<< ours\n  - trigger: \":a\"\n||||||| base\n  - trigger: \":b\"\n=======\n  - trigger: \":c\"\n>>>>>>> theirs\n  - trigger: \":d\"\n";
+        let sides = split_markers(contents).unwrap();
+        assert_eq!(sides.ours, "matches:\n  - trigger: \":a\"\n  - trigger: \":d\"\n");
+        assert_eq!(sides.base.as_deref(), Some("matches:\n  - trigger: \":b\"\n  - trigger: \":d\"\n"));
+        assert_eq!(sides.theirs, "matches:\n  - trigger: \":c\"\n  - trigger: \":d\"\n");
+        let two_way = split_markers("<<<<<<< a\nx\n=======\ny\n>>>>>>> b\n").unwrap();
+        assert_eq!((two_way.ours.as_str(), two_way.base, two_way.theirs.as_str()), ("x\n", None, "y\n"));
+        assert!(split_markers("matches: []\n").is_none());
+    }
+
+    #[test]
+    fn the_changed_side_is_chosen() {
+        let base = [plain(":same", "S"), plain(":ours", "O"), plain(":theirs", "T"), plain(":both", "B"), plain(":gone", "G")];
+        let ours = [plain(":same", "S"), plain(":ours", "O2"), plain(":theirs", "T"), plain(":both", "B1")];
+        let theirs = [plain(":same", "S"), plain(":ours", "O"), plain(":theirs", "T2"), plain(":both", "B2"), plain(":gone", "G"), plain(":new", "N")];
+        let merged = entries(Some(&base), &ours, &theirs);
+        let summary: Vec<(&str, Side, bool)> = merged.iter().map(|e| (e.key.as_str(), e.choice, e.is_conflict())).collect();
+        assert_eq!(
+            summary,
+            [
+                (":same", Side::Theirs, false),
+                (":ours", Side::Ours, false),
+                (":theirs", Side::Theirs, false),
+                (":both", Side::Ours, true),
+                // Removed on our side, unchanged on theirs
+                (":gone", Side::Ours, false),
+                // Only added on their side
+                (":new", Side::Theirs, false),
+            ]
+        );
+        let resolved: Vec<&str> = merged.iter().filter_map(|e| e.chosen()).map(|m| m.replace.as_str()).collect();
+        assert_eq!(resolved, ["S", "O2", "T2", "B1", "N"]);
+    }
+
+    #[test]
+    fn without_a_base_every_difference_is_a_conflict() {
+        let merged = entries(None, &[plain(":a", "1"), plain(":b", "same")], &[plain(":a", "2"), plain(":b", "same")]);
+        assert!(merged[0].is_conflict() && merged[0].choice == Side::Ours);
+        assert!(!merged[1].is_conflict());
+    }
+}
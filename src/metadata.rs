@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::{app_config_dir, yaml_io, Match};
+
+const METADATA_FILE: &str = "metadata.yml";
+
+// What the helper knows about a match beyond what espanso stores in the match file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MatchMeta {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    // Expansion count; espanso doesn't report expansions yet, so nothing increments it
+    #[serde(default)]
+    pub uses: u64,
+}
+
+// Metadata plus where the match was last seen, for finding it again after an edit
+// made outside the helper
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Entry {
+    pub trigger: String,
+    pub file: String,
+    #[serde(flatten)]
+    pub meta: MatchMeta,
+}
+
+// Sidecar store keyed by a hash of what a match expands to plus its trigger, so two
+// matches with the same content keep their own entries. Renames, moves between files
+// and content edits are followed by `record_edit`, `record_move` and `reconcile`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MetadataStore {
+    #[serde(default)]
+    pub entries: BTreeMap<String, Entry>,
+}
+
+// FNV-1a over the match without its triggers. Unlike `DefaultHasher` the result is
+// the same across Rust versions, so keys stay valid in the saved file.
+pub fn content_hash(m: &Match) -> String {
+    let content = Match { trigger: String::new(), aliases: Vec::new(), regex: None, ..m.clone() };
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in yaml_io::match_fragment(&content).bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+fn key(hash: &str, trigger: &str) -> String {
+    format!("{} {}", hash, trigger)
+}
+
+fn match_key(m: &Match) -> String {
+    key(&content_hash(m), &m.display_trigger())
+}
+
+impl MetadataStore {
+    pub fn load() -> Self {
+        let store: Self = fs::read_to_string(app_config_dir().join(METADATA_FILE))
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or_default();
+        store.upgraded()
+    }
+
+    // Files written before the trigger was part of the key have the bare hash
+    fn upgraded(self) -> Self {
+        let entries = self.entries.into_iter().map(|(hash, entry)| if hash.contains(' ') { (hash, entry) } else { (key(&hash, &entry.trigger), entry) }).collect();
+        Self { entries }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(dir.join(METADATA_FILE), contents)
+    }
+
+    pub fn get(&self, m: &Match) -> Option<&MatchMeta> {
+        self.entries.get(&match_key(m)).map(|entry| &entry.meta)
+    }
+
+    pub fn get_mut(&mut self, file: &str, m: &Match) -> &mut MatchMeta {
+        let entry = self.entries.entry(match_key(m)).or_default();
+        entry.trigger = m.display_trigger();
+        entry.file = file.to_string();
+        &mut entry.meta
+    }
+
+    // Moves the metadata along with an edit made in the helper and stamps the time
+    pub fn record_edit(&mut self, file: &str, old: Option<&Match>, new: &Match, now: &str) {
+        let carried = old.and_then(|old| self.entries.remove(&match_key(old)));
+        let meta = self.get_mut(file, new);
+        if let Some(carried) = carried {
+            *meta = carried.meta;
+        }
+        meta.created.get_or_insert_with(|| now.to_string());
+        meta.modified = Some(now.to_string());
+    }
+
+    pub fn record_move(&mut self, file: &str, m: &Match) {
+        if let Some(entry) = self.entries.get_mut(&match_key(m)) {
+            entry.file = file.to_string();
+        }
+    }
+
+    pub fn remove(&mut self, m: &Match) {
+        self.entries.remove(&match_key(m));
+    }
+
+    // Re-attaches entries of `file` whose match no longer exists, which is what an edit
+    // outside the helper looks like: to the match with the same trigger when its content
+    // changed, else to the one match with the same content when its trigger was renamed.
+    // Returns whether anything changed.
+    pub fn reconcile(&mut self, file: &str, matches: &[Match]) -> bool {
+        let hashes: Vec<String> = matches.iter().map(content_hash).collect();
+        let keys: Vec<String> = matches.iter().zip(&hashes).map(|(m, hash)| key(hash, &m.display_trigger())).collect();
+        let orphaned: Vec<String> = self.entries.iter().filter(|(k, entry)| entry.file == file && !keys.contains(k)).map(|(k, _)| k.clone()).collect();
+        let mut changed = false;
+        for orphan in &orphaned {
+            let entry = &self.entries[orphan];
+            let by_trigger = matches.iter().position(|m| m.display_trigger() == entry.trigger);
+            // Content alone is ambiguous when several matches or orphans share it
+            let hash = orphan.split(' ').next().unwrap_or_default();
+            let renamed = || {
+                let same: Vec<usize> = (0..matches.len()).filter(|&i| hashes[i] == hash).collect();
+                let orphans = orphaned.iter().filter(|o| o.split(' ').next() == Some(hash)).count();
+                (same.len() == 1 && orphans == 1).then(|| same[0])
+            };
+            let Some(position) = by_trigger.or_else(renamed) else { continue };
+            if self.entries.contains_key(&keys[position]) {
+                continue;
+            }
+            if let Some(mut entry) = self.entries.remove(orphan) {
+                entry.trigger = matches[position].display_trigger();
+                self.entries.insert(keys[position].clone(), entry);
+                changed = true;
+            }
+        }
+        // Matches that moved to this file from elsewhere
+        for k in &keys {
+            if let Some(entry) = self.entries.get_mut(k) {
+                if entry.file != file {
+                    entry.file = file.to_string();
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(trigger: &str, replace: &str) -> Match {
+        Match { trigger: trigger.to_string(), replace: replace.to_string(), ..Default::default() }
+    }
+
+    fn noted(store: &mut MetadataStore, file: &str, m: &Match, note: &str) {
+        store.get_mut(file, m).note = note.to_string();
+    }
+
+    #[test]
+    fn same_content_keeps_separate_entries() {
+        let (hi, hello) = (plain(":hi", "Hello"), plain(":hello", "Hello"));
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &hi, "short");
+        noted(&mut store, "base.yml", &hello, "long");
+        assert_eq!(store.get(&hi).unwrap().note, "short");
+        assert_eq!(store.get(&hello).unwrap().note, "long");
+        store.remove(&hi);
+        assert!(store.get(&hi).is_none());
+        assert_eq!(store.get(&hello).unwrap().note, "long");
+    }
+
+    #[test]
+    fn rename_outside_follows_the_content() {
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &plain(":sig", "Best regards"), "signature");
+        let renamed = [plain(":signature", "Best regards"), plain(":date", "Today")];
+        assert!(store.reconcile("base.yml", &renamed));
+        assert_eq!(store.get(&renamed[0]).unwrap().note, "signature");
+        assert_eq!(store.entries.len(), 1);
+    }
+
+    #[test]
+    fn rename_with_shared_content_is_not_guessed() {
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &plain(":a", "Same"), "first");
+        let renamed = [plain(":b", "Same"), plain(":c", "Same")];
+        assert!(!store.reconcile("base.yml", &renamed));
+        assert!(renamed.iter().all(|m| store.get(m).is_none()));
+    }
+
+    #[test]
+    fn content_edit_outside_follows_the_trigger() {
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &plain(":sig", "Best regards"), "signature");
+        let edited = [plain(":sig", "Kind regards")];
+        assert!(store.reconcile("base.yml", &edited));
+        assert_eq!(store.get(&edited[0]).unwrap().note, "signature");
+    }
+
+    #[test]
+    fn move_updates_the_file() {
+        let m = plain(":sig", "Best regards");
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &m, "signature");
+        store.record_move("work.yml", &m);
+        assert_eq!(store.entries.values().next().unwrap().file, "work.yml");
+        // Moved outside the helper, the match shows up in another file
+        assert!(store.reconcile("other.yml", std::slice::from_ref(&m)));
+        assert_eq!(store.entries.values().next().unwrap().file, "other.yml");
+        assert!(!store.reconcile("base.yml", &[]));
+    }
+
+    #[test]
+    fn edit_in_the_helper_carries_the_metadata() {
+        let (old, new) = (plain(":sig", "Best regards"), plain(":regards", "Kind regards"));
+        let mut store = MetadataStore::default();
+        noted(&mut store, "base.yml", &old, "signature");
+        store.record_edit("base.yml", Some(&old), &new, "2024-01-01");
+        assert!(store.get(&old).is_none());
+        let meta = store.get(&new).unwrap();
+        assert_eq!((meta.note.as_str(), meta.modified.as_deref()), ("signature", Some("2024-01-01")));
+    }
+
+    #[test]
+    fn bare_hash_keys_are_upgraded() {
+        let m = plain(":sig", "Best regards");
+        let entry = Entry { trigger: m.display_trigger(), file: "base.yml".to_string(), meta: MatchMeta { note: "old".to_string(), ..Default::default() } };
+        let store = MetadataStore { entries: BTreeMap::from([(content_hash(&m), entry)]) }.upgraded();
+        assert_eq!(store.get(&m).unwrap().note, "old");
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{text, yaml_io};
+
+/// One entry of a file's `matches` list. Fields added in later versions always have
+/// a default, so build matches with `..Default::default()` to stay compatible.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Match {
+    pub trigger: String,
+    /// Further triggers sharing this replacement, written as an espanso `triggers:` list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    pub replace: String,
+    /// Only expand when the trigger stands as a word of its own
+    #[serde(default)]
+    pub word: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub propagate_case: bool,
+    #[serde(default)]
+    pub force_clipboard: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vars: Vec<Var>,
+    /// Keys the editor doesn't cover (form, image_path, markdown, search_terms, …), kept verbatim
+    #[serde(default, skip_serializing_if = "serde_yaml::Mapping::is_empty")]
+    pub extra: serde_yaml::Mapping,
+}
+
+/// A variable of an espanso match, e.g. `{name: now, type: date, params: {format: "%H:%M"}}`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Var {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub var_type: String,
+    #[serde(default, skip_serializing_if = "serde_yaml::Mapping::is_empty")]
+    pub params: serde_yaml::Mapping,
+}
+
+impl Match {
+    /// Text identifying the match in the UI: the triggers, or the pattern of a regex match
+    pub fn display_trigger(&self) -> String {
+        match &self.regex {
+            Some(regex) => format!("regex: {}", regex),
+            None => self.all_triggers().join(", "),
+        }
+    }
+
+    /// Every key espanso reacts to for this match, used for conflict detection
+    pub fn all_triggers(&self) -> Vec<String> {
+        match &self.regex {
+            Some(_) => vec![self.display_trigger()],
+            None => std::iter::once(self.trigger.clone()).chain(self.aliases.iter().cloned()).collect(),
+        }
+    }
+
+    /// One-line description of what the match expands to, used in the list
+    pub fn summary(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => self.preview(),
+        }
+    }
+
+    /// The expansion on one line, whatever the label says
+    pub fn preview(&self) -> String {
+        match self.content_key() {
+            Some(key) if self.replace.is_empty() => {
+                let value = self.extra.get(key).and_then(serde_yaml::Value::as_str).unwrap_or_default();
+                format!("{}: {}", key, text::summarize(value))
+            }
+            _ => text::summarize(&self.replace),
+        }
+    }
+
+    /// The key providing the expansion when it isn't plain `replace` text
+    pub fn content_key(&self) -> Option<&'static str> {
+        yaml_io::CONTENT_KEYS.iter().copied().find(|key| self.extra.contains_key(*key))
+    }
+
+    /// Why the match can't be handed to another text expander as a trigger and its text
+    pub fn plain_text_issue(&self) -> Option<&'static str> {
+        if self.regex.is_some() {
+            Some("regex trigger")
+        } else if let Some(key) = self.content_key() {
+            Some(key)
+        } else if !self.vars.is_empty() || self.replace.contains("{{") {
+            Some("variables")
+        } else {
+            None
+        }
+    }
+}
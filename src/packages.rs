@@ -0,0 +1,116 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::tasks::TaskControl;
+use crate::{remote, text, yaml_io, Match};
+
+const HUB_INDEX_URL: &str = "https://github.com/espanso/hub/releases/latest/download/package_index.json";
+
+// A package under `match/packages`, as described by its `_manifest.yml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    pub dir: PathBuf,
+    // Packages installed from a git repository can't be compared with the hub
+    pub from_hub: bool,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct HubIndex {
+    packages: Vec<HubPackage>,
+}
+
+#[derive(Deserialize)]
+struct HubPackage {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Edited,
+    AddedLocally,
+    RemovedLocally,
+}
+
+impl Change {
+    pub fn label(self) -> &'static str {
+        match self {
+            Change::Edited => "edited",
+            Change::AddedLocally => "added locally",
+            Change::RemovedLocally => "removed locally",
+        }
+    }
+}
+
+// The installed package compared with the hub's copy of the same version, which is
+// what `espanso package update` puts back
+#[derive(Debug, Clone)]
+pub struct PackageDiff {
+    pub package: InstalledPackage,
+    // Newest version on the hub, when it differs from the installed one
+    pub newer_version: Option<String>,
+    pub changes: Vec<(String, Change)>,
+    pub lines: Vec<(text::DiffOp, String)>,
+}
+
+pub fn installed(config_dir: &Path) -> Vec<InstalledPackage> {
+    let mut packages: Vec<InstalledPackage> = fs::read_dir(config_dir.join("packages"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.ok()?.path();
+            let manifest: Manifest = serde_yaml::from_str(&fs::read_to_string(dir.join("_manifest.yml")).ok()?).ok()?;
+            let source = fs::read_to_string(dir.join("_pkgsource.yml")).unwrap_or_default();
+            let from_hub = source.is_empty() || source.contains("hub");
+            Some(InstalledPackage { name: manifest.name, version: manifest.version, dir, from_hub })
+        })
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+fn upstream_url(name: &str, version: &str) -> String {
+    format!("https://raw.githubusercontent.com/espanso/hub/main/packages/{}/{}/package.yml", name, version)
+}
+
+// Downloads the hub's copy of the installed version and lists what differs per trigger
+pub fn compare(package: InstalledPackage, control: &TaskControl) -> Result<PackageDiff, String> {
+    control.set_total(2);
+    let index: HubIndex = serde_json::from_str(&remote::fetch(HUB_INDEX_URL)?).map_err(|e| format!("unreadable hub index: {}", e))?;
+    let latest = index.packages.into_iter().find(|p| p.name == package.name).ok_or(format!("{} is not on the hub", package.name))?;
+    control.advance();
+    let upstream = remote::fetch(&upstream_url(&package.name, &package.version))?;
+    control.advance();
+    let local = fs::read_to_string(package.dir.join("package.yml")).map_err(|e| e.to_string())?;
+
+    let local_matches = yaml_io::MatchFile::parse(&local).matches();
+    let upstream_matches = yaml_io::MatchFile::parse(&upstream).matches();
+    let find = |matches: &[Match], trigger: &str| matches.iter().find(|m| m.display_trigger() == trigger).cloned();
+    let mut changes = Vec::new();
+    for m in &local_matches {
+        let trigger = m.display_trigger();
+        match find(&upstream_matches, &trigger) {
+            Some(original) if original != *m => changes.push((trigger, Change::Edited)),
+            Some(_) => {}
+            None => changes.push((trigger, Change::AddedLocally)),
+        }
+    }
+    for m in &upstream_matches {
+        let trigger = m.display_trigger();
+        if find(&local_matches, &trigger).is_none() {
+            changes.push((trigger, Change::RemovedLocally));
+        }
+    }
+    let newer_version = (latest.version != package.version).then_some(latest.version);
+    Ok(PackageDiff { lines: text::line_diff(&upstream, &local), package, newer_version, changes })
+}
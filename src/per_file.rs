@@ -0,0 +1,58 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::app_config_dir;
+
+// A default value plus optional overrides per match file, stored in the helper's config dir
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PerFile<T> {
+    #[serde(default)]
+    pub default: T,
+    #[serde(default)]
+    pub files: BTreeMap<String, T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default + Clone> PerFile<T> {
+    pub fn load(name: &str) -> Self {
+        fs::read_to_string(app_config_dir().join(name))
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(dir.join(name), contents)
+    }
+
+    pub fn for_file(&self, file: &str) -> &T {
+        self.files.get(file).unwrap_or(&self.default)
+    }
+
+    pub fn has_override(&self, file: &str) -> bool {
+        self.files.contains_key(file)
+    }
+
+    // Starts an override from the current default, or drops it again
+    pub fn set_override(&mut self, file: &str, enabled: bool) {
+        if enabled {
+            let default = self.default.clone();
+            self.files.entry(file.to_string()).or_insert(default);
+        } else {
+            self.files.remove(file);
+        }
+    }
+
+    // The value that applies to `file`, for editing in place
+    pub fn for_file_mut(&mut self, file: &str) -> &mut T {
+        match self.files.get_mut(file) {
+            Some(value) => value,
+            None => &mut self.default,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::yaml_io::MatchFile;
+use crate::Match;
+
+// Match file holding the default version of every snippet that has platform variants.
+// For each platform with variants, `_platform_<os>.yml` carries the resolved set and an
+// app config filtered on that OS swaps it in for the default file.
+pub const VARIANTS_FILE: &str = "platform_variants.yml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Os {
+    Windows,
+    Linux,
+    Macos,
+}
+
+impl Os {
+    pub const ALL: [Os; 3] = [Os::Windows, Os::Linux, Os::Macos];
+
+    // Name used by espanso's `filter_os`
+    pub fn name(self) -> &'static str {
+        match self {
+            Os::Windows => "windows",
+            Os::Linux => "linux",
+            Os::Macos => "macos",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Os::Windows => "Windows",
+            Os::Linux => "Linux",
+            Os::Macos => "macOS",
+        }
+    }
+
+    fn match_file(self) -> String {
+        format!("_platform_{}.yml", self.name())
+    }
+
+    fn config_file(self) -> String {
+        format!("platform_{}.yml", self.name())
+    }
+}
+
+// Replacement per trigger for every platform that deviates from the default
+pub type Variants = HashMap<Os, HashMap<String, String>>;
+
+// Reads the platform files next to the default variants file, keeping only entries
+// whose replacement differs from the default one
+pub fn load(match_dir: &Path, defaults: &[Match]) -> Variants {
+    let mut variants = Variants::new();
+    for os in Os::ALL {
+        let resolved = MatchFile::load(&match_dir.join(os.match_file())).matches();
+        for m in resolved {
+            let differs = defaults.iter().any(|d| d.trigger == m.trigger && d.replace != m.replace);
+            if differs {
+                variants.entry(os).or_default().insert(m.trigger, m.replace);
+            }
+        }
+    }
+    variants
+}
+
+pub fn for_trigger(variants: &Variants, trigger: &str) -> Vec<(Os, String)> {
+    Os::ALL
+        .iter()
+        .filter_map(|os| Some((*os, variants.get(os)?.get(trigger)?.clone())))
+        .collect()
+}
+
+// Regenerates the per-platform match files and app configs from the defaults
+pub fn write(match_dir: &Path, defaults: &[Match], variants: &Variants) -> io::Result<()> {
+    let config_dir = match_dir.parent().unwrap_or(match_dir).join("config");
+    for os in Os::ALL {
+        let match_path = match_dir.join(os.match_file());
+        let config_path = config_dir.join(os.config_file());
+        let overrides = variants.get(&os).filter(|v| !v.is_empty());
+        let Some(overrides) = overrides else {
+            for path in [&match_path, &config_path] {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+            continue;
+        };
+
+        let resolved: Vec<Match> = defaults
+            .iter()
+            .map(|m| match overrides.get(&m.trigger) {
+                Some(replace) => Match { replace: replace.clone(), ..m.clone() },
+                None => m.clone(),
+            })
+            .collect();
+        MatchFile::default().save(&match_path, &resolved).map_err(io::Error::other)?;
+
+        fs::create_dir_all(&config_dir)?;
+        let config = format!(
+            "# Generated by Espanso Helper: swaps in the {} variants of {}\nfilter_os: {}\nextra_includes:\n  - \"../match/{}\"\nextra_excludes:\n  - \"../match/{}\"\n",
+            os.label(),
+            VARIANTS_FILE,
+            os.name(),
+            os.match_file(),
+            VARIANTS_FILE
+        );
+        fs::write(config_path, config)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+// Starting points for new match files
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FilePreset {
+    #[default]
+    Empty,
+    GlobalVars,
+    WorkProfile,
+    EmojiPack,
+}
+
+impl FilePreset {
+    pub const ALL: [FilePreset; 4] = [FilePreset::Empty, FilePreset::GlobalVars, FilePreset::WorkProfile, FilePreset::EmojiPack];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FilePreset::Empty => "Empty",
+            FilePreset::GlobalVars => "With global variables",
+            FilePreset::WorkProfile => "Work profile (app-specific)",
+            FilePreset::EmojiPack => "Emoji pack",
+        }
+    }
+
+    // espanso loads every match file unless its name starts with `_`; a work profile
+    // is only pulled in by its app config
+    pub fn file_name(self, name: &str) -> String {
+        let (folder, file) = name.rsplit_once('/').map_or(("", name), |(folder, file)| (folder, file));
+        if self == FilePreset::WorkProfile && !file.starts_with('_') {
+            if folder.is_empty() { format!("_{}", file) } else { format!("{}/_{}", folder, file) }
+        } else {
+            name.to_string()
+        }
+    }
+
+    pub fn contents(self) -> &'static str {
+        match self {
+            FilePreset::Empty => "matches: []\n",
+            FilePreset::GlobalVars => concat!(
+                "global_vars:\n",
+                "  - name: today\n",
+                "    type: date\n",
+                "    params:\n",
+                "      format: \"%Y-%m-%d\"\n",
+                "\n",
+                "matches:\n",
+                "  - trigger: \":today\"\n",
+                "    replace: \"{{today}}\"\n",
+            ),
+            FilePreset::WorkProfile => concat!(
+                "# Only active in the applications listed in the companion config file\n",
+                "matches:\n",
+                "  - trigger: \":sig\"\n",
+                "    replace: \"Best regards\"\n",
+            ),
+            FilePreset::EmojiPack => concat!(
+                "# Emoji are pasted through the clipboard, typing them key by key is unreliable\n",
+                "matches:\n",
+                "  - trigger: \":smile:\"\n",
+                "    replace: \"😄\"\n",
+                "    force_clipboard: true\n",
+                "  - trigger: \":heart:\"\n",
+                "    replace: \"❤️\"\n",
+                "    force_clipboard: true\n",
+                "  - trigger: \":thumbsup:\"\n",
+                "    replace: \"👍\"\n",
+                "    force_clipboard: true\n",
+            ),
+        }
+    }
+
+    // App config written to espanso's `config` folder next to a work profile, as
+    // (file name, contents)
+    pub fn companion(self, file_name: &str) -> Option<(String, String)> {
+        if self != FilePreset::WorkProfile {
+            return None;
+        }
+        let stem = file_name.rsplit('/').next().unwrap_or(file_name).trim_start_matches('_').trim_end_matches(".yml").trim_end_matches(".yaml");
+        let contents = format!(
+            "# Matches of {} are only expanded in applications matching this filter\nfilter_exec: \"{}\"\n\nextra_includes:\n  - \"../match/{}\"\n",
+            file_name, stem, file_name
+        );
+        Some((format!("{}.yml", stem), contents))
+    }
+}
@@ -0,0 +1,118 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+use crate::yaml_io::{self, MatchFile};
+use crate::{backups, settings, Match};
+
+// A small window that stays on top of other apps and adds one match, started with
+// `--quick-add`. Bound to a shortcut in the desktop's keyboard settings, or opened from
+// the tray and its shortcut of a build with the `tray` feature, it captures a snippet
+// from anywhere without opening the full editor.
+pub struct QuickAdd {
+    config_dir: PathBuf,
+    files: Vec<String>,
+    settings: settings::Settings,
+    trigger: String,
+    replace: String,
+    status: String,
+    focus_trigger: bool,
+}
+
+pub fn options() -> eframe::NativeOptions {
+    eframe::NativeOptions {
+        always_on_top: true,
+        initial_window_size: Some(egui::vec2(420.0, 240.0)),
+        centered: true,
+        ..Default::default()
+    }
+}
+
+impl QuickAdd {
+    pub fn new() -> Self {
+        let mut settings = settings::Settings::load();
+        let config_dir = settings.config_dir.clone().unwrap_or_else(crate::default_config_dir);
+        let mut files = crate::list_yaml_files(&config_dir);
+        files.sort();
+        // The file used last time, else the first one espanso loads
+        if !files.contains(&settings.quick_add_file) {
+            settings.quick_add_file = files.iter().find(|file| !crate::is_included_only(file)).cloned().unwrap_or_else(|| "base.yml".to_string());
+        }
+        yaml_io::set_indent(settings.yaml_indent);
+        Self { config_dir, files, settings, trigger: ":".to_string(), replace: String::new(), status: String::new(), focus_trigger: true }
+    }
+
+    fn save(&mut self) -> bool {
+        let m = Match { trigger: self.trigger.trim().to_string(), replace: self.replace.clone(), ..Default::default() };
+        if m.trigger.is_empty() || m.replace.is_empty() {
+            self.status = "Enter a trigger and a replacement".to_string();
+            return false;
+        }
+        let file = self.settings.quick_add_file.clone();
+        if let Err(error) = append(&self.config_dir, &file, m, self.settings.backup_count) {
+            self.status = error;
+            return false;
+        }
+        // Only the target file is remembered; everything else stays as the editor left it
+        let mut saved = settings::Settings::load();
+        saved.quick_add_file = file;
+        if let Err(error) = saved.save() {
+            log::warn!("Saving the settings failed: {}", error);
+        }
+        true
+    }
+}
+
+impl eframe::App for QuickAdd {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let (submit, cancel) = ctx.input(|i| (i.key_pressed(egui::Key::Enter) && i.modifiers.command, i.key_pressed(egui::Key::Escape)));
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::Grid::new("quick_add").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                ui.label("Trigger:");
+                let trigger = ui.text_edit_singleline(&mut self.trigger);
+                if std::mem::take(&mut self.focus_trigger) {
+                    trigger.request_focus();
+                }
+                ui.end_row();
+                ui.label("Replacement:");
+                ui.add(egui::TextEdit::multiline(&mut self.replace).desired_rows(4));
+                ui.end_row();
+                ui.label("File:");
+                egui::ComboBox::from_id_source("quick_add_file").selected_text(&self.settings.quick_add_file).show_ui(ui, |ui| {
+                    for file in &self.files {
+                        ui.selectable_value(&mut self.settings.quick_add_file, file.clone(), file);
+                    }
+                });
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                if (ui.button("Add").on_hover_text("Ctrl+Enter").clicked() || submit) && self.save() {
+                    frame.close();
+                }
+                if ui.button("Cancel").on_hover_text("Escape").clicked() || cancel {
+                    frame.close();
+                }
+            });
+            if !self.status.is_empty() {
+                ui.colored_label(ui.visuals().warn_fg_color, &self.status);
+            }
+        });
+    }
+}
+
+// Adds `m` at the end of `file`, creating the file when it doesn't exist yet
+pub fn append(config_dir: &Path, file: &str, m: Match, keep_backups: usize) -> Result<(), String> {
+    let path = config_dir.join(file);
+    let mut document = MatchFile::load(&path);
+    let mut matches = document.matches();
+    if matches.iter().any(|existing| existing.all_triggers().contains(&m.trigger)) {
+        return Err(format!("{} already has a match for {}", file, m.trigger));
+    }
+    matches.push(m);
+    if let Err(error) = backups::create(config_dir, file, keep_backups) {
+        log::warn!("Backing up {} failed: {}", file, error);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Creating {} failed: {}", parent.display(), e))?;
+    }
+    document.save(&path, &matches).map_err(|e| format!("Saving {} failed: {}", file, e))
+}
@@ -0,0 +1,181 @@
+use eframe::egui;
+use regex::Regex;
+
+// Character classes offered by the builder, named after what they match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharClass {
+    Digits,
+    Letters,
+    Word,
+    Anything,
+}
+
+impl CharClass {
+    const ALL: [CharClass; 4] = [CharClass::Digits, CharClass::Letters, CharClass::Word, CharClass::Anything];
+
+    fn pattern(self) -> &'static str {
+        match self {
+            CharClass::Digits => r"\d+",
+            CharClass::Letters => r"[a-zA-Z]+",
+            CharClass::Word => r"\w+",
+            CharClass::Anything => r".*",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            CharClass::Digits => "digits",
+            CharClass::Letters => "letters",
+            CharClass::Word => "word characters",
+            CharClass::Anything => "anything",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexPart {
+    Literal(String),
+    Class(CharClass),
+    Optional(String),
+    Group { name: String, class: CharClass },
+}
+
+impl RegexPart {
+    fn pattern(&self) -> String {
+        match self {
+            RegexPart::Literal(text) => regex::escape(text),
+            RegexPart::Class(class) => class.pattern().to_string(),
+            RegexPart::Optional(text) => format!("(?:{})?", regex::escape(text)),
+            RegexPart::Group { name, class } => format!("(?P<{}>{})", name, class.pattern()),
+        }
+    }
+}
+
+pub enum BuilderAction {
+    UsePattern(String),
+    InsertPlaceholders(String),
+}
+
+// Assembles an espanso `regex:` value from simple building blocks
+#[derive(Debug, Clone, Default)]
+pub struct RegexBuilder {
+    pub parts: Vec<RegexPart>,
+    pub sample: String,
+}
+
+impl RegexBuilder {
+    pub fn pattern(&self) -> String {
+        self.parts.iter().map(RegexPart::pattern).collect()
+    }
+
+    pub fn group_names(&self) -> Vec<&str> {
+        self.parts.iter().filter_map(|part| match part {
+            RegexPart::Group { name, .. } => Some(name.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    // `{{group}}` references for every named group, ready to paste into the replacement
+    pub fn placeholders(&self) -> String {
+        self.group_names().iter().map(|name| format!("{{{{{}}}}}", name)).collect::<Vec<_>>().join(" ")
+    }
+
+    // Runs the pattern against the sample text, returning the captured groups on a match
+    pub fn test(&self) -> Result<Option<Vec<(String, String)>>, String> {
+        let regex = Regex::new(&self.pattern()).map_err(|e| e.to_string())?;
+        Ok(regex.captures(&self.sample).map(|captures| {
+            regex.capture_names().flatten().map(|name| {
+                let value = captures.name(name).map(|m| m.as_str()).unwrap_or_default();
+                (name.to_string(), value.to_string())
+            }).collect()
+        }))
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<BuilderAction> {
+        let mut action = None;
+        let mut removed = None;
+        for (index, part) in self.parts.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                match part {
+                    RegexPart::Literal(text) => {
+                        ui.label("Text");
+                        ui.text_edit_singleline(text);
+                    }
+                    RegexPart::Class(class) => {
+                        ui.label(format!("Some {}", class.label()));
+                    }
+                    RegexPart::Optional(text) => {
+                        ui.label("Optional text");
+                        ui.text_edit_singleline(text);
+                    }
+                    RegexPart::Group { name, class } => {
+                        ui.label("Group");
+                        ui.add(egui::TextEdit::singleline(name).desired_width(80.0));
+                        egui::ComboBox::from_id_source(("regex_group_class", index))
+                            .selected_text(class.label())
+                            .show_ui(ui, |ui| {
+                                for option in CharClass::ALL {
+                                    ui.selectable_value(class, option, option.label());
+                                }
+                            });
+                    }
+                }
+                if ui.small_button("✖").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            self.parts.remove(index);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("+ Text").clicked() {
+                self.parts.push(RegexPart::Literal(String::new()));
+            }
+            for class in CharClass::ALL {
+                if ui.button(format!("+ {}", class.label())).clicked() {
+                    self.parts.push(RegexPart::Class(class));
+                }
+            }
+            if ui.button("+ Optional text").clicked() {
+                self.parts.push(RegexPart::Optional(String::new()));
+            }
+            if ui.button("+ Named group").clicked() {
+                let name = format!("group{}", self.group_names().len() + 1);
+                self.parts.push(RegexPart::Group { name, class: CharClass::Word });
+            }
+        });
+
+        let pattern = self.pattern();
+        ui.label(egui::RichText::new(&pattern).monospace());
+        ui.horizontal(|ui| {
+            ui.label("Try it:");
+            ui.text_edit_singleline(&mut self.sample);
+        });
+        match self.test() {
+            Err(error) => {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            Ok(None) => {
+                ui.label("No match");
+            }
+            Ok(Some(groups)) => {
+                ui.colored_label(egui::Color32::GREEN, "Matches");
+                for (name, value) in groups {
+                    ui.label(format!("{{{{{}}}}} = {}", name, value));
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Use Pattern").clicked() {
+                action = Some(BuilderAction::UsePattern(pattern));
+            }
+            if ui.add_enabled(!self.group_names().is_empty(), egui::Button::new("Insert Placeholders")).clicked() {
+                action = Some(BuilderAction::InsertPlaceholders(self.placeholders()));
+            }
+        });
+        action
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::tasks::Task;
+
+// Turns GitHub page URLs and `owner/repo/path` shorthands into raw file URLs;
+// anything else is returned unchanged.
+pub fn raw_url(input: &str) -> String {
+    let input = input.trim();
+    let path = input
+        .strip_prefix("https://github.com/")
+        .or_else(|| input.strip_prefix("http://github.com/"))
+        .or_else(|| input.strip_prefix("github.com/"))
+        .or_else(|| (!input.contains("://")).then_some(input));
+
+    if let Some(path) = path {
+        let parts: Vec<&str> = path.splitn(4, '/').collect();
+        match parts.as_slice() {
+            [owner, repo, "blob" | "raw", rest] => {
+                return format!("https://raw.githubusercontent.com/{}/{}/{}", owner, repo, rest);
+            }
+            [owner, repo, rest @ ..] if !rest.is_empty() => {
+                return format!("https://raw.githubusercontent.com/{}/{}/HEAD/{}", owner, repo, rest.join("/"));
+            }
+            _ => {}
+        }
+    }
+    input.to_string()
+}
+
+// Downloads `url` using the system's curl, which keeps TLS handling out of this crate
+pub fn fetch(url: &str) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--max-time", "30"])
+        .arg(url)
+        .output()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|_| "response is not valid UTF-8".to_string())
+}
+
+pub fn spawn_fetch(url: String) -> Task<String> {
+    let thread_url = url.clone();
+    Task::spawn(url, move |_| fetch(&thread_url))
+}
+
+// Saves `url` to `target` using curl
+pub fn download(url: &str, target: &Path) -> Result<(), String> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+        .arg(target)
+        .arg(url)
+        .output()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Characters conventionally used to prefix triggers so they never occur in prose
+const TRIGGER_PREFIXES: &[char] = &[':', ';', '/', '\\', '!', ',', '.', '#', '@', '+', '='];
+
+// Word list built from the hunspell/myspell dictionaries installed on the system
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    pub fn load_system() -> Self {
+        let mut words = HashSet::new();
+        for path in dictionary_files() {
+            let Ok(contents) = fs::read(&path) else { continue };
+            let contents = String::from_utf8_lossy(&contents);
+            let is_hunspell = path.extension().is_some_and(|ext| ext == "dic");
+            // The first line of a hunspell .dic file is the word count
+            for line in contents.lines().skip(usize::from(is_hunspell)) {
+                let word = line.split('/').next().unwrap_or_default().trim();
+                if !word.is_empty() {
+                    words.insert(word.to_lowercase());
+                }
+            }
+        }
+        Self { words }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    // Some dictionary word that contains `fragment` without being equal to it
+    pub fn word_containing(&self, fragment: &str) -> Option<&str> {
+        let fragment = fragment.to_lowercase();
+        self.words
+            .iter()
+            .filter(|word| word.len() > fragment.len() && word.contains(&fragment))
+            .min_by_key(|word| word.len())
+            .map(String::as_str)
+    }
+}
+
+fn dictionary_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"] {
+        if let Ok(entries) = fs::read_dir(dir) {
+            files.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "dic")),
+            );
+        }
+    }
+    let words = Path::new("/usr/share/dict/words");
+    if words.exists() {
+        files.push(words.to_path_buf());
+    }
+    files
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskFinding {
+    pub index: usize,
+    pub trigger: String,
+    pub risk: Risk,
+    pub reason: String,
+}
+
+// How likely a trigger is to fire by accident while typing normal text
+pub fn score(trigger: &str, dictionary: &Dictionary) -> (Risk, String) {
+    if trigger.starts_with(TRIGGER_PREFIXES) {
+        return (Risk::Low, "prefixed abbreviation".to_string());
+    }
+    if dictionary.contains(trigger) {
+        return (Risk::High, format!("\"{}\" is a dictionary word", trigger));
+    }
+    if let Some(word) = dictionary.word_containing(trigger) {
+        return (Risk::Medium, format!("fires inside words like \"{}\"", word));
+    }
+    (Risk::Low, "not found in the dictionary".to_string())
+}
+
+pub fn has_prefix(trigger: &str) -> bool {
+    trigger.starts_with(TRIGGER_PREFIXES)
+}
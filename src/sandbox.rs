@@ -0,0 +1,83 @@
+use regex::Regex;
+
+use crate::{docs, Match};
+
+// Text typed into the sandbox with every trigger expanded the way espanso would,
+// variables filled in with sample values
+pub fn expand(input: &str, matches: &[Match], locale: &str) -> String {
+    let patterns: Vec<Option<Regex>> = matches
+        .iter()
+        .map(|m| m.regex.as_ref().and_then(|pattern| Regex::new(&format!("^(?:{})", pattern)).ok()))
+        .collect();
+    let mut output = String::new();
+    let mut rest = input;
+    'outer: while !rest.is_empty() {
+        for (m, pattern) in matches.iter().zip(&patterns) {
+            if let Some((consumed, expansion)) = expand_at(&output, rest, m, pattern.as_ref(), locale) {
+                output.push_str(&expansion);
+                rest = &rest[consumed..];
+                continue 'outer;
+            }
+        }
+        let next = rest.chars().next().map_or(1, char::len_utf8);
+        output.push_str(&rest[..next]);
+        rest = &rest[next..];
+    }
+    output
+}
+
+// The length of `rest` a match consumes at its start, and what it expands to
+fn expand_at(before: &str, rest: &str, m: &Match, pattern: Option<&Regex>, locale: &str) -> Option<(usize, String)> {
+    if m.regex.is_some() {
+        let regex = pattern?;
+        let caps = regex.captures(rest).filter(|caps| !caps[0].is_empty())?;
+        let mut replace = m.replace.clone();
+        for name in regex.capture_names().flatten() {
+            if let Some(value) = caps.name(name) {
+                replace = replace.replace(&format!("{{{{{}}}}}", name), value.as_str());
+            }
+        }
+        return Some((caps[0].len(), docs::example(&replace, &m.vars, locale)));
+    }
+    for trigger in m.all_triggers().iter().filter(|t| !t.is_empty()) {
+        let Some(typed) = rest.get(..trigger.len()) else { continue };
+        let exact = typed == trigger;
+        let capitalized = m.propagate_case && !exact && typed.to_lowercase() == trigger.to_lowercase();
+        if !exact && !capitalized {
+            continue;
+        }
+        if m.word {
+            let after = rest[trigger.len()..].chars().next();
+            if before.chars().next_back().is_some_and(char::is_alphanumeric) || after.is_some_and(char::is_alphanumeric) {
+                continue;
+            }
+        }
+        let mut expansion = docs::example(&m.replace, &m.vars, locale);
+        if capitalized {
+            expansion = propagate_case(typed, &expansion);
+        }
+        return Some((trigger.len(), expansion));
+    }
+    None
+}
+
+// espanso capitalizes the replacement for `:Sig` and upper-cases it for `:SIG`
+fn propagate_case(typed: &str, expansion: &str) -> String {
+    let letters: Vec<char> = typed.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        return expansion.to_uppercase();
+    }
+    let mut chars = expansion.chars();
+    match chars.next() {
+        Some(first) if letters.first().is_some_and(|c| c.is_uppercase()) => first.to_uppercase().chain(chars).collect(),
+        _ => expansion.to_string(),
+    }
+}
+
+// Sample text with the trigger typed in the middle, for trying a match right away
+pub fn seed(m: &Match) -> String {
+    match &m.regex {
+        Some(pattern) => format!("Type text matching {} here: ", pattern),
+        None => format!("Some text before {} and after.", m.trigger),
+    }
+}
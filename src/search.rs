@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+
+use crate::tasks::TaskControl;
+use crate::yaml_io::MatchFile;
+
+const PACKAGES_FOLDER: &str = "packages/";
+
+// One match of any file, as much as searching needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub file: String,
+    pub index: usize,
+    // The installed package the file belongs to; those are only shown, never edited
+    pub package: Option<String>,
+    pub triggers: Vec<String>,
+    pub replace: String,
+}
+
+// How well a match fits, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    ExactTrigger,
+    Trigger,
+    Replacement,
+}
+
+impl Rank {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rank::ExactTrigger => "trigger",
+            Rank::Trigger => "in trigger",
+            Rank::Replacement => "in replacement",
+        }
+    }
+}
+
+// The package of a file below `packages/`, like `emoji` for `packages/emoji/package.yml`
+pub fn package_of(file: &str) -> Option<&str> {
+    file.strip_prefix(PACKAGES_FOLDER)?.split('/').next().filter(|name| !name.is_empty())
+}
+
+// Reads every match of `files`, the user's own and those of installed packages
+pub fn index(config_dir: &Path, files: &[String], control: &TaskControl) -> Result<Vec<Entry>, String> {
+    control.set_total(files.len());
+    let mut entries = Vec::new();
+    for file in files {
+        if control.is_cancelled() {
+            return Err("Indexing cancelled".to_string());
+        }
+        let contents = fs::read_to_string(config_dir.join(file)).unwrap_or_default();
+        for (index, m) in MatchFile::parse(&contents).matches().into_iter().enumerate() {
+            entries.push(Entry {
+                file: file.clone(),
+                index,
+                package: package_of(file).map(str::to_string),
+                triggers: m.all_triggers(),
+                replace: m.replace,
+            });
+        }
+        control.advance();
+    }
+    Ok(entries)
+}
+
+// Matches containing `query` in a trigger or the replacement, ignoring case. Better ranks
+// come first and within a rank the user's own files come before packages.
+pub fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<(&'a Entry, Rank)> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<(&Entry, Rank)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let triggers: Vec<String> = entry.triggers.iter().map(|t| t.to_lowercase()).collect();
+            let rank = if triggers.contains(&query) {
+                Rank::ExactTrigger
+            } else if triggers.iter().any(|t| t.contains(&query)) {
+                Rank::Trigger
+            } else if entry.replace.to_lowercase().contains(&query) {
+                Rank::Replacement
+            } else {
+                return None;
+            };
+            Some((entry, rank))
+        })
+        .collect();
+    hits.sort_by(|(a, a_rank), (b, b_rank)| {
+        a_rank.cmp(b_rank).then(a.package.is_some().cmp(&b.package.is_some())).then(a.file.cmp(&b.file)).then(a.index.cmp(&b.index))
+    });
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(file: &str, index: usize, trigger: &str, replace: &str) -> Entry {
+        Entry { file: file.to_string(), index, package: package_of(file).map(str::to_string), triggers: vec![trigger.to_string()], replace: replace.to_string() }
+    }
+
+    #[test]
+    fn packages_are_named_after_their_folder() {
+        assert_eq!(package_of("packages/contacts/package.yml"), Some("contacts"));
+        assert_eq!(package_of("packages/emoji/sub/more.yml"), Some("emoji"));
+        assert_eq!(package_of("packages/"), None);
+        assert_eq!(package_of("base.yml"), None);
+    }
+
+    #[test]
+    fn hits_are_ranked() {
+        let package = "packages/contacts/package.yml";
+        let entries = [
+            entry("base.yml", 0, ":addr2", "Second address"),
+            entry("base.yml", 1, ":home", "Send to :addr"),
+            entry(package, 0, ":addr", "Main Street 1"),
+            entry(package, 1, ":addrx", "Elsewhere"),
+            entry(package, 2, ":other", "Nothing"),
+        ];
+        let hits: Vec<(&str, Rank)> = search(&entries, " :ADDR ").into_iter().map(|(entry, rank)| (entry.replace.as_str(), rank)).collect();
+        // Exact triggers first, then the user's own files before packages
+        assert_eq!(
+            hits,
+            [
+                ("Main Street 1", Rank::ExactTrigger),
+                ("Second address", Rank::Trigger),
+                ("Elsewhere", Rank::Trigger),
+                ("Send to :addr", Rank::Replacement),
+            ]
+        );
+        assert!(search(&entries, "  ").is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_config_dir;
+use crate::filter::{FilterMode, FilterScope};
+use crate::validation::LintRules;
+
+const SETTINGS_FILE: &str = "settings.yml";
+
+// Layout of the settings file. Raise it together with a new entry in `MIGRATIONS`
+// when a setting is renamed, moved or changes its meaning.
+const SCHEMA_VERSION: u32 = 1;
+
+// `MIGRATIONS[n]` turns a file of version n into one of version n + 1
+const MIGRATIONS: [fn(&mut serde_yaml::Mapping); SCHEMA_VERSION as usize] = [
+    // Files from before the version was recorded already have the layout of version 1
+    |_| {},
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "Follow System",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+}
+
+// Outer position and inner size of the window in points, as eframe reports them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// Preferences kept between sessions in the helper's config dir. Missing keys take
+// their default, so older files keep working as settings are added.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    // Schema version of the file; files without one are version 0
+    #[serde(default)]
+    pub version: u32,
+    // Match folder to use instead of espanso's default one
+    pub config_dir: Option<PathBuf>,
+    // Spaces per level in YAML the helper writes; existing entries keep theirs
+    pub yaml_indent: usize,
+    pub theme: Theme,
+    // Scale of the whole interface on top of the screen's own, 1.0 is 100 %
+    pub zoom: f32,
+    // Off: changes are written on Ctrl+S, when switching files and when closing
+    pub autosave: bool,
+    pub backup_count: usize,
+    pub restart_after_save: bool,
+    pub review_saves: bool,
+    pub keep_typography: bool,
+    pub check_updates_on_startup: bool,
+    // Once a week, sum up how the match folder changed
+    pub health_summary: bool,
+    // Count edits per file and bulk operations, kept on this computer only
+    pub usage_insights: bool,
+    // Keep text copied elsewhere as candidates for new matches
+    pub clipboard_watcher: bool,
+    // Leave files starting with `_` out of the file lists
+    pub hide_included_only: bool,
+    pub filter_mode: FilterMode,
+    pub filter_scope: FilterScope,
+    // Where `--quick-add` puts new matches, the one picked last time
+    pub quick_add_file: String,
+    // Shortcut opening the quick-add window while `--tray` runs, like `Ctrl+Alt+Space`
+    pub quick_add_shortcut: String,
+    pub daemon_poll_interval_secs: u64,
+    // Locale for date previews; empty follows the system
+    pub date_locale: String,
+    pub window: Option<WindowGeometry>,
+    pub lint: LintRules,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            config_dir: None,
+            yaml_indent: 2,
+            theme: Theme::System,
+            zoom: 1.0,
+            autosave: true,
+            backup_count: 10,
+            restart_after_save: false,
+            review_saves: false,
+            keep_typography: false,
+            check_updates_on_startup: false,
+            health_summary: false,
+            usage_insights: false,
+            clipboard_watcher: false,
+            hide_included_only: false,
+            filter_mode: FilterMode::Substring,
+            filter_scope: FilterScope::All,
+            quick_add_file: String::new(),
+            quick_add_shortcut: "Ctrl+Alt+Space".to_string(),
+            daemon_poll_interval_secs: 30,
+            date_locale: String::new(),
+            window: None,
+            lint: LintRules::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        fs::read_to_string(app_config_dir().join(SETTINGS_FILE)).ok().and_then(|contents| parse(&contents).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(SETTINGS_FILE);
+        keep_unreadable(&path)?;
+        let contents = serde_yaml::to_string(&Settings { version: SCHEMA_VERSION, ..self.clone() }).map_err(io::Error::other)?;
+        write_atomically(&path, &contents)
+    }
+}
+
+// Reads a settings file of any earlier version. Files of a newer version are read as far
+// as this version understands them.
+fn parse(contents: &str) -> Result<Settings, String> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    if value.is_null() {
+        return Ok(Settings::default());
+    }
+    let mapping = value.as_mapping_mut().ok_or("the settings are not a mapping")?;
+    let version = mapping.get("version").and_then(serde_yaml::Value::as_u64).unwrap_or(0) as usize;
+    for migrate in MIGRATIONS.iter().skip(version) {
+        migrate(mapping);
+    }
+    if version < MIGRATIONS.len() {
+        mapping.insert("version".into(), SCHEMA_VERSION.into());
+    }
+    serde_yaml::from_value(value).map_err(|e| e.to_string())
+}
+
+// Saving replaces a file the helper can't read in full, one that doesn't parse or that a
+// newer version wrote, so that one is copied aside first instead of being lost
+fn keep_unreadable(path: &Path) -> io::Result<()> {
+    let Ok(contents) = fs::read_to_string(path) else { return Ok(()) };
+    let newer = serde_yaml::from_str::<serde_yaml::Value>(&contents)
+        .ok()
+        .and_then(|value| value.get("version")?.as_u64())
+        .filter(|version| *version > SCHEMA_VERSION as u64);
+    let kept = match (newer, parse(&contents)) {
+        (Some(version), _) => path.with_extension(format!("yml.v{}", version)),
+        (None, Err(_)) => path.with_extension("yml.broken"),
+        (None, Ok(_)) => return Ok(()),
+    };
+    if !kept.exists() {
+        log::warn!("Keeping the previous settings as {}", kept.display());
+        fs::copy(path, kept)?;
+    }
+    Ok(())
+}
+
+// Writes next to `path` and then renames over it, so a crash midway leaves the old file
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temporary = path.with_extension("yml.saving");
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh folder per test, since they run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("espanso-helper-settings-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn version_0_files_are_migrated() {
+        let settings = parse("autosave: false\nyaml_indent: 4\n").unwrap();
+        assert_eq!(settings.version, SCHEMA_VERSION);
+        assert!(!settings.autosave);
+        assert_eq!(settings.yaml_indent, 4);
+        // Whatever is missing takes its default
+        assert_eq!(settings.backup_count, Settings::default().backup_count);
+        assert_eq!(parse("").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn newer_files_are_read_as_far_as_understood() {
+        let settings = parse(&format!("version: {}\nautosave: false\nsetting_from_the_future: 3\n", SCHEMA_VERSION + 1)).unwrap();
+        assert_eq!(settings.version, SCHEMA_VERSION + 1);
+        assert!(!settings.autosave);
+        assert!(parse("- not\n- a mapping\n").is_err());
+    }
+
+    #[test]
+    fn unreadable_files_are_kept_aside() {
+        let dir = temp_dir("keep");
+        let path = dir.join(SETTINGS_FILE);
+        fs::write(&path, "autosave: [broken\n").unwrap();
+        keep_unreadable(&path).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("settings.yml.broken")).unwrap(), "autosave: [broken\n");
+        let newer = format!("version: {}\n", SCHEMA_VERSION + 1);
+        fs::write(&path, &newer).unwrap();
+        keep_unreadable(&path).unwrap();
+        assert_eq!(fs::read_to_string(dir.join(format!("settings.yml.v{}", SCHEMA_VERSION + 1))).unwrap(), newer);
+        // A file this version reads is simply replaced
+        fs::write(&path, "autosave: true\n").unwrap();
+        keep_unreadable(&path).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn writes_replace_the_file_whole() {
+        let dir = temp_dir("atomic");
+        let path = dir.join(SETTINGS_FILE);
+        fs::write(&path, "old contents that are longer than the new ones\n").unwrap();
+        write_atomically(&path, "autosave: true\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "autosave: true\n");
+        assert!(!dir.join("settings.yml.saving").exists());
+    }
+}
@@ -0,0 +1,81 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::app_config_dir;
+
+const SHORTCUTS_FILE: &str = "shortcuts.yml";
+
+// Keys that can be bound, besides the modifiers
+const KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    // Types `argument` into the replacement editor, e.g. `{{date}}`
+    InsertText,
+    // Moves the selected match to the file named by `argument`
+    MoveToFile,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 2] = [ShortcutAction::InsertText, ShortcutAction::MoveToFile];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::InsertText => "Insert text",
+            ShortcutAction::MoveToFile => "Move match to file",
+        }
+    }
+}
+
+// A user-defined key binding such as `Ctrl+Shift+D`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Shortcut {
+    pub keys: String,
+    pub action: ShortcutAction,
+    #[serde(default)]
+    pub argument: String,
+}
+
+impl Default for Shortcut {
+    fn default() -> Self {
+        Self { keys: String::new(), action: ShortcutAction::InsertText, argument: String::new() }
+    }
+}
+
+impl Shortcut {
+    pub fn parse_keys(&self) -> Option<KeyboardShortcut> {
+        let mut modifiers = Modifiers::NONE;
+        let mut key = None;
+        for part in self.keys.split('+').map(str::trim) {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => modifiers = modifiers | Modifiers::COMMAND,
+                "alt" | "option" => modifiers = modifiers | Modifiers::ALT,
+                "shift" => modifiers = modifiers | Modifiers::SHIFT,
+                _ => key = Some(*KEYS.iter().find(|k| k.name().eq_ignore_ascii_case(part))?),
+            }
+        }
+        Some(KeyboardShortcut::new(modifiers, key?))
+    }
+}
+
+pub fn load() -> Vec<Shortcut> {
+    fs::read_to_string(app_config_dir().join(SHORTCUTS_FILE))
+        .ok()
+        .and_then(|contents| serde_yaml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(shortcuts: &[Shortcut]) -> io::Result<()> {
+    let dir = app_config_dir();
+    fs::create_dir_all(&dir)?;
+    let contents = serde_yaml::to_string(shortcuts).map_err(io::Error::other)?;
+    fs::write(dir.join(SHORTCUTS_FILE), contents)
+}
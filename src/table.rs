@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Match;
+
+// Rows of a CSV or JSON export from another snippet tool, before the columns are
+// assigned to trigger and replacement
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub rows: Vec<Vec<String>>,
+    // JSON keys always form a header row
+    pub header_fixed: bool,
+    // How a CSV file was read, with its decoded text for reading it again with another
+    // delimiter; None for JSON
+    pub csv: Option<CsvSource>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CsvSource {
+    pub delimiter: char,
+    pub encoding: &'static str,
+    text: String,
+}
+
+// Delimiters a CSV file is checked for, with how they are shown
+pub const DELIMITERS: [(char, &str); 4] = [(',', "Comma"), (';', "Semicolon"), ('\t', "Tab"), ('|', "Pipe")];
+
+// Which column feeds which part of a match; `has_header` skips the first row
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    pub has_header: bool,
+    pub trigger: usize,
+    pub replace: usize,
+    pub label: Option<usize>,
+    // Tags for the helper's metadata, several per cell separated by commas or semicolons
+    pub tags: Option<usize>,
+}
+
+impl Table {
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or_default()
+    }
+
+    pub fn column_names(&self, has_header: bool) -> Vec<String> {
+        (0..self.width())
+            .map(|column| match self.rows.first().and_then(|row| row.get(column)) {
+                Some(name) if has_header && !name.is_empty() => name.clone(),
+                _ => format!("Column {}", column + 1),
+            })
+            .collect()
+    }
+
+    // Picks columns by their header names, falling back to the first two columns
+    pub fn guess_mapping(&self) -> ColumnMapping {
+        let header: Vec<String> = self.rows.first().map(|row| row.iter().map(|c| c.to_lowercase()).collect()).unwrap_or_default();
+        let find = |names: &[&str]| header.iter().position(|h| names.iter().any(|n| h.contains(n)));
+        let trigger = find(&["trigger", "keyword", "abbr", "shortcut"]);
+        let replace = find(&["replace", "snippet", "expansion", "text", "content", "phrase"]);
+        let label = find(&["label", "name", "description", "title"]).filter(|l| Some(*l) != trigger && Some(*l) != replace);
+        let tags = find(&["tag", "categor", "group", "folder"]).filter(|t| ![trigger, replace, label].contains(&Some(*t)));
+        ColumnMapping {
+            has_header: self.header_fixed || trigger.is_some() || replace.is_some(),
+            trigger: trigger.unwrap_or(0),
+            replace: replace.unwrap_or(1.min(self.width().saturating_sub(1))),
+            label,
+            tags,
+        }
+    }
+
+    // The same CSV text split at another delimiter
+    pub fn with_delimiter(&self, delimiter: char) -> Result<Table, String> {
+        let source = self.csv.as_ref().ok_or("not a CSV file")?;
+        let mut table = split_csv(&source.text, delimiter)?;
+        table.csv = Some(CsvSource { delimiter, encoding: source.encoding, text: source.text.clone() });
+        Ok(table)
+    }
+
+    // Triggers with the tags given for them; empty without a tags column
+    pub fn tags(&self, mapping: &ColumnMapping) -> Vec<(String, Vec<String>)> {
+        let Some(column) = mapping.tags else { return Vec::new() };
+        self.rows
+            .iter()
+            .skip(usize::from(mapping.has_header))
+            .filter_map(|row| {
+                let trigger = row.get(mapping.trigger)?.trim().to_string();
+                let tags: Vec<String> =
+                    row.get(column)?.split([',', ';', '|']).map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect();
+                (!trigger.is_empty() && !tags.is_empty()).then_some((trigger, tags))
+            })
+            .collect()
+    }
+
+    // Rows without a trigger or a replacement are skipped
+    pub fn to_matches(&self, mapping: &ColumnMapping) -> Vec<Match> {
+        let cell = |row: &[String], column: usize| row.get(column).map(|c| c.trim_end_matches('\r').to_string()).unwrap_or_default();
+        self.rows
+            .iter()
+            .skip(usize::from(mapping.has_header))
+            .filter_map(|row| {
+                let trigger = cell(row, mapping.trigger).trim().to_string();
+                let replace = cell(row, mapping.replace);
+                if trigger.is_empty() || replace.is_empty() {
+                    return None;
+                }
+                let label = mapping.label.map(|column| cell(row, column)).filter(|l| !l.is_empty());
+                Some(Match { trigger, replace, label, ..Default::default() })
+            })
+            .collect()
+    }
+}
+
+// Text of a file in whatever encoding a snippet tool wrote it: UTF-8 or UTF-16 (told
+// apart by their byte order marks or by zero bytes), otherwise Windows-1252, which
+// also covers Latin-1. Returns the text and the name of the encoding.
+pub fn decode(bytes: &[u8]) -> (String, &'static str) {
+    let utf16 = |bytes: &[u8], little_endian: bool| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| if little_endian { u16::from_le_bytes([pair[0], pair[1]]) } else { u16::from_be_bytes([pair[0], pair[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return (String::from_utf8_lossy(rest).into_owned(), "UTF-8");
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return (utf16(rest, true), "UTF-16 LE");
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return (utf16(rest, false), "UTF-16 BE");
+    }
+    // ASCII text in UTF-16 has every other byte zero
+    let sample = &bytes[..bytes.len().min(200) & !1];
+    let zeros_at = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|b| **b == 0).count();
+    if !sample.is_empty() && zeros_at(1) * 2 > sample.len() / 2 {
+        return (utf16(bytes, true), "UTF-16 LE");
+    }
+    if !sample.is_empty() && zeros_at(0) * 2 > sample.len() / 2 {
+        return (utf16(bytes, false), "UTF-16 BE");
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "UTF-8"),
+        Err(_) => (bytes.iter().map(|&b| windows_1252(b)).collect(), "Windows-1252"),
+    }
+}
+
+// 0x80–0x9F differ from Latin-1, where they are control characters nobody types
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+// CSV with quoted fields and doubled quotes; the delimiter is whichever of `DELIMITERS`
+// appears most often in the first line
+pub fn parse_csv(bytes: &[u8]) -> Result<Table, String> {
+    let (text, encoding) = decode(bytes);
+    let text = text.trim_start_matches('\u{FEFF}').to_string();
+    let first_line = text.lines().next().unwrap_or_default();
+    let count = |d: &char| first_line.matches(*d).count();
+    // `rev` so a tie goes to the delimiter listed first
+    let delimiter = DELIMITERS.iter().rev().map(|(d, _)| *d).filter(|d| count(d) > 0).max_by_key(count).unwrap_or(',');
+    let mut table = split_csv(&text, delimiter)?;
+    table.csv = Some(CsvSource { delimiter, encoding, text });
+    Ok(table)
+}
+
+fn split_csv(contents: &str, delimiter: char) -> Result<Table, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("a quoted field is not closed".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+    if rows.is_empty() {
+        return Err("the file has no rows".to_string());
+    }
+    Ok(Table { rows, header_fixed: false, csv: None })
+}
+
+// A JSON array of flat objects; the keys of all objects become the columns
+pub fn parse_json(contents: &str) -> Result<Table, String> {
+    let data: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let items = data.as_array().ok_or("expected a list of objects")?;
+    let mut keys: Vec<String> = Vec::new();
+    for object in items.iter().filter_map(Value::as_object) {
+        for key in object.keys() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+    }
+    if keys.is_empty() {
+        return Err("the list contains no objects".to_string());
+    }
+    let mut rows = vec![keys.clone()];
+    for object in items.iter().filter_map(Value::as_object) {
+        rows.push(
+            keys.iter()
+                .map(|key| match object.get(key) {
+                    Some(Value::String(text)) => text.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(other) => other.to_string(),
+                })
+                .collect(),
+        );
+    }
+    Ok(Table { rows, header_fixed: true, csv: None })
+}
+
+// Plain-text matches as rows of trigger, replacement and label, one row per alias.
+// Regex and form or image matches have no such form; their number is returned.
+fn rows(matches: &[Match]) -> (Vec<[String; 3]>, usize) {
+    let plain: Vec<&Match> = matches.iter().filter(|m| m.regex.is_none() && m.content_key().is_none()).collect();
+    let skipped = matches.len() - plain.len();
+    let rows = plain
+        .iter()
+        .flat_map(|m| m.all_triggers().into_iter().map(|trigger| [trigger, m.replace.clone(), m.label.clone().unwrap_or_default()]))
+        .collect();
+    (rows, skipped)
+}
+
+pub fn render_csv(matches: &[Match]) -> (String, usize) {
+    let quote = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+    let (rows, skipped) = rows(matches);
+    let mut output = String::from("trigger,replace,label\n");
+    for row in rows {
+        output.push_str(&row.iter().map(|field| quote(field)).collect::<Vec<_>>().join(","));
+        output.push('\n');
+    }
+    (output, skipped)
+}
+
+// The same columns as `render_csv`, as a list of objects that `parse_json` reads back
+pub fn render_json(matches: &[Match]) -> (String, usize) {
+    let (rows, skipped) = rows(matches);
+    let items: Vec<Value> = rows
+        .into_iter()
+        .map(|[trigger, replace, label]| {
+            let mut item = serde_json::json!({ "trigger": trigger, "replace": replace });
+            if !label.is_empty() {
+                item["label"] = Value::String(label);
+            }
+            item
+        })
+        .collect();
+    (serde_json::to_string_pretty(&items).unwrap_or_default(), skipped)
+}
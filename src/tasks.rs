@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Progress and cancellation shared between a background job and the UI
+#[derive(Debug, Default)]
+pub struct TaskControl {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl TaskControl {
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Long-running work on a background thread, polled once per frame
+#[derive(Debug, Clone)]
+pub struct Task<T> {
+    pub label: String,
+    control: Arc<TaskControl>,
+    result: Arc<Mutex<Option<Result<T, String>>>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn(label: String, job: impl FnOnce(&TaskControl) -> Result<T, String> + Send + 'static) -> Self {
+        let control = Arc::new(TaskControl::default());
+        let result = Arc::new(Mutex::new(None));
+        let (thread_control, slot) = (Arc::clone(&control), Arc::clone(&result));
+        thread::spawn(move || {
+            let outcome = job(&thread_control);
+            if let Ok(mut slot) = slot.lock() {
+                *slot = Some(outcome);
+            }
+        });
+        Self { label, control, result }
+    }
+
+    // The job's outcome once it has finished; a cancelled task reports an error right away
+    pub fn poll(&self) -> Option<Result<T, String>> {
+        if self.control.is_cancelled() {
+            return Some(Err(format!("{} was cancelled", self.label)));
+        }
+        // Only a panicking job poisons the lock, and it never stores a result then
+        match self.result.lock() {
+            Ok(mut result) => result.take(),
+            Err(_) => Some(Err(format!("{} stopped unexpectedly", self.label))),
+        }
+    }
+}
+
+// Type-erased view on a task for the activity indicator
+pub trait RunningTask {
+    fn label(&self) -> &str;
+    // Completed fraction, if the job reports how much work there is
+    fn progress(&self) -> Option<f32>;
+    fn cancel(&self);
+}
+
+impl<T> RunningTask for Task<T> {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn progress(&self) -> Option<f32> {
+        let total = self.control.total.load(Ordering::Relaxed);
+        let done = self.control.done.load(Ordering::Relaxed);
+        (total > 0).then(|| done.min(total) as f32 / total as f32)
+    }
+
+    fn cancel(&self) {
+        self.control.cancelled.store(true, Ordering::Relaxed);
+    }
+}
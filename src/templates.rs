@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::per_file::PerFile;
+
+pub const TEMPLATES_FILE: &str = "templates.yml";
+
+// Skeleton applied to matches created with "Add Match"
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MatchTemplate {
+    #[serde(default)]
+    pub word: bool,
+    #[serde(default)]
+    pub strip_trailing_whitespace: bool,
+    #[serde(default)]
+    pub footer: String,
+}
+
+impl MatchTemplate {
+    pub fn apply(&self, replacement: &str) -> String {
+        let mut result = if self.strip_trailing_whitespace {
+            replacement.trim_end().to_string()
+        } else {
+            replacement.to_string()
+        };
+        if !self.footer.is_empty() && !result.ends_with(&self.footer) {
+            result.push_str(&self.footer);
+        }
+        result
+    }
+}
+
+pub type Templates = PerFile<MatchTemplate>;
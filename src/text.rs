@@ -0,0 +1,299 @@
+// Helpers for cleaning up text that ends up in triggers and replacements.
+
+use serde::{Deserialize, Serialize};
+
+// Characters that are invisible in the editor but still get typed by espanso.
+const INVISIBLE_CHARS: &[char] = &[
+    '\u{200B}', // zero-width space
+    '\u{200C}', // zero-width non-joiner
+    '\u{200D}', // zero-width joiner
+    '\u{2060}', // word joiner
+    '\u{FEFF}', // byte order mark / zero-width no-break space
+    '\u{00AD}', // soft hyphen
+];
+
+// Turns pasted clipboard content into plain text. Line endings are normalized and
+// invisible characters removed; unless `keep_typography` is set, curly quotes and
+// non-breaking spaces are replaced by their plain ASCII counterparts as well.
+pub fn to_plain_text(input: &str, keep_typography: bool) -> String {
+    let text = input.replace("\r\n", "\n").replace('\r', "\n");
+    text.chars()
+        .filter(|c| !INVISIBLE_CHARS.contains(c))
+        .map(|c| if keep_typography { c } else { plain_char(c) })
+        .collect()
+}
+
+fn plain_char(c: char) -> char {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+        c if is_non_breaking_space(c) => ' ',
+        _ => c,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextIssue {
+    Invisible(char),
+    NonBreakingSpace(char),
+    NotNormalized,
+}
+
+impl TextIssue {
+    pub fn describe(&self) -> String {
+        match self {
+            TextIssue::Invisible(c) => format!("invisible character {}", codepoint(*c)),
+            TextIssue::NonBreakingSpace(c) => format!("non-breaking space {}", codepoint(*c)),
+            TextIssue::NotNormalized => "mixed NFC/NFD forms".to_string(),
+        }
+    }
+}
+
+fn codepoint(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+fn is_non_breaking_space(c: char) -> bool {
+    matches!(c, '\u{00A0}' | '\u{202F}' | '\u{2007}')
+}
+
+// Lists every distinct problem found in `text`, in order of first appearance.
+pub fn audit(text: &str) -> Vec<TextIssue> {
+    let mut issues = Vec::new();
+    for c in text.chars() {
+        let issue = if INVISIBLE_CHARS.contains(&c) {
+            TextIssue::Invisible(c)
+        } else if is_non_breaking_space(c) {
+            TextIssue::NonBreakingSpace(c)
+        } else {
+            continue;
+        };
+        if !issues.contains(&issue) {
+            issues.push(issue);
+        }
+    }
+    if !unicode_normalization::is_nfc(text) {
+        issues.push(TextIssue::NotNormalized);
+    }
+    issues
+}
+
+// Removes invisible characters, turns non-breaking spaces into regular ones and
+// composes everything into NFC.
+pub fn normalize(text: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    text.chars()
+        .filter(|c| !INVISIBLE_CHARS.contains(c))
+        .map(|c| if is_non_breaking_space(c) { ' ' } else { c })
+        .nfc()
+        .collect()
+}
+
+// Folds case, accents and a leading `:` away, so `:Sig`, `sig` and `:sïg` compare equal
+pub fn spelling_key(text: &str) -> String {
+    use unicode_normalization::char::is_combining_mark;
+    use unicode_normalization::UnicodeNormalization;
+    text.trim_start_matches(':').nfd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+// Makes invisible and non-breaking characters visible as `⟨U+XXXX⟩` markers.
+pub fn reveal_invisible(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if INVISIBLE_CHARS.contains(&c) || is_non_breaking_space(c) {
+                format!("⟨{}⟩", codepoint(c))
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+const SUMMARY_MAX_CHARS: usize = 80;
+
+// Replaces occurrences of `from` that don't sit inside a longer word, so renaming
+// `:br` leaves `:brb` alone
+pub fn replace_whole(text: &str, from: &str, to: &str) -> String {
+    if from.is_empty() {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in text.match_indices(from) {
+        let end = start + from.len();
+        if start < last {
+            continue;
+        }
+        let before = text[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let after = text[end..].chars().next().is_some_and(char::is_alphanumeric);
+        if !before && !after {
+            result.push_str(&text[last..start]);
+            result.push_str(to);
+            last = end;
+        }
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+// Reduces a (possibly multi-line) replacement to its first non-empty line,
+// marking anything that was left out with an ellipsis.
+pub fn summarize(text: &str) -> String {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let first = lines.next().unwrap_or_default();
+    let mut summary: String = first.chars().take(SUMMARY_MAX_CHARS).collect();
+    if first.chars().count() > SUMMARY_MAX_CHARS || lines.next().is_some() {
+        summary.push('…');
+    }
+    summary
+}
+
+// Whitespace clean-up applied to replacements when a file is saved
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TrimOptions {
+    #[serde(default)]
+    pub strip_line_endings: bool,
+    #[serde(default)]
+    pub trim_trailing_newlines: bool,
+    #[serde(default)]
+    pub collapse_blank_lines: bool,
+}
+
+impl TrimOptions {
+    pub fn is_active(&self) -> bool {
+        self.strip_line_endings || self.trim_trailing_newlines || self.collapse_blank_lines
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        if self.strip_line_endings {
+            lines = lines.into_iter().map(|l| l.trim_end_matches([' ', '\t'])).collect();
+        }
+        if self.collapse_blank_lines {
+            let mut collapsed: Vec<&str> = Vec::new();
+            for line in lines {
+                let blank = line.trim().is_empty();
+                if blank && collapsed.last().is_some_and(|l| l.trim().is_empty()) {
+                    continue;
+                }
+                collapsed.push(line);
+            }
+            lines = collapsed;
+        }
+        let mut result = lines.join("\n");
+        if self.trim_trailing_newlines {
+            let trimmed = result.trim_end_matches(['\n', '\r']).len();
+            result.truncate(trimmed);
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffOp {
+    Same,
+    Removed,
+    Added,
+}
+
+// Beyond this many token pairs the diff gives up and shows the whole text as replaced
+const DIFF_MAX_PAIRS: usize = 1_000_000;
+
+// Word-level difference between two texts. Whitespace runs are tokens of their own,
+// so concatenating the `Same` and `Added` parts gives back `new`.
+pub fn word_diff(old: &str, new: &str) -> Vec<(DiffOp, String)> {
+    diff(diff_tokens(old), diff_tokens(new))
+}
+
+// Line-level difference, for whole files where a word diff would be too fine and slow
+pub fn line_diff(old: &str, new: &str) -> Vec<(DiffOp, String)> {
+    diff(old.split_inclusive('\n').collect(), new.split_inclusive('\n').collect())
+}
+
+fn diff(old: Vec<&str>, new: Vec<&str>) -> Vec<(DiffOp, String)> {
+    let mut parts: Vec<(DiffOp, String)> = Vec::new();
+    let mut push = |op: DiffOp, token: &str| match parts.last_mut() {
+        Some((last, text)) if *last == op => text.push_str(token),
+        _ => parts.push((op, token.to_string())),
+    };
+    if old.len() * new.len() > DIFF_MAX_PAIRS {
+        old.iter().for_each(|t| push(DiffOp::Removed, t));
+        new.iter().for_each(|t| push(DiffOp::Added, t));
+        return parts;
+    }
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(DiffOp::Same, old[i]);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            push(DiffOp::Removed, old[i]);
+            i += 1;
+        } else {
+            push(DiffOp::Added, new[j]);
+            j += 1;
+        }
+    }
+    parts
+}
+
+// A line diff in unified form: each line prefixed with ` `, `-` or `+`, and unchanged
+// stretches cut down to `context` lines around the changes with `⋯` in between
+pub fn unified(parts: &[(DiffOp, String)], context: usize) -> Vec<(DiffOp, String)> {
+    let lines: Vec<(DiffOp, &str)> =
+        parts.iter().flat_map(|(op, text)| text.split_inclusive('\n').map(move |line| (*op, line))).collect();
+    let mut shown = vec![false; lines.len()];
+    for (index, (op, _)) in lines.iter().enumerate() {
+        if *op != DiffOp::Same {
+            let end = (index + context + 1).min(lines.len());
+            shown[index.saturating_sub(context)..end].iter_mut().for_each(|s| *s = true);
+        }
+    }
+    let mut output: Vec<(DiffOp, String)> = Vec::new();
+    for (index, (op, line)) in lines.iter().enumerate() {
+        if !shown[index] {
+            if index == 0 || shown[index - 1] {
+                output.push((DiffOp::Same, "⋯\n".to_string()));
+            }
+            continue;
+        }
+        let prefix = match op {
+            DiffOp::Same => ' ',
+            DiffOp::Removed => '-',
+            DiffOp::Added => '+',
+        };
+        let newline = if line.ends_with('\n') { "" } else { "\n" };
+        output.push((*op, format!("{}{}{}", prefix, line, newline)));
+    }
+    output
+}
+
+fn diff_tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut last_space = None;
+    for (index, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if last_space.is_some_and(|last| last != space) {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        last_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
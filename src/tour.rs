@@ -0,0 +1,84 @@
+use std::fs;
+
+use crate::{app_config_dir, Match};
+
+// Written once the tour was finished or skipped, so it only starts by itself on the first run
+const TOUR_DONE_FILE: &str = "tour_done";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TourStep {
+    FileSelector,
+    AddForm,
+    Filter,
+    Saving,
+}
+
+impl TourStep {
+    pub const ALL: [TourStep; 4] = [TourStep::FileSelector, TourStep::AddForm, TourStep::Filter, TourStep::Saving];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            TourStep::FileSelector => "Match files",
+            TourStep::AddForm => "Adding matches",
+            TourStep::Filter => "Finding matches",
+            TourStep::Saving => "Saving",
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            TourStep::FileSelector => {
+                "espanso reads every .yml file in its match folder. Pick the file to work on here; \
+                 the helper remembers your scroll position and selection per file."
+            }
+            TourStep::AddForm => {
+                "Type a trigger and its replacement, then press Add Match. Use Regex for pattern \
+                 triggers and Record to capture Tab and Enter literally."
+            }
+            TourStep::Filter => {
+                "The filter searches triggers and replacements. Ctrl- and Shift-click select several \
+                 matches; Trigger Grid shows many triggers at once."
+            }
+            TourStep::Saving => {
+                "Every change is written to the file right away. During this tour you are editing \
+                 sample matches, so nothing on disk changes. Audit, Import and Shortcuts have more tools."
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tour {
+    pub step: usize,
+}
+
+impl Tour {
+    pub fn current(&self) -> TourStep {
+        TourStep::ALL[self.step.min(TourStep::ALL.len() - 1)]
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.step + 1 >= TourStep::ALL.len()
+    }
+}
+
+pub fn is_first_run() -> bool {
+    !app_config_dir().join(TOUR_DONE_FILE).exists()
+}
+
+pub fn mark_done() {
+    let dir = app_config_dir();
+    // Failing to write the marker only means the tour shows again next time
+    let _ = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join(TOUR_DONE_FILE), ""));
+}
+
+pub fn sample_matches() -> Vec<Match> {
+    let sample = |trigger: &str, replace: &str| Match { trigger: trigger.to_string(), replace: replace.to_string(), ..Default::default() };
+    vec![
+        sample(":sig", "Best regards,\nAlex"),
+        sample(":addr", "Example Street 1\n12345 Sample City"),
+        sample(":shrug", "¯\\_(ツ)_/¯"),
+        Match { regex: Some(":date(?P<offset>\\d+)".to_string()), ..sample("", "{{offset}} days from now") },
+        Match { aliases: vec![":ty".to_string()], ..sample(":thx", "Thank you!") },
+    ]
+}
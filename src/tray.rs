@@ -0,0 +1,176 @@
+use std::process::Command;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, ConnectionExt as _, EventMask, GrabMode, ModMask};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::CURRENT_TIME;
+
+// Opcode of the tray protocol asking the tray to embed a window
+const SYSTEM_TRAY_REQUEST_DOCK: u32 = 0;
+
+// Keysyms of the keys a shortcut can end in besides letters and digits
+const NAMED_KEYS: [(&str, u32); 5] = [("space", 0x20), ("enter", 0xff0d), ("return", 0xff0d), ("insert", 0xff63), ("pause", 0xff13)];
+
+// A shortcut like `Ctrl+Alt+Space`, as X wants it for grabbing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hotkey {
+    modifiers: u16,
+    keysym: u32,
+}
+
+pub fn parse_hotkey(text: &str) -> Result<Hotkey, String> {
+    let mut modifiers = 0;
+    let mut keysym = None;
+    for part in text.split('+').map(|part| part.trim().to_lowercase()) {
+        let modifier = match part.as_str() {
+            "ctrl" | "control" => ModMask::CONTROL,
+            "alt" => ModMask::M1,
+            "shift" => ModMask::SHIFT,
+            "super" | "win" | "meta" => ModMask::M4,
+            _ => {
+                keysym = Some(key_sym(&part).ok_or_else(|| format!("{} is not a key a shortcut can use", part))?);
+                continue;
+            }
+        };
+        modifiers |= u16::from(modifier);
+    }
+    match keysym {
+        Some(keysym) if modifiers != 0 => Ok(Hotkey { modifiers, keysym }),
+        Some(_) => Err(format!("{} needs Ctrl, Alt, Shift or Super, or it would take the key away from every app", text)),
+        None => Err(format!("{} has no key besides the modifiers", text)),
+    }
+}
+
+fn key_sym(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return c.is_ascii_alphanumeric().then_some(u32::from(c));
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u32>().ok()).filter(|n| (1..=12).contains(n)) {
+        return Some(0xffbe + number - 1);
+    }
+    NAMED_KEYS.iter().find(|(key, _)| *key == name).map(|(_, keysym)| *keysym)
+}
+
+// Starts another instance of the helper, `--quick-add` or the full editor
+fn launch(args: &[&str]) {
+    let started = std::env::current_exe().and_then(|exe| Command::new(exe).args(args).spawn());
+    match started {
+        // Waited for in the background so it doesn't linger as a zombie
+        Ok(mut child) => drop(std::thread::spawn(move || child.wait())),
+        Err(error) => log::error!("Starting the helper failed: {}", error),
+    }
+}
+
+// Runs the tray icon and the global shortcut until the X connection ends, started with
+// `--tray`. A left click or the shortcut opens the quick-add window, a right click the
+// full editor. Without a tray, as on plain GNOME, only the shortcut is there.
+pub fn run(shortcut: &str) -> Result<(), String> {
+    let hotkey = parse_hotkey(shortcut)?;
+    let (conn, screen_number) = x11rb::connect(None).map_err(|e| format!("Connecting to the X server failed: {}", e))?;
+    let screen = conn.setup().roots[screen_number].clone();
+    let keycode = grab(&conn, screen.root, hotkey)?;
+    let icon = match dock(&conn, &screen, screen_number) {
+        Ok(icon) => icon,
+        Err(error) => {
+            log::warn!("No tray icon: {}", error);
+            None
+        }
+    };
+    conn.flush().map_err(|e| e.to_string())?;
+    log::info!("Tray running, quick add on {}", shortcut);
+    loop {
+        let event = conn.wait_for_event().map_err(|e| format!("The X connection ended: {}", e))?;
+        match event {
+            Event::KeyPress(key) if key.detail == keycode => launch(&["--quick-add"]),
+            Event::ButtonPress(button) if Some(button.event) == icon.map(|(window, _)| window) => match button.detail {
+                1 => launch(&["--quick-add"]),
+                3 => launch(&[]),
+                _ => {}
+            },
+            Event::Expose(expose) if expose.count == 0 => {
+                if let Some((window, gc)) = icon {
+                    draw(&conn, window, gc).map_err(|e| e.to_string())?;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Grabs the shortcut on the root window, also with Caps Lock and Num Lock on, which X
+// counts as modifiers. Returns the keycode it ended up on.
+fn grab(conn: &RustConnection, root: xproto::Window, hotkey: Hotkey) -> Result<xproto::Keycode, String> {
+    let setup = conn.setup();
+    let count = setup.max_keycode - setup.min_keycode + 1;
+    let mapping = conn.get_keyboard_mapping(setup.min_keycode, count).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?;
+    let per_keycode = usize::from(mapping.keysyms_per_keycode.max(1));
+    let position = mapping.keysyms.chunks(per_keycode).position(|syms| syms.contains(&hotkey.keysym)).ok_or("the key of the shortcut is not on this keyboard")?;
+    let keycode = setup.min_keycode + position as u8;
+    for locks in [0, u16::from(ModMask::LOCK), u16::from(ModMask::M2), u16::from(ModMask::LOCK | ModMask::M2)] {
+        let grabbed = conn.grab_key(true, root, ModMask::from(hotkey.modifiers | locks), keycode, GrabMode::ASYNC, GrabMode::ASYNC).map_err(|e| e.to_string())?;
+        if grabbed.check().is_err() {
+            return Err("another app already uses the shortcut".to_string());
+        }
+    }
+    Ok(keycode)
+}
+
+fn atom(conn: &RustConnection, name: &str) -> Result<xproto::Atom, String> {
+    Ok(conn.intern_atom(false, name.as_bytes()).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?.atom)
+}
+
+// Asks the tray of the screen to embed an icon window, following the XEmbed based
+// system tray protocol. None when no tray is running.
+fn dock(conn: &RustConnection, screen: &xproto::Screen, screen_number: usize) -> Result<Option<(xproto::Window, xproto::Gcontext)>, String> {
+    let tray = atom(conn, &format!("_NET_SYSTEM_TRAY_S{}", screen_number))?;
+    let owner = conn.get_selection_owner(tray).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?.owner;
+    if owner == x11rb::NONE {
+        return Ok(None);
+    }
+    let window = conn.generate_id().map_err(|e| e.to_string())?;
+    let aux = xproto::CreateWindowAux::new().background_pixel(screen.black_pixel).event_mask(EventMask::EXPOSURE | EventMask::BUTTON_PRESS);
+    conn.create_window(x11rb::COPY_DEPTH_FROM_PARENT, window, screen.root, 0, 0, 24, 24, 0, xproto::WindowClass::INPUT_OUTPUT, x11rb::COPY_FROM_PARENT, &aux)
+        .map_err(|e| e.to_string())?;
+    let title = b"Espanso Helper";
+    conn.change_property8(xproto::PropMode::REPLACE, window, xproto::AtomEnum::WM_NAME, xproto::AtomEnum::STRING, title).map_err(|e| e.to_string())?;
+    // Version 0, mapped once embedded
+    let xembed_info = atom(conn, "_XEMBED_INFO")?;
+    conn.change_property32(xproto::PropMode::REPLACE, window, xembed_info, xembed_info, &[0, 1]).map_err(|e| e.to_string())?;
+    let gc = conn.generate_id().map_err(|e| e.to_string())?;
+    conn.create_gc(gc, window, &xproto::CreateGCAux::new().foreground(screen.white_pixel)).map_err(|e| e.to_string())?;
+    let opcode = atom(conn, "_NET_SYSTEM_TRAY_OPCODE")?;
+    let request = xproto::ClientMessageEvent::new(32, owner, opcode, [CURRENT_TIME, SYSTEM_TRAY_REQUEST_DOCK, window, 0, 0]);
+    conn.send_event(false, owner, EventMask::NO_EVENT, request).map_err(|e| e.to_string())?;
+    Ok(Some((window, gc)))
+}
+
+// A white `E` on the black background, scaled to the size the tray gave the icon
+fn draw(conn: &RustConnection, window: xproto::Window, gc: xproto::Gcontext) -> Result<(), Box<dyn std::error::Error>> {
+    let geometry = conn.get_geometry(window)?.reply()?;
+    let unit = (geometry.width.min(geometry.height) / 8).max(1) as i16;
+    let (x, y) = ((geometry.width as i16 - 5 * unit) / 2, (geometry.height as i16 - 6 * unit) / 2);
+    let bar = |dy: i16, width: i16| xproto::Rectangle { x, y: y + dy * unit, width: (width * unit) as u16, height: unit as u16 };
+    let stem = xproto::Rectangle { x, y, width: unit as u16, height: (6 * unit) as u16 };
+    conn.poly_fill_rectangle(window, gc, &[stem, bar(0, 5), bar(2, 4), bar(5, 5)])?;
+    conn.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_parse() {
+        let hotkey = parse_hotkey("Ctrl+Alt+Space").unwrap();
+        assert_eq!(hotkey, Hotkey { modifiers: u16::from(ModMask::CONTROL | ModMask::M1), keysym: 0x20 });
+        assert_eq!(parse_hotkey("super + E").unwrap().keysym, u32::from('e'));
+        assert_eq!(parse_hotkey("Shift+F12").unwrap().keysym, 0xffc9);
+        assert!(parse_hotkey("Space").is_err());
+        assert!(parse_hotkey("Ctrl+Alt").is_err());
+        assert!(parse_hotkey("Ctrl+Banana").is_err());
+    }
+}
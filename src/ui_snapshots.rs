@@ -0,0 +1,610 @@
+// Renders the main screens headless and compares the text they draw, with its
+// position, against src/snapshots/<screen>.txt. A missing snapshot is written on the
+// first run; run with UPDATE_SNAPSHOTS=1 to accept an intended layout change.
+use eframe::egui;
+use eframe::epaint::{ClippedShape, Shape};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use crate::{espanso_config, settings, EspansoHelper};
+
+const SAMPLE_FILE: &str = concat!(
+    "matches:\n",
+    "  - trigger: \":sig\"\n",
+    "    replace: \"Best regards\"\n",
+    "  - trigger: \":date\"\n",
+    "    replace: \"{{today}}\"\n",
+    "    vars:\n",
+    "      - name: today\n",
+    "        type: date\n",
+    "        params:\n",
+    "          format: \"%Y-%m-%d\"\n",
+);
+
+// A helper pointed at a fresh config dir with `files`, cut off from everything the
+// real session would read from the user's machine
+fn fixture(screen: &str, files: &[(&str, &str)]) -> EspansoHelper {
+    let config_dir = std::env::temp_dir().join(format!("espanso-helper-snapshot-{}", screen)).join("match");
+    let _ = fs::remove_dir_all(&config_dir);
+    fs::create_dir_all(&config_dir).unwrap();
+    for (name, contents) in files {
+        fs::create_dir_all(config_dir.join(name).parent().unwrap()).unwrap();
+        fs::write(config_dir.join(name), contents).unwrap();
+    }
+    let mut helper = EspansoHelper::new(true);
+    helper.safe_mode = false;
+    helper.status.clear();
+    helper.templates = Default::default();
+    helper.trim_rules = Default::default();
+    helper.wrap_modes = Default::default();
+    // Safe mode also stops the daemon checks, which would show up depending on the machine
+    helper.settings = settings::Settings { daemon_poll_interval_secs: 0, ..Default::default() };
+    helper.metadata = Default::default();
+    helper.shortcuts = Vec::new();
+    helper.espanso_config = espanso_config::GlobalConfig::load(config_dir.parent().unwrap());
+    helper.files = crate::list_yaml_files(&config_dir);
+    helper.files.sort();
+    helper.selected_file = helper.files.first().cloned().unwrap_or_default();
+    helper.config_dir = config_dir;
+    if !helper.selected_file.is_empty() {
+        helper.load_matches();
+    }
+    helper.reset_editor();
+    // Background checks would show spinners depending on timing
+    helper.last_validation = Some(Instant::now());
+    helper.last_watch = Some(Instant::now());
+    helper
+}
+
+// Two frames, since some widgets only settle their size on the second one
+fn render(helper: &mut EspansoHelper) -> String {
+    let ctx = egui::Context::default();
+    let input = || egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+        ..Default::default()
+    };
+    let _ = ctx.run(input(), |ctx| helper.show(ctx));
+    let output = ctx.run(input(), |ctx| helper.show(ctx));
+    let mut lines = Vec::new();
+    for ClippedShape(_, shape) in &output.shapes {
+        collect_text(shape, &mut lines);
+    }
+    lines.join("\n") + "\n"
+}
+
+fn collect_text(shape: &Shape, lines: &mut Vec<String>) {
+    match shape {
+        Shape::Vec(shapes) => shapes.iter().for_each(|shape| collect_text(shape, lines)),
+        Shape::Text(text) if !text.galley.text().trim().is_empty() => {
+            lines.push(format!("{:>4} {:>4} {}", text.pos.x.round(), text.pos.y.round(), text.galley.text().replace('\n', "⏎")));
+        }
+        _ => {}
+    }
+}
+
+fn assert_snapshot(screen: &str, rendered: &str) {
+    let path: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("snapshots").join(format!("{}.txt", screen));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, rendered).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(expected, rendered, "{} no longer matches, rerun with UPDATE_SNAPSHOTS=1 if the change is intended", path.display());
+}
+
+#[test]
+fn empty_state() {
+    let mut helper = fixture("empty_state", &[]);
+    assert_snapshot("empty_state", &render(&mut helper));
+}
+
+#[test]
+fn loaded_file() {
+    let mut helper = fixture("loaded_file", &[("base.yml", SAMPLE_FILE)]);
+    assert_snapshot("loaded_file", &render(&mut helper));
+}
+
+#[test]
+fn edit_mode() {
+    let mut helper = fixture("edit_mode", &[("base.yml", SAMPLE_FILE)]);
+    helper.start_editing(1);
+    assert_snapshot("edit_mode", &render(&mut helper));
+}
+
+#[test]
+fn external_change() {
+    let mut helper = fixture("external_change", &[("base.yml", SAMPLE_FILE)]);
+    // As if the file had been rewritten by a sync tool after it was loaded
+    helper.loaded_mtime = Some(SystemTime::UNIX_EPOCH);
+    helper.save_matches();
+    assert!(helper.external_change);
+    assert_snapshot("external_change", &render(&mut helper));
+}
+
+#[test]
+fn review_changes() {
+    let mut helper = fixture("review_changes", &[("base.yml", SAMPLE_FILE)]);
+    helper.settings.review_saves = true;
+    helper.matches[0].replace = "Kind regards".to_string();
+    helper.save_matches();
+    assert!(helper.pending_review.is_some());
+    assert_snapshot("review_changes", &render(&mut helper));
+}
+
+#[test]
+fn pending_review_keeps_the_file_open() {
+    let mut helper = fixture("pending_review", &[("base.yml", SAMPLE_FILE), ("other.yml", "matches: []\n")]);
+    helper.settings.review_saves = true;
+    helper.matches[0].replace = "Kind regards".to_string();
+    helper.save_matches();
+    assert!(helper.unsaved_file);
+    // Leaving the file would drop the change under review
+    helper.select_file("other.yml".to_string());
+    assert_eq!(helper.selected_file, "base.yml");
+    assert_eq!(helper.matches[0].replace, "Kind regards");
+    assert!(helper.pending_review.is_some());
+    helper.accept_review();
+    assert!(!helper.unsaved_file);
+    assert!(fs::read_to_string(helper.config_dir.join("base.yml")).unwrap().contains("Kind regards"));
+    helper.select_file("other.yml".to_string());
+    assert_eq!(helper.selected_file, "other.yml");
+}
+
+#[test]
+fn accepting_a_blocked_review_keeps_it() {
+    let mut helper = fixture("blocked_review", &[("base.yml", SAMPLE_FILE)]);
+    helper.settings.review_saves = true;
+    helper.matches[0].replace = "Kind regards".to_string();
+    helper.save_matches();
+    helper.tour = Some(crate::tour::Tour::default());
+    helper.accept_review();
+    assert!(helper.pending_review.is_some());
+    assert_eq!(helper.status, "Tour: changes to the sample matches are not saved");
+    // A failed write doesn't claim the file was saved
+    helper.tour = None;
+    let path = helper.config_dir.join("base.yml");
+    fs::remove_file(&path).unwrap();
+    fs::create_dir(&path).unwrap();
+    helper.loaded_mtime = helper.current_mtime();
+    helper.accept_review();
+    assert!(helper.unsaved_file);
+    assert!(helper.status.starts_with("Saving base.yml failed"), "{}", helper.status);
+}
+
+#[test]
+fn export_names_stay_in_the_folder() {
+    let mut helper = fixture("export_names", &[("base.yml", SAMPLE_FILE)]);
+    for name in ["../outside", "sub/out", "..", "a\\b"] {
+        helper.export_file_name = name.to_string();
+        helper.export_filter_result();
+        assert_eq!(helper.status, "Enter a file name without a folder", "{}", name);
+    }
+    assert!(!helper.config_dir.parent().unwrap().join("outside.yml").exists());
+    helper.export_file_name = "out".to_string();
+    helper.export_filter_result();
+    assert_eq!(rust_mit_cursor::yaml_io::MatchFile::load(&helper.config_dir.join("out.yml")).matches(), helper.matches);
+}
+
+#[test]
+fn export_removes_matches_from_every_source() {
+    let other = "matches:\n  - trigger: \":date\"\n    replace: \"today\"\n  - trigger: \":keep\"\n    replace: \"kept\"\n";
+    let mut helper = fixture("export_sources", &[("base.yml", SAMPLE_FILE), ("other.yml", other)]);
+    helper.filter_text = ":date".to_string();
+    helper.export_all_files = true;
+    helper.export_remove_sources = true;
+    helper.export_file_name = "dates".to_string();
+    helper.export_filter_result();
+    let load = |file: &str| rust_mit_cursor::yaml_io::MatchFile::load(&helper.config_dir.join(file)).matches();
+    assert_eq!(load("dates.yml").len(), 2);
+    assert_eq!(load("other.yml").len(), 1);
+    assert!(helper.matches.iter().all(|m| m.trigger != ":date"));
+}
+
+#[test]
+fn focus_editor() {
+    let mut helper = fixture("focus_editor", &[("base.yml", SAMPLE_FILE)]);
+    helper.start_editing(0);
+    helper.focus_editor = Some("Best regards,\nAlex".to_string());
+    assert_snapshot("focus_editor", &render(&mut helper));
+    let ctx = egui::Context::default();
+    let _ = ctx.run(egui::RawInput { events: vec![key(egui::Key::Enter, egui::Modifiers::COMMAND)], ..Default::default() }, |ctx| helper.show(ctx));
+    assert!(helper.focus_editor.is_none());
+    assert_eq!(helper.matches[0].replace, "Best regards,\nAlex");
+}
+
+// 1280×800 pixels at 200 %, so half as many points as the other screens
+const ZOOMED_SIZE: egui::Vec2 = egui::vec2(640.0, 400.0);
+
+fn zoomed_frame(ctx: &egui::Context, helper: &mut EspansoHelper, events: Vec<egui::Event>) -> egui::FullOutput {
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, ZOOMED_SIZE)),
+        pixels_per_point: Some(2.0),
+        events,
+        ..Default::default()
+    };
+    ctx.run(input, |ctx| helper.show(ctx))
+}
+
+fn key(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+    egui::Event::Key { key, pressed: true, repeat: false, modifiers }
+}
+
+#[test]
+fn zoomed_controls_fit() {
+    let mut helper = fixture("zoomed_controls_fit", &[("base.yml", SAMPLE_FILE)]);
+    let ctx = egui::Context::default();
+    let _ = zoomed_frame(&ctx, &mut helper, Vec::new());
+    let output = zoomed_frame(&ctx, &mut helper, Vec::new());
+    let mut lines = Vec::new();
+    for ClippedShape(_, shape) in &output.shapes {
+        collect_text(shape, &mut lines);
+        if let Shape::Text(text) = shape {
+            let right = text.pos.x + text.galley.size().x;
+            assert!(right <= ZOOMED_SIZE.x + 1.0, "{} ends at {}, outside the window", text.galley.text(), right);
+        }
+    }
+    assert_snapshot("zoomed_controls_fit", &(lines.join("\n") + "\n"));
+}
+
+#[test]
+fn keyboard_new_match() {
+    let mut helper = fixture("keyboard_new_match", &[("base.yml", SAMPLE_FILE)]);
+    let ctx = egui::Context::default();
+    let none = egui::Modifiers::NONE;
+    let steps = vec![
+        vec![],
+        vec![key(egui::Key::N, egui::Modifiers::COMMAND)],
+        vec![egui::Event::Text(":hi".to_string())],
+        // Past the aliases, the record, wrap and focus buttons into the replacement
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Tab, none)],
+        vec![egui::Event::Text("Hello there".to_string())],
+        // Past the typography checkbox to word mode
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::Space, none)],
+        vec![key(egui::Key::Enter, egui::Modifiers::COMMAND)],
+    ];
+    for events in steps {
+        let _ = zoomed_frame(&ctx, &mut helper, events);
+    }
+    let added = helper.matches.iter().find(|m| m.trigger == ":hi").expect("the match was added");
+    assert_eq!(added.replace, "Hello there");
+    assert!(added.word);
+}
+
+#[test]
+fn keyboard_list_navigation() {
+    let mut helper = fixture("keyboard_list_navigation", &[("base.yml", SAMPLE_FILE)]);
+    let ctx = egui::Context::default();
+    let none = egui::Modifiers::NONE;
+    for events in [vec![], vec![key(egui::Key::ArrowDown, none)], vec![key(egui::Key::ArrowDown, none)], vec![key(egui::Key::Enter, none)]] {
+        let _ = zoomed_frame(&ctx, &mut helper, events);
+    }
+    assert_eq!(helper.editing_index, Some(1));
+    let steps = vec![
+        vec![key(egui::Key::F, egui::Modifiers::COMMAND)],
+        vec![egui::Event::Text("sig".to_string())],
+        vec![key(egui::Key::Escape, none)],
+        vec![key(egui::Key::ArrowDown, none)],
+        vec![key(egui::Key::Delete, none)],
+    ];
+    for events in steps {
+        let _ = zoomed_frame(&ctx, &mut helper, events);
+    }
+    assert_eq!(helper.filter_text, "sig");
+    assert_eq!(helper.pending_delete, Some((0, ":sig".to_string())));
+}
+
+#[test]
+fn large_file_lays_out_visible_rows() {
+    let mut contents = String::from("matches:\n");
+    for i in 0..3000 {
+        contents.push_str(&format!("  - trigger: \":m{}\"\n    replace: \"Match number {}\"\n", i, i));
+    }
+    let mut helper = fixture("large_file", &[("big.yml", &contents)]);
+    assert_eq!(helper.matches.len(), 3000);
+    let rendered = render(&mut helper);
+    let rows = rendered.lines().filter(|line| line.ends_with(" ⋯")).count();
+    assert!(rows > 0 && rows < 100, "{} rows were laid out", rows);
+    // Up with nothing selected goes to the last match, which scrolls into view: its
+    // trigger shows in the list as well as above the details
+    helper.move_selection(-1);
+    assert_eq!(render(&mut helper).lines().filter(|line| line.ends_with(" :m2999")).count(), 2);
+}
+
+#[test]
+fn table_sorted_by_trigger() {
+    let mut helper = fixture("table_sorted", &[("base.yml", SAMPLE_FILE)]);
+    helper.match_sort = Some((crate::MatchColumn::Trigger, false));
+    let rendered = render(&mut helper);
+    let row = |trigger: &str| rendered.lines().position(|line| line.ends_with(&format!(" {}", trigger))).unwrap();
+    assert!(row(":date") < row(":sig"));
+    assert!(rendered.contains("Trigger ⏶"));
+}
+
+#[test]
+fn filter_modes() {
+    let mut helper = fixture("filter_modes", &[("base.yml", SAMPLE_FILE)]);
+    helper.settings.filter_mode = crate::filter::FilterMode::Fuzzy;
+    helper.filter_text = "a".to_string();
+    // Ranked by the query, see filter.rs for the modes themselves
+    assert_eq!(helper.filtered_matches(), vec![1, 0]);
+    helper.settings.filter_mode = crate::filter::FilterMode::Regex;
+    helper.filter_text = "(".to_string();
+    assert!(helper.filtered_matches().is_empty());
+    assert!(render(&mut helper).contains("Invalid regex"));
+}
+
+#[test]
+fn inline_edit_in_list() {
+    let mut helper = fixture("inline_edit", &[("base.yml", SAMPLE_FILE)]);
+    let ctx = egui::Context::default();
+    let none = egui::Modifiers::NONE;
+    let _ = zoomed_frame(&ctx, &mut helper, vec![]);
+    helper.start_inline_edit(0);
+    let steps = vec![
+        vec![],
+        vec![egui::Event::Text("s".to_string())],
+        vec![key(egui::Key::Tab, none)],
+        vec![key(egui::Key::A, egui::Modifiers::COMMAND)],
+        vec![egui::Event::Text("Cheers".to_string())],
+        vec![key(egui::Key::Enter, none)],
+    ];
+    for events in steps {
+        let _ = zoomed_frame(&ctx, &mut helper, events);
+    }
+    assert!(helper.inline_edit.is_none());
+    assert_eq!((helper.matches[0].trigger.as_str(), helper.matches[0].replace.as_str()), (":sigs", "Cheers"));
+    // Escape leaves the match as it was
+    helper.start_inline_edit(1);
+    for events in [vec![], vec![egui::Event::Text("x".to_string())], vec![key(egui::Key::Escape, none)]] {
+        let _ = zoomed_frame(&ctx, &mut helper, events);
+    }
+    assert!(helper.inline_edit.is_none());
+    assert_eq!(helper.matches[1].trigger, ":date");
+}
+
+#[test]
+fn clicking_a_row_selects_it() {
+    let mut helper = fixture("row_click", &[("base.yml", SAMPLE_FILE)]);
+    let ctx = egui::Context::default();
+    let mut frame = |events: Vec<egui::Event>| {
+        let screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0)));
+        ctx.run(egui::RawInput { screen_rect, events, ..Default::default() }, |ctx| helper.show(ctx))
+    };
+    let _ = frame(vec![]);
+    let pos = frame(vec![])
+        .shapes
+        .iter()
+        .find_map(|ClippedShape(_, shape)| match shape {
+            Shape::Text(text) if text.galley.text() == ":date" => Some(text.pos + egui::vec2(2.0, 2.0)),
+            _ => None,
+        })
+        .expect("the row of :date");
+    // The click is applied once the list is drawn
+    let press = |pressed| egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE };
+    for events in [vec![egui::Event::PointerMoved(pos)], vec![press(true)], vec![press(false)]] {
+        let _ = frame(events);
+    }
+    assert_eq!(helper.selected_index, Some(1));
+}
+
+#[test]
+fn grouped_by_tag() {
+    let mut helper = fixture("grouped_by_tag", &[("base.yml", SAMPLE_FILE)]);
+    let first = helper.matches[0].clone();
+    helper.metadata.get_mut("base.yml", &first).tags.push("greeting".to_string());
+    helper.group_by_tag = true;
+    let rendered = render(&mut helper);
+    assert!(rendered.contains("By Tag"), "{}", rendered);
+    assert!(rendered.contains("🏷 greeting (1)"), "{}", rendered);
+    assert!(rendered.contains("Untagged (1)"), "{}", rendered);
+}
+
+#[test]
+fn clipboard_candidates() {
+    let mut helper = fixture("clipboard_candidates", &[("base.yml", SAMPLE_FILE)]);
+    helper.clipboard.observe("Dear Sir,\nthanks".to_string());
+    helper.clipboard.observe("second".to_string());
+    let rendered = render(&mut helper);
+    assert!(rendered.contains("Copied Text (2)"), "{}", rendered);
+    helper.new_match_with_replacement(helper.clipboard.candidates[1].clone());
+    assert_eq!(helper.new_replacement, "Dear Sir,\nthanks");
+    assert!(helper.focus_new_match);
+}
+
+#[test]
+fn search_all_files() {
+    let own = "matches:\n  - trigger: \":addr2\"\n    replace: \"Second address\"\n  - trigger: \":home\"\n    replace: \"Send to :addr\"\n";
+    let package = "matches:\n  - trigger: \":addr\"\n    replace: \"Main Street 1\"\n  - trigger: \":addrx\"\n    replace: \"Elsewhere\"\n";
+    let mut helper = fixture("search_all_files", &[("base.yml", own), ("packages/contacts/package.yml", package)]);
+    helper.show_search = true;
+    helper.search_query = ":ADDR".to_string();
+    let ctx = egui::Context::default();
+    let started = Instant::now();
+    while helper.search_index.is_none() && started.elapsed() < std::time::Duration::from_secs(5) {
+        let _ = zoomed_frame(&ctx, &mut helper, Vec::new());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let output = zoomed_frame(&ctx, &mut helper, Vec::new());
+    let mut lines = Vec::new();
+    for ClippedShape(_, shape) in &output.shapes {
+        collect_text(shape, &mut lines);
+    }
+    let rendered = lines.join("\n");
+    assert!(rendered.contains("package contacts (read-only)"), "{}", rendered);
+    let position = |text: &str| rendered.find(text).unwrap_or_else(|| panic!("{} missing in\n{}", text, rendered));
+    assert!(rendered.contains("4 results"), "{}", rendered);
+    // In the order search.rs ranks them
+    assert!(position("trigger: Main Street 1") < position("in replacement: Send to :addr"), "{}", rendered);
+}
+
+#[test]
+fn import_converts_espanso_1_files() {
+    let legacy = concat!(
+        "name: work\n",
+        "parent: default\n",
+        "filter_title: \"Slack\"\n",
+        "toggle_key: ALT\n",
+        "global_vars:\n",
+        "  - name: company\n",
+        "    type: dummy\n",
+        "    params:\n",
+        "      echo: ACME\n",
+        "matches:\n",
+        "  - trigger: \":greet\"\n",
+        "    replace: \"Hello $0$, meet $1$\"\n",
+        "    passive_only: true\n",
+        "  - trigger: \":sig\"\n",
+        "    replace: \"Best, {{name}}\"\n",
+        "    vars:\n",
+        "      - name: name\n",
+        "        type: dummy\n",
+        "        params:\n",
+        "          echo: Ann\n",
+    );
+    let mut helper = fixture("import_legacy", &[("base.yml", SAMPLE_FILE)]);
+    let source = helper.config_dir.parent().unwrap().join("work.yml");
+    fs::write(&source, legacy).unwrap();
+    helper.import_path = source.display().to_string();
+    helper.preview_import();
+    let started = Instant::now();
+    while helper.import_preview.is_none() && started.elapsed() < std::time::Duration::from_secs(5) {
+        helper.poll_import_preview();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let preview = helper.import_preview.clone().expect("the preview is ready");
+    let greet = &preview.candidates[0];
+    assert_eq!(greet.regex.as_deref(), Some(r":greet/(?P<arg0>[^/]*)/(?P<arg1>[^/]*)/"));
+    assert_eq!(greet.replace, "Hello {{arg0}}, meet {{arg1}}");
+    assert_eq!(preview.candidates[1].vars[0].var_type, "echo");
+    let notes = preview.legacy_notes.expect("converted");
+    assert_eq!(notes.len(), 3, "{:?}", notes);
+    assert!(notes[0].starts_with("filter_title: Slack"), "{:?}", notes);
+    assert!(notes[1].starts_with("toggle_key"), "{:?}", notes);
+    assert!(notes[2].starts_with(":greet takes arguments"), "{:?}", notes);
+    // The global variables come along into the target file
+    helper.commit_import();
+    let saved = rust_mit_cursor::yaml_io::MatchFile::load(&helper.config_dir.join("base.yml"));
+    assert_eq!(saved.global_vars().iter().map(|var| (var.name.as_str(), var.var_type.as_str())).collect::<Vec<_>>(), [("company", "echo")]);
+    assert_eq!(saved.matches().len(), 3, "the colliding :sig is skipped");
+    assert!(fs::read_to_string(helper.config_dir.join("base.yml")).unwrap().starts_with("global_vars:\n"));
+}
+
+#[test]
+fn safe_mode_does_not_watch_files() {
+    let mut helper = fixture("safe_mode_watch", &[("base.yml", SAMPLE_FILE)]);
+    // As after starting with --safe-mode, before the user loads anything
+    helper.safe_mode = true;
+    helper.matches.clear();
+    helper.loaded_mtime = None;
+    helper.last_watch = None;
+    helper.poll_file_changes();
+    assert!(helper.matches.is_empty());
+}
+
+#[test]
+fn safe_mode_does_not_validate() {
+    let mut helper = fixture("safe_mode_validate", &[("base.yml", SAMPLE_FILE)]);
+    helper.safe_mode = true;
+    helper.last_validation = None;
+    helper.poll_validation();
+    assert!(helper.pending_validation.is_none());
+}
+
+#[test]
+fn held_back_changes_survive_an_external_change() {
+    let mut helper = fixture("held_back_changes", &[("base.yml", SAMPLE_FILE), ("other.yml", "matches: []\n")]);
+    helper.settings.autosave = false;
+    helper.matches[0].replace = "Kind regards".to_string();
+    helper.save_matches();
+    assert!(helper.unsaved_file);
+    // As if another editor wrote the file after it was loaded
+    helper.loaded_mtime = Some(SystemTime::UNIX_EPOCH);
+    helper.select_file("other.yml".to_string());
+    assert_eq!(helper.selected_file, "base.yml");
+    assert!(helper.unsaved_file && helper.external_change);
+    assert_eq!(helper.matches[0].replace, "Kind regards");
+    helper.overwrite_matches();
+    assert!(!helper.unsaved_file && !helper.external_change);
+    assert!(fs::read_to_string(helper.config_dir.join("base.yml")).unwrap().contains("Kind regards"));
+}
+
+#[test]
+fn reload_keeps_the_editor_on_its_match() {
+    let mut helper = fixture("reload_follows_editor", &[("base.yml", SAMPLE_FILE)]);
+    let path = helper.config_dir.join("base.yml");
+    helper.start_editing(1);
+    helper.selected_index = Some(1);
+    // Another editor adds a match above the one being edited
+    fs::write(&path, SAMPLE_FILE.replacen("matches:\n", "matches:\n  - trigger: \":new\"\n    replace: \"New\"\n", 1)).unwrap();
+    helper.loaded_mtime = Some(SystemTime::UNIX_EPOCH);
+    assert!(helper.reload_if_changed());
+    assert_eq!((helper.editing_index, helper.selected_index), (Some(2), Some(2)));
+    assert_eq!(helper.matches[2].trigger, ":date");
+    // And then removes it
+    fs::write(&path, "matches:\n  - trigger: \":new\"\n    replace: \"New\"\n").unwrap();
+    helper.loaded_mtime = Some(SystemTime::UNIX_EPOCH);
+    assert!(helper.reload_if_changed());
+    assert_eq!((helper.editing_index, helper.selected_index), (None, None));
+}
+
+#[test]
+fn removed_file_keeps_unsaved_changes() {
+    let mut helper = fixture("removed_with_changes", &[("base.yml", SAMPLE_FILE), ("other.yml", "matches: []\n")]);
+    helper.settings.autosave = false;
+    helper.matches[0].replace = "Kind regards".to_string();
+    helper.save_matches();
+    fs::remove_file(helper.config_dir.join("base.yml")).unwrap();
+    helper.last_watch = None;
+    helper.poll_file_changes();
+    assert_eq!(helper.selected_file, "base.yml");
+    assert!(helper.external_change);
+    assert_eq!(helper.matches[0].replace, "Kind regards");
+    helper.overwrite_matches();
+    assert!(fs::read_to_string(helper.config_dir.join("base.yml")).unwrap().contains("Kind regards"));
+}
+
+#[test]
+fn merge_keeps_the_conflict_copy_until_written() {
+    let copy = "base.sync-conflict-20240101-120000-ABCDEFG.yml";
+    let theirs = "matches:\n  - trigger: \":theirs\"\n    replace: \"From the laptop\"\n";
+    let mut helper = fixture("merge_conflict_copy", &[("base.yml", SAMPLE_FILE), (copy, theirs)]);
+    helper.select_file("base.yml".to_string());
+    helper.settings.autosave = false;
+    helper.settings.review_saves = true;
+    let path = helper.config_dir.join("base.yml");
+    // A failed write leaves the copy in place
+    helper.open_merge();
+    fs::remove_file(&path).unwrap();
+    fs::create_dir(&path).unwrap();
+    helper.apply_merge();
+    assert!(helper.config_dir.join(copy).exists());
+    fs::remove_dir(&path).unwrap();
+    fs::write(&path, SAMPLE_FILE).unwrap();
+    helper.load_matches();
+    // Neither autosave being off nor the review holds the merge back
+    helper.open_merge();
+    let merged = helper.merge_session.as_ref().unwrap().resolve();
+    helper.apply_merge();
+    assert!(!helper.config_dir.join(copy).exists());
+    assert!(!helper.unsaved_file);
+    assert_eq!(rust_mit_cursor::yaml_io::MatchFile::load(&path).matches(), merged);
+}
+
+#[test]
+fn merge_variants_folds_every_alias() {
+    let file = "matches:\n  - trigger: \":a\"\n    replace: \"Same\"\n  - trigger: \":b\"\n    replace: \"Other\"\n  - trigger: \":c\"\n    replace: \"Same\"\n  - trigger: \":d\"\n    replace: \" Same\"\n";
+    let mut helper = fixture("merge_variants", &[("base.yml", file)]);
+    helper.make_aliases(0, &[2, 3]);
+    assert_eq!(helper.matches.len(), 2);
+    assert_eq!(helper.matches[0].aliases, [":c", ":d"]);
+    assert_eq!(helper.selected_index, Some(0));
+    assert_eq!(rust_mit_cursor::yaml_io::MatchFile::load(&helper.config_dir.join("base.yml")).matches(), helper.matches);
+}
@@ -0,0 +1,179 @@
+use serde_yaml::Value;
+use std::path::PathBuf;
+
+use crate::remote;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/alexander-graf/rust_mit_cursor/releases/latest";
+
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    pub page_url: String,
+    pub asset_url: Option<String>,
+    // Hex SHA256 the release publishes for the asset, checked before it is made executable
+    pub asset_sha256: Option<String>,
+}
+
+impl Release {
+    pub fn is_newer_than_running(&self) -> bool {
+        is_newer(&self.version, env!("CARGO_PKG_VERSION"))
+    }
+}
+
+// Asks GitHub for the latest published release. The API answers in JSON,
+// which serde_yaml reads just fine.
+pub fn latest_release() -> Result<Release, String> {
+    let body = remote::fetch(RELEASES_URL)?;
+    let data: Value = serde_yaml::from_str(&body).map_err(|e| e.to_string())?;
+    let version = data.get("tag_name").and_then(Value::as_str).ok_or("release has no tag")?;
+    let page_url = data.get("html_url").and_then(Value::as_str).unwrap_or_default();
+    let assets: Vec<&Value> = data.get("assets").and_then(Value::as_sequence).into_iter().flatten().collect();
+    fn url_of(asset: &Value) -> Option<&str> {
+        asset.get("browser_download_url")?.as_str()
+    }
+    let asset = assets.iter().copied().find(|asset| url_of(asset).is_some_and(is_platform_asset));
+    let asset_url = asset.and_then(url_of);
+    // GitHub reports a digest for every asset; older releases only have a checksum file
+    let asset_sha256 = match asset {
+        Some(asset) => match asset.get("digest").and_then(Value::as_str).and_then(|digest| digest.strip_prefix("sha256:")) {
+            Some(digest) => Some(digest.to_lowercase()),
+            None => {
+                let name = asset.get("name").and_then(Value::as_str).unwrap_or_default();
+                let checksums = assets.iter().copied().filter_map(url_of).find(|url| is_checksum_file(url, name));
+                checksums.and_then(|url| remote::fetch(url).ok()).and_then(|contents| published_sha256(&contents, name))
+            }
+        },
+        None => None,
+    };
+    Ok(Release {
+        version: version.trim_start_matches('v').to_string(),
+        page_url: page_url.to_string(),
+        asset_url: asset_url.map(str::to_string),
+        asset_sha256,
+    })
+}
+
+// `<asset>.sha256` or a `SHA256SUMS` listing every asset
+fn is_checksum_file(url: &str, asset_name: &str) -> bool {
+    let name = url.rsplit('/').next().unwrap_or_default();
+    name == format!("{}.sha256", asset_name) || name.to_lowercase().starts_with("sha256sums")
+}
+
+// The hash of `asset_name` in `sha256sum` output, or the only hash of a single-asset file
+fn published_sha256(contents: &str, asset_name: &str) -> Option<String> {
+    let is_hash = |word: &str| word.len() == 64 && word.chars().all(|c| c.is_ascii_hexdigit());
+    let lines: Vec<Vec<&str>> = contents.lines().map(|line| line.split_whitespace().collect()).filter(|words: &Vec<&str>| words.first().is_some_and(|w| is_hash(w))).collect();
+    let named = lines.iter().find(|words| words.get(1).is_some_and(|name| name.trim_start_matches('*') == asset_name));
+    let line = named.or_else(|| lines.first().filter(|words| lines.len() == 1 && words.len() == 1))?;
+    Some(line[0].to_lowercase())
+}
+
+fn is_platform_asset(url: &str) -> bool {
+    let name = url.rsplit('/').next().unwrap_or_default().to_lowercase();
+    cfg!(target_os = "linux") && (name.ends_with(".appimage") || name.contains("linux"))
+}
+
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version.split(['.', '-']).map_while(|part| part.parse().ok()).collect()
+    };
+    parse(latest) > parse(current)
+}
+
+// Downloads a release asset into the user's download folder and marks it executable once
+// its SHA256 matches the one the release published
+pub fn download_asset(url: &str, sha256: Option<&str>) -> Result<PathBuf, String> {
+    let file_name = url.rsplit('/').next().filter(|n| !n.is_empty()).ok_or("invalid download URL")?;
+    let expected = sha256.ok_or_else(|| format!("the release publishes no SHA256 for {}, download it from the release page", file_name))?;
+    let target = dirs::download_dir().or_else(dirs::home_dir).unwrap_or_default().join(file_name);
+    let partial = target.with_file_name(format!("{}.part", file_name));
+    remote::download(url, &partial)?;
+    let contents = std::fs::read(&partial).map_err(|e| e.to_string())?;
+    let actual: String = sha256_digest(&contents).iter().map(|byte| format!("{:02x}", byte)).collect();
+    if actual != expected.to_lowercase() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(format!("{} doesn't match the SHA256 the release published and was deleted", file_name));
+    }
+    std::fs::rename(&partial, &target).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    Ok(target)
+}
+
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74,
+    0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d,
+    0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e,
+    0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5,
+    0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// SHA-256 as in FIPS 180-4; no crate for it is worth pulling in for one check
+fn sha256_digest(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(SHA256_ROUND_CONSTANTS[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (word, added) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(added);
+        }
+    }
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(data: &[u8]) -> String {
+        sha256_digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn sha256_known_answers() {
+        assert_eq!(hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[test]
+    fn checksum_files() {
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let sums = format!("{}  helper-windows.exe\n{}  *helper-linux.AppImage\n", "0".repeat(64), hash);
+        assert_eq!(published_sha256(&sums, "helper-linux.AppImage").as_deref(), Some(hash));
+        assert_eq!(published_sha256(&sums, "other"), None);
+        assert_eq!(published_sha256(&format!("{}\n", hash.to_uppercase()), "helper-linux.AppImage").as_deref(), Some(hash));
+        assert!(is_checksum_file("https://example.com/helper-linux.AppImage.sha256", "helper-linux.AppImage"));
+        assert!(is_checksum_file("https://example.com/SHA256SUMS.txt", "helper-linux.AppImage"));
+    }
+}
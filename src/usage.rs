@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::app_config_dir;
+
+const USAGE_FILE: &str = "usage.yml";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BulkOperation {
+    Delete,
+    Move,
+    Copy,
+    WordMode,
+    Wrap,
+    Normalize,
+    RenameGlobalVar,
+    Export,
+}
+
+impl BulkOperation {
+    pub const ALL: [BulkOperation; 8] = [
+        BulkOperation::Delete,
+        BulkOperation::Move,
+        BulkOperation::Copy,
+        BulkOperation::WordMode,
+        BulkOperation::Wrap,
+        BulkOperation::Normalize,
+        BulkOperation::RenameGlobalVar,
+        BulkOperation::Export,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            BulkOperation::Delete => "delete",
+            BulkOperation::Move => "move",
+            BulkOperation::Copy => "copy",
+            BulkOperation::WordMode => "word_mode",
+            BulkOperation::Wrap => "wrap",
+            BulkOperation::Normalize => "normalize",
+            BulkOperation::RenameGlobalVar => "rename_global_var",
+            BulkOperation::Export => "export",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BulkOperation::Delete => "Deleted several matches",
+            BulkOperation::Move => "Moved several matches",
+            BulkOperation::Copy => "Copied several matches",
+            BulkOperation::WordMode => "Enabled word mode",
+            BulkOperation::Wrap => "Wrapped replacements",
+            BulkOperation::Normalize => "Normalized a file",
+            BulkOperation::RenameGlobalVar => "Renamed a global variable",
+            BulkOperation::Export => "Exported matches",
+        }
+    }
+}
+
+// How the helper gets used, counted only when the user opts in. It stays in the
+// helper's config dir; nothing here is ever sent anywhere.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Usage {
+    // When counting started
+    pub since: Option<String>,
+    // Saved changes per match file
+    pub file_edits: BTreeMap<String, usize>,
+    // Runs per `BulkOperation::key`
+    pub bulk_operations: BTreeMap<String, usize>,
+}
+
+impl Usage {
+    pub fn load() -> Self {
+        fs::read_to_string(app_config_dir().join(USAGE_FILE))
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(dir.join(USAGE_FILE), contents)
+    }
+
+    fn start(&mut self) {
+        if self.since.is_none() {
+            self.since = Some(chrono::Local::now().format("%Y-%m-%d").to_string());
+        }
+    }
+
+    pub fn record_edit(&mut self, file: &str) {
+        self.start();
+        *self.file_edits.entry(file.to_string()).or_default() += 1;
+    }
+
+    pub fn record_bulk(&mut self, operation: BulkOperation) {
+        self.start();
+        *self.bulk_operations.entry(operation.key().to_string()).or_default() += 1;
+    }
+
+    pub fn bulk_count(&self, operation: BulkOperation) -> usize {
+        self.bulk_operations.get(operation.key()).copied().unwrap_or(0)
+    }
+
+    // Files by saved changes, most first
+    pub fn most_edited(&self, limit: usize) -> Vec<(&str, usize)> {
+        let mut files: Vec<(&str, usize)> = self.file_edits.iter().map(|(file, count)| (file.as_str(), *count)).collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        files.truncate(limit);
+        files
+    }
+}
@@ -0,0 +1,318 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::risk::{self, Dictionary, Risk};
+use crate::tasks::TaskControl;
+use crate::yaml_io::{self, MatchFile};
+use crate::Match;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Error, Severity::Warning, Severity::Info];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Info => "Info",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProblemKind {
+    ParseError,
+    Duplicate,
+    UndefinedVar,
+    RiskyTrigger,
+    UnlabeledRegex,
+    LargeReplacement,
+    TriggerWhitespace,
+    EmptyReplacement,
+    ShadowedTrigger,
+    TriggerPrefix,
+}
+
+impl ProblemKind {
+    // Lint rules that can be switched off or given another severity; a file espanso
+    // can't parse is always an error
+    pub const RULES: [ProblemKind; 9] = [
+        ProblemKind::Duplicate,
+        ProblemKind::UndefinedVar,
+        ProblemKind::RiskyTrigger,
+        ProblemKind::UnlabeledRegex,
+        ProblemKind::LargeReplacement,
+        ProblemKind::TriggerWhitespace,
+        ProblemKind::EmptyReplacement,
+        ProblemKind::ShadowedTrigger,
+        ProblemKind::TriggerPrefix,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProblemKind::ParseError => "Parse error",
+            ProblemKind::Duplicate => "Duplicate trigger",
+            ProblemKind::UndefinedVar => "Undefined variable",
+            ProblemKind::RiskyTrigger => "Risky trigger",
+            ProblemKind::UnlabeledRegex => "Regex without label",
+            ProblemKind::LargeReplacement => "Large replacement",
+            ProblemKind::TriggerWhitespace => "Whitespace around trigger",
+            ProblemKind::EmptyReplacement => "Empty replacement",
+            ProblemKind::ShadowedTrigger => "Trigger prefix of another",
+            ProblemKind::TriggerPrefix => "Trigger without prefix",
+        }
+    }
+
+    // Name of the rule in the settings file
+    pub fn key(self) -> &'static str {
+        match self {
+            ProblemKind::ParseError => "parse_error",
+            ProblemKind::Duplicate => "duplicate_trigger",
+            ProblemKind::UndefinedVar => "undefined_variable",
+            ProblemKind::RiskyTrigger => "risky_trigger",
+            ProblemKind::UnlabeledRegex => "regex_without_label",
+            ProblemKind::LargeReplacement => "large_replacement",
+            ProblemKind::TriggerWhitespace => "trigger_whitespace",
+            ProblemKind::EmptyReplacement => "empty_replacement",
+            ProblemKind::ShadowedTrigger => "shadowed_trigger",
+            ProblemKind::TriggerPrefix => "trigger_prefix",
+        }
+    }
+
+    fn default_severity(self) -> Severity {
+        match self {
+            ProblemKind::ParseError | ProblemKind::Duplicate | ProblemKind::UndefinedVar => Severity::Error,
+            ProblemKind::RiskyTrigger
+            | ProblemKind::UnlabeledRegex
+            | ProblemKind::TriggerWhitespace
+            | ProblemKind::EmptyReplacement
+            | ProblemKind::ShadowedTrigger => Severity::Warning,
+            ProblemKind::LargeReplacement | ProblemKind::TriggerPrefix => Severity::Info,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RuleSetting {
+    pub enabled: bool,
+    pub severity: Severity,
+}
+
+// Which lint rules run and how serious their findings are, as kept in the settings.
+// Rules missing from the map run with their default severity.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LintRules {
+    pub rules: BTreeMap<String, RuleSetting>,
+    // Replacements above this size are reported by `LargeReplacement`
+    pub large_replacement_kb: usize,
+    // What every plain trigger should start with for `TriggerPrefix`; empty accepts any
+    pub trigger_prefix: String,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        Self { rules: BTreeMap::new(), large_replacement_kb: 4, trigger_prefix: ":".to_string() }
+    }
+}
+
+impl LintRules {
+    pub fn get(&self, kind: ProblemKind) -> RuleSetting {
+        if kind == ProblemKind::ParseError {
+            return RuleSetting { enabled: true, severity: Severity::Error };
+        }
+        self.rules.get(kind.key()).copied().unwrap_or(RuleSetting { enabled: true, severity: kind.default_severity() })
+    }
+
+    pub fn set(&mut self, kind: ProblemKind, setting: RuleSetting) {
+        self.rules.insert(kind.key().to_string(), setting);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub severity: Severity,
+    // Position in the file's match list; `None` for problems with the file itself
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+// Problems of every match file, keyed by file name
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub files: HashMap<String, Vec<Problem>>,
+    // Every trigger and alias with the file and list position of the matches defining it
+    pub triggers: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl Report {
+    pub fn for_file(&self, file: &str) -> &[Problem] {
+        self.files.get(file).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn for_match(&self, file: &str, index: usize) -> impl Iterator<Item = &Problem> {
+        self.for_file(file).iter().filter(move |p| p.index == Some(index))
+    }
+
+    pub fn total(&self) -> usize {
+        self.files.values().map(Vec::len).sum()
+    }
+
+    // How often each rule fired at each severity, for the lint summary
+    pub fn counts(&self) -> BTreeMap<(Severity, &'static str), usize> {
+        let mut counts = BTreeMap::new();
+        for problem in self.files.values().flatten() {
+            *counts.entry((problem.severity, problem.kind.name())).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn definitions(&self, trigger: &str) -> &[(String, usize)] {
+        self.triggers.get(trigger).map_or(&[], Vec::as_slice)
+    }
+}
+
+// Reads every match file from disk and collects what espanso would trip over
+pub fn validate(config_dir: &Path, files: &[String], dictionary: &Dictionary, rules: &LintRules, control: &TaskControl) -> Report {
+    control.set_total(files.len() * 2);
+    let mut parsed = Vec::new();
+    for file in files {
+        if control.is_cancelled() {
+            return Report::default();
+        }
+        let contents = fs::read_to_string(config_dir.join(file)).unwrap_or_default();
+        let document = MatchFile::parse(&contents);
+        parsed.push((file, yaml_io::parse_error(&contents), document.matches(), document.global_vars()));
+        control.advance();
+    }
+
+    let mut report = Report::default();
+    for (file, _, matches, _) in &parsed {
+        for (index, m) in matches.iter().enumerate() {
+            for trigger in m.all_triggers() {
+                report.triggers.entry(trigger).or_default().push((file.to_string(), index));
+            }
+        }
+    }
+    let variable = Regex::new(r"\{\{\s*(\w+)[\w.]*\s*\}\}").expect("valid regex");
+    let global_vars: HashSet<String> = parsed.iter().flat_map(|(_, _, _, vars)| vars.iter().map(|v| v.name.clone())).collect();
+
+    for (file, error, matches, _) in &parsed {
+        let mut problems = Vec::new();
+        if let Some(error) = error {
+            problems.push(Problem { kind: ProblemKind::ParseError, severity: Severity::Error, index: None, message: error.clone() });
+        }
+        for (index, m) in matches.iter().enumerate() {
+            let mut problem = |kind: ProblemKind, message| {
+                let setting = rules.get(kind);
+                if setting.enabled {
+                    problems.push(Problem { kind, severity: setting.severity, index: Some(index), message });
+                }
+            };
+            for trigger in m.all_triggers() {
+                let defined_in = report.definitions(&trigger);
+                if defined_in.len() > 1 {
+                    let mut others: Vec<&str> = defined_in.iter().map(|(f, _)| f.as_str()).filter(|f| f != file).collect();
+                    others.dedup();
+                    let message = if others.is_empty() {
+                        format!("{} is defined {} times in this file", trigger, defined_in.len())
+                    } else {
+                        format!("{} is also defined in {}", trigger, others.join(", "))
+                    };
+                    problem(ProblemKind::Duplicate, message);
+                }
+                if m.regex.is_none() && risk::score(&trigger, dictionary).0 == Risk::High {
+                    problem(ProblemKind::RiskyTrigger, format!("{} is a dictionary word and fires while typing", trigger));
+                }
+            }
+            for name in undefined_vars(m, &variable, &global_vars) {
+                problem(ProblemKind::UndefinedVar, format!("{{{{{}}}}} is not defined", name));
+            }
+            // The search bar shows the label, and a regex alone says little about what it expands to
+            if m.regex.is_some() && m.label.as_deref().is_none_or(|label| label.trim().is_empty()) {
+                problem(ProblemKind::UnlabeledRegex, "regex matches should have a label to be found in the search bar".to_string());
+            }
+            if m.replace.len() > rules.large_replacement_kb * 1024 {
+                problem(ProblemKind::LargeReplacement, format!("the replacement is {} KB, above the {} KB limit", m.replace.len().div_ceil(1024), rules.large_replacement_kb));
+            }
+            let siblings = matches.iter().enumerate().filter(|(other, _)| *other != index).map(|(_, m)| m);
+            for (kind, message) in lint_match(m, siblings, rules) {
+                problem(kind, message);
+            }
+        }
+        if !problems.is_empty() {
+            report.files.insert(file.to_string(), problems);
+        }
+        control.advance();
+    }
+    report
+}
+
+// Checks on the match itself and against the other matches of its file, also run
+// when a match is added or updated. Findings of disabled rules are included.
+pub fn lint_match<'a>(m: &Match, siblings: impl Iterator<Item = &'a Match>, rules: &LintRules) -> Vec<(ProblemKind, String)> {
+    let mut found = Vec::new();
+    if m.content_key().is_none() && m.replace.trim().is_empty() {
+        let message = if m.replace.is_empty() { "the replacement is empty" } else { "the replacement is only whitespace" };
+        found.push((ProblemKind::EmptyReplacement, message.to_string()));
+    }
+    if m.regex.is_some() {
+        return found;
+    }
+    let triggers = m.all_triggers();
+    for trigger in &triggers {
+        if trigger.trim() != trigger {
+            found.push((ProblemKind::TriggerWhitespace, format!("\"{}\" starts or ends with whitespace", trigger)));
+        }
+        if !rules.trigger_prefix.is_empty() && !trigger.starts_with(&rules.trigger_prefix) {
+            found.push((ProblemKind::TriggerPrefix, format!("{} doesn't start with {}", trigger, rules.trigger_prefix)));
+        }
+    }
+    // espanso expands as soon as a trigger is typed, so a longer trigger starting with
+    // a shorter one is never reached, unless the shorter one waits for a word end
+    for other in siblings.filter(|other| other.regex.is_none()) {
+        for trigger in triggers.iter().filter(|t| !t.is_empty()) {
+            for other_trigger in other.all_triggers().iter().filter(|t| !t.is_empty() && *t != trigger) {
+                if !other.word && trigger.starts_with(other_trigger.as_str()) {
+                    found.push((ProblemKind::ShadowedTrigger, format!("{} can't be typed, {} expands first", trigger, other_trigger)));
+                } else if !m.word && other_trigger.starts_with(trigger.as_str()) {
+                    found.push((ProblemKind::ShadowedTrigger, format!("{} expands before {} can be typed", trigger, other_trigger)));
+                }
+            }
+        }
+    }
+    found
+}
+
+// Variables used in the replacement that neither the match, a global var nor a
+// regex capture group provides
+fn undefined_vars(m: &Match, variable: &Regex, global_vars: &HashSet<String>) -> Vec<String> {
+    // Form matches define their fields in the layout itself
+    if m.content_key() == Some("form") {
+        return Vec::new();
+    }
+    let captures: Vec<String> = m
+        .regex
+        .as_deref()
+        .and_then(|r| Regex::new(r).ok())
+        .map(|r| r.capture_names().flatten().map(str::to_string).collect())
+        .unwrap_or_default();
+    let mut undefined: Vec<String> = variable
+        .captures_iter(&m.replace)
+        .map(|caps| caps[1].to_string())
+        .filter(|name| !m.vars.iter().any(|v| &v.name == name) && !global_vars.contains(name) && !captures.contains(name))
+        .collect();
+    undefined.dedup();
+    undefined
+}
@@ -0,0 +1,113 @@
+use regex::Regex;
+use serde_yaml::Value;
+
+use crate::model::Var;
+
+// Variable types the editor has fields for; others keep their params untouched
+pub const TYPES: &[&str] = &["date", "clipboard", "echo", "random", "shell", "script"];
+
+// Shells espanso's `shell` extension accepts besides its platform default
+pub const SHELLS: &[&str] = &["", "bash", "sh", "zsh", "powershell", "pwsh", "cmd", "wsl"];
+
+pub enum Param {
+    // A single string
+    Text(&'static str, &'static str),
+    // A list of strings, edited one per line
+    Lines(&'static str, &'static str),
+    Flag(&'static str, &'static str),
+    Shell,
+}
+
+// The params the editor shows for a variable type
+pub fn params(var_type: &str) -> &'static [Param] {
+    match var_type {
+        "date" => &[
+            Param::Text("format", "Format (e.g. %Y-%m-%d)"),
+            Param::Text("offset", "Offset in seconds"),
+            Param::Text("locale", "Locale (e.g. de-DE)"),
+        ],
+        "echo" => &[Param::Text("echo", "Text")],
+        "random" => &[Param::Lines("choices", "Choices, one per line")],
+        "shell" => &[Param::Text("cmd", "Command"), Param::Shell, Param::Flag("trim", "Trim output")],
+        "script" => &[Param::Lines("args", "Arguments, one per line"), Param::Flag("trim", "Trim output")],
+        _ => &[],
+    }
+}
+
+pub fn text(var: &Var, key: &str) -> String {
+    match var.params.get(key) {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Number(number)) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+// Empty values remove the param; numbers stay numbers, so `offset: 3600` round-trips
+pub fn set_text(var: &mut Var, key: &str, value: &str) {
+    if value.is_empty() {
+        var.params.remove(key);
+    } else if let Some(number) = value.parse::<i64>().ok().filter(|_| key == "offset") {
+        var.params.insert(key.into(), number.into());
+    } else {
+        var.params.insert(key.into(), value.into());
+    }
+}
+
+pub fn lines(var: &Var, key: &str) -> String {
+    var.params
+        .get(key)
+        .and_then(Value::as_sequence)
+        .map(|items| items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("\n"))
+        .unwrap_or_default()
+}
+
+pub fn set_lines(var: &mut Var, key: &str, value: &str) {
+    let items: Vec<Value> = value.lines().filter(|l| !l.is_empty()).map(Value::from).collect();
+    if items.is_empty() {
+        var.params.remove(key);
+    } else {
+        var.params.insert(key.into(), Value::Sequence(items));
+    }
+}
+
+pub fn flag(var: &Var, key: &str) -> bool {
+    var.params.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+pub fn set_flag(var: &mut Var, key: &str, value: bool) {
+    if value {
+        var.params.insert(key.into(), true.into());
+    } else {
+        var.params.remove(key);
+    }
+}
+
+// Names must be set and unique for espanso to resolve `{{name}}`
+pub fn check(vars: &[Var]) -> Result<(), String> {
+    for (index, var) in vars.iter().enumerate() {
+        if var.name.trim().is_empty() {
+            return Err(format!("Variable {} needs a name", index + 1));
+        }
+        if vars[..index].iter().any(|v| v.name == var.name) {
+            return Err(format!("Variable {} is defined twice", var.name));
+        }
+    }
+    Ok(())
+}
+
+// `text` with `{{old}}` and `{{old.field}}` references pointing to `new` instead
+pub fn rename_references(text: &str, old: &str, new: &str) -> String {
+    let reference = Regex::new(&format!(r"\{{\{{(\s*){}(\b[\w.]*\s*)\}}\}}", regex::escape(old))).expect("valid regex");
+    reference.replace_all(text, format!("{{{{${{1}}{}${{2}}}}}}", new)).into_owned()
+}
+
+// Runs `update` over every string in `value`, e.g. the params of a variable
+pub fn map_strings(value: &mut Value, update: &dyn Fn(&str) -> String) {
+    match value {
+        Value::String(text) => *text = update(text),
+        Value::Sequence(items) => items.iter_mut().for_each(|item| map_strings(item, update)),
+        Value::Mapping(mapping) => mapping.iter_mut().for_each(|(_, item)| map_strings(item, update)),
+        Value::Tagged(tagged) => map_strings(&mut tagged.value, update),
+        _ => {}
+    }
+}
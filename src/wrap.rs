@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::{app_config_dir, Match};
+
+const WRAP_FILE: &str = "wrap.yml";
+
+// The raw YAML editor, which has no match to take a hint from
+pub const YAML: &str = "yaml";
+
+// What kind of text a replacement holds, so shell one-liners can scroll sideways while
+// prose keeps wrapping
+pub fn hint(m: &Match) -> &'static str {
+    if m.vars.iter().any(|v| v.var_type == "shell" || v.var_type == "script") {
+        "shell"
+    } else if m.extra.contains_key("html") {
+        "html"
+    } else if m.extra.contains_key("markdown") {
+        "markdown"
+    } else {
+        "text"
+    }
+}
+
+// Soft wrap (true) or horizontal scrolling (false) per hint, as last chosen by the user
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WrapModes(BTreeMap<String, bool>);
+
+impl WrapModes {
+    pub fn load() -> Self {
+        fs::read_to_string(app_config_dir().join(WRAP_FILE))
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = app_config_dir();
+        fs::create_dir_all(&dir)?;
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(dir.join(WRAP_FILE), contents)
+    }
+
+    // Code scrolls by default, since wrapping a command makes it hard to read
+    pub fn soft_wrap(&self, hint: &str) -> bool {
+        self.0.get(hint).copied().unwrap_or(!matches!(hint, "shell" | YAML))
+    }
+
+    pub fn set(&mut self, hint: &str, soft_wrap: bool) {
+        self.0.insert(hint.to_string(), soft_wrap);
+    }
+}
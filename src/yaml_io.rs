@@ -0,0 +1,694 @@
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::model::{Match, Var};
+use crate::vars;
+
+// Spaces per level for YAML written from scratch, from the settings
+static INDENT: AtomicUsize = AtomicUsize::new(2);
+
+/// Spaces per level, 2 to 8, for YAML written from scratch; existing entries keep theirs
+pub fn set_indent(width: usize) {
+    INDENT.store(width.clamp(2, 8), Ordering::Relaxed);
+}
+
+/// Keys besides `replace` that define what a match expands to
+pub const CONTENT_KEYS: &[&str] = &["form", "image_path", "markdown", "html"];
+
+// Keys `Match` has fields for; everything else ends up in `Match::extra`
+const KNOWN_KEYS: &[&str] = &[
+    "trigger", "triggers", "regex", "replace", "word", "label", "propagate_case", "force_clipboard", "vars",
+];
+
+/// A match file as found on disk. Files may consist of several YAML documents;
+/// only the one holding `matches` is touched, the others are written back verbatim.
+#[derive(Debug, Clone, Default)]
+pub struct MatchFile {
+    /// Leading comment block of the file, without the `#` markers
+    pub header: String,
+    // Header as last read or written, to tell whether it was edited
+    saved_header: String,
+    // The header lines exactly as read, blank lines included, written back while it's unedited
+    header_source: String,
+    documents: Vec<String>,
+    match_document: usize,
+    root: Mapping,
+    // Files synced from Windows keep their line endings
+    crlf: bool,
+    // Set when the file on disk no longer matches what was parsed, e.g. after a merge
+    modified: bool,
+    // Source text of the match list, so unchanged entries keep their comments and formatting
+    layout: Option<Box<MatchListLayout>>,
+    // Why the file couldn't be read in full; saving it would lose what was skipped
+    error: Option<String>,
+}
+
+// The match document split around its `matches` list
+#[derive(Debug, Clone)]
+struct MatchListLayout {
+    // Everything up to and including the `matches:` line
+    prefix: String,
+    items: Vec<LayoutItem>,
+    indent: usize,
+    suffix: String,
+}
+
+#[derive(Debug, Clone)]
+struct LayoutItem {
+    // `None` for entries `Match` can't represent, which are written back untouched
+    parsed: Option<Match>,
+    // Comments and blank lines above the entry
+    leading: String,
+    text: String,
+}
+
+impl MatchFile {
+    /// A missing file is an empty one; any other read error is kept in `error`
+    pub fn load(file_path: &Path) -> Self {
+        match fs::read_to_string(file_path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => Self { error: Some(error.to_string()), ..Self::default() },
+        }
+    }
+
+    /// Never fails; what can't be parsed is reported by [`MatchFile::error`]
+    pub fn parse(contents: &str) -> Self {
+        let (header, body) = split_header(contents);
+        let header_source = contents[..contents.len() - body.len()].to_string();
+        let mut documents = split_documents(body);
+        if documents.len() > 1 && documents[0].trim().is_empty() {
+            documents.remove(0);
+        }
+        let mut error = None;
+        let parsed: Vec<Value> = documents
+            .iter()
+            .map(|doc| {
+                serde_yaml::from_str(doc).unwrap_or_else(|e| {
+                    error.get_or_insert(e.to_string());
+                    Value::Null
+                })
+            })
+            .collect();
+        let match_document = parsed
+            .iter()
+            .position(|doc| doc.get("matches").is_some())
+            .or_else(|| parsed.iter().position(Value::is_mapping))
+            .unwrap_or(0);
+        let root = parsed
+            .get(match_document)
+            .and_then(Value::as_mapping)
+            .cloned()
+            .unwrap_or_default();
+        let crlf = contents.contains("\r\n");
+        let layout = documents.get(match_document).and_then(|doc| MatchListLayout::parse(doc, &root)).map(Box::new);
+        Self { saved_header: header.clone(), header, header_source, documents, match_document, root, crlf, modified: false, layout, error }
+    }
+
+    /// Why the file couldn't be read in full; such files refuse to be saved
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Makes the next save write the file even if the matches look unchanged
+    pub fn mark_modified(&mut self) {
+        self.modified = true;
+    }
+
+    /// The number of YAML documents in the file, separated by `---`
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// The entries of the `matches` list; ones that aren't a match are left out but kept in the file
+    pub fn matches(&self) -> Vec<Match> {
+        match self.root.get("matches").and_then(|m| m.as_sequence()) {
+            Some(matches) => matches.iter().filter_map(value_to_match).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Variables defined under `global_vars`, which every match in the config can use
+    pub fn global_vars(&self) -> Vec<Var> {
+        self.root
+            .get("global_vars")
+            .and_then(|v| serde_yaml::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Renames the global variable `old` to `new` and runs `update` over the params of
+    /// every global variable. The text around the match list is edited in place to keep
+    /// its comments; when the result doesn't parse back to the same, the match document
+    /// is written from scratch instead. False if no global variable changed.
+    pub fn rename_global_var(&mut self, old: &str, new: &str, update: &dyn Fn(&str) -> String) -> bool {
+        let matches = self.matches();
+        let Some(Value::Sequence(vars)) = self.root.get_mut("global_vars") else { return false };
+        let before = vars.clone();
+        for var in vars.iter_mut().filter_map(Value::as_mapping_mut) {
+            if var.get("name").and_then(Value::as_str) == Some(old) {
+                var.insert("name".into(), new.into());
+            }
+            if let Some(params) = var.get_mut("params") {
+                vars::map_strings(params, update);
+            }
+        }
+        if *vars == before {
+            return false;
+        }
+        self.modified = true;
+        if let Some(layout) = &mut self.layout {
+            let name = Regex::new(&format!(r#"(?m)^(\s*(?:-\s+)?name:\s*)(["']?){}(["']?\s*(?:#.*)?)$"#, regex::escape(old))).expect("valid regex");
+            for text in [&mut layout.prefix, &mut layout.suffix] {
+                *text = update(&name.replace_all(text, format!("${{1}}${{2}}{}${{3}}", new)));
+            }
+        }
+        let expected = self.global_vars();
+        let reparsed = self.render(&matches).map(|output| Self::parse(&output));
+        if !reparsed.is_ok_and(|file| file.global_vars() == expected && file.matches() == matches) {
+            self.layout = None;
+        }
+        true
+    }
+
+    /// Appends `added` to the file's `global_vars`. A file without any gets the list
+    /// written above its match list, keeping the rest of the text as it is.
+    pub fn add_global_vars(&mut self, added: &[Var]) -> bool {
+        let values: Vec<Value> = added.iter().filter_map(|var| serde_yaml::to_value(var).ok()).collect();
+        if values.is_empty() {
+            return false;
+        }
+        let matches = self.matches();
+        let had_vars = self.root.contains_key("global_vars");
+        match self.root.get_mut("global_vars") {
+            Some(Value::Sequence(vars)) => vars.extend(values.iter().cloned()),
+            _ => {
+                self.root.insert("global_vars".into(), Value::Sequence(values.clone()));
+            }
+        }
+        self.modified = true;
+        if let Some(layout) = self.layout.as_mut().filter(|_| !had_vars) {
+            // Indented like the entries of the match list
+            let padding = " ".repeat(layout.indent);
+            let items = reindent(&serde_yaml::to_string(&values).unwrap_or_default());
+            let block: String = std::iter::once("global_vars:\n".to_string()).chain(items.lines().map(|line| format!("{}{}\n", padding, line))).collect();
+            let start = layout.prefix.trim_end_matches('\n').rfind('\n').map_or(0, |end| end + 1);
+            layout.prefix.insert_str(start, &block);
+        } else {
+            self.layout = None;
+        }
+        let expected = self.global_vars();
+        let reparsed = self.render(&matches).map(|output| Self::parse(&output));
+        if !reparsed.is_ok_and(|file| file.global_vars() == expected && file.matches() == matches) {
+            self.layout = None;
+        }
+        true
+    }
+
+    /// Renders the whole file with `matches` replacing the previous match list
+    pub fn render(&self, matches: &[Match]) -> Result<String, String> {
+        let body = match &self.layout {
+            Some(layout) => layout.render(matches),
+            None => self.render_document(matches)?,
+        };
+
+        let mut output = if self.header == self.saved_header { self.header_source.clone() } else { render_header(&self.header) };
+        if self.documents.len() <= 1 {
+            output.push_str(&body);
+            return Ok(self.with_line_endings(output));
+        }
+        for (index, document) in self.documents.iter().enumerate() {
+            if index == self.match_document {
+                output.push_str(&body);
+            } else {
+                output.push_str(document);
+            }
+        }
+        Ok(self.with_line_endings(output))
+    }
+
+    // The match document serialized from scratch, for files without a usable layout
+    fn render_document(&self, matches: &[Match]) -> Result<String, String> {
+        let mut root = self.root.clone();
+        root.insert(
+            Value::String("matches".to_string()),
+            Value::Sequence(matches.iter().map(match_to_value).collect()),
+        );
+        let mut output = String::new();
+        let document = self.documents.get(self.match_document);
+        if let Some(marker) = document.and_then(|d| d.lines().next()).filter(|l| is_document_marker(l)) {
+            output.push_str(marker);
+            output.push('\n');
+        }
+        output.push_str(&reindent(&serde_yaml::to_string(&Value::Mapping(root)).map_err(|e| e.to_string())?));
+        Ok(output)
+    }
+
+    fn with_line_endings(&self, output: String) -> String {
+        if self.crlf {
+            output.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            output
+        }
+    }
+
+    /// Leaves the file alone when neither the matches nor the header changed, so merely
+    /// opening a synced file never rewrites it in a different formatting
+    pub fn save(&mut self, file_path: &Path, matches: &[Match]) -> Result<(), String> {
+        if let Some(error) = &self.error {
+            return Err(format!("it wasn't read in full and saving would lose the rest ({})", error));
+        }
+        let unchanged = !self.modified && self.header == self.saved_header && self.matches() == matches;
+        if unchanged && file_path.exists() {
+            return Ok(());
+        }
+        let output = self.render(matches)?;
+        fs::write(file_path, &output).map_err(|e| e.to_string())?;
+        *self = Self::parse(&output);
+        Ok(())
+    }
+}
+
+impl MatchListLayout {
+    fn parse(document: &str, root: &Mapping) -> Option<Self> {
+        let expected = root.get("matches")?.as_sequence()?;
+        let lines: Vec<&str> = document.split_inclusive('\n').collect();
+        let start = lines.iter().position(|line| {
+            let rest = line.trim_end().strip_prefix("matches:");
+            rest.is_some_and(|r| r.trim().is_empty() || r.trim_start().starts_with('#'))
+        })?;
+        let mut layout = Self {
+            prefix: lines[..=start].concat(),
+            items: Vec::new(),
+            indent: 0,
+            suffix: String::new(),
+        };
+        let mut raw_items: Vec<(String, String)> = Vec::new();
+        let mut pending = String::new();
+        let mut indent = None;
+        for (position, line) in lines.iter().enumerate().skip(start + 1) {
+            let content = line.trim_end();
+            let line_indent = content.len() - content.trim_start_matches(' ').len();
+            let trimmed = content.trim_start();
+            let item_start = trimmed == "-" || trimmed.starts_with("- ");
+            if indent.is_none() && item_start {
+                indent = Some(line_indent);
+            }
+            let list_indent = indent.unwrap_or(usize::MAX);
+            if trimmed.is_empty() || (trimmed.starts_with('#') && (raw_items.is_empty() || line_indent <= list_indent)) {
+                pending.push_str(line);
+            } else if item_start && line_indent == list_indent {
+                raw_items.push((std::mem::take(&mut pending), line.to_string()));
+            } else if line_indent > list_indent && !raw_items.is_empty() {
+                let (_, item) = raw_items.last_mut()?;
+                item.push_str(&std::mem::take(&mut pending));
+                item.push_str(line);
+            } else {
+                layout.suffix = pending + &lines[position..].concat();
+                pending = String::new();
+                break;
+            }
+        }
+        layout.suffix.push_str(&pending);
+        layout.indent = indent.unwrap_or(0);
+
+        // Only trust the split if every entry parses to exactly what the full parse saw
+        if raw_items.len() != expected.len() {
+            return None;
+        }
+        for ((leading, text), value) in raw_items.into_iter().zip(expected) {
+            let dedented: String = text
+                .split_inclusive('\n')
+                .map(|line| {
+                    let spaces = line.len() - line.trim_start_matches(' ').len();
+                    &line[spaces.min(layout.indent)..]
+                })
+                .collect();
+            let parsed: Vec<Value> = serde_yaml::from_str(&dedented).ok()?;
+            if parsed.len() != 1 || parsed[0] != *value {
+                return None;
+            }
+            layout.items.push(LayoutItem { parsed: value_to_match(value), leading, text });
+        }
+        Some(layout)
+    }
+
+    // Writes unchanged entries from their source text and only renders new or edited ones.
+    // Edited entries are recognized by their trigger and keep the comments above them.
+    fn render(&self, matches: &[Match]) -> String {
+        let mut output = self.prefix.clone();
+        let mut used = vec![false; self.items.len()];
+        let mut flushed = 0;
+        for m in matches {
+            let unused = |same: &dyn Fn(&Match) -> bool| {
+                (0..self.items.len()).find(|&i| !used[i] && self.items[i].parsed.as_ref().is_some_and(same))
+            };
+            let unchanged = unused(&|item| item == m);
+            let edited = unchanged.or_else(|| unused(&|item| item.display_trigger() == m.display_trigger()));
+            if let Some(index) = edited {
+                // Entries the helper can't show stay where they were relative to the others
+                for item in &self.items[flushed.min(index)..index] {
+                    if item.parsed.is_none() {
+                        push_line_block(&mut output, &item.leading);
+                        push_line_block(&mut output, &item.text);
+                    }
+                }
+                flushed = flushed.max(index);
+                used[index] = true;
+                push_line_block(&mut output, &self.items[index].leading);
+            }
+            match unchanged {
+                Some(index) => push_line_block(&mut output, &self.items[index].text),
+                None => {
+                    let padding = " ".repeat(self.indent);
+                    for line in reindent(&match_fragment(m)).lines() {
+                        if !line.is_empty() {
+                            output.push_str(&padding);
+                        }
+                        output.push_str(line);
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+        for item in &self.items[flushed..] {
+            if item.parsed.is_none() {
+                push_line_block(&mut output, &item.leading);
+                push_line_block(&mut output, &item.text);
+            }
+        }
+        output.push_str(&self.suffix);
+        output
+    }
+}
+
+// Appends source lines, making sure the next entry starts on a line of its own
+fn push_line_block(output: &mut String, text: &str) {
+    output.push_str(text);
+    if !text.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+fn value_to_match(m: &Value) -> Option<Match> {
+    let regex = m.get("regex").and_then(Value::as_str).map(str::to_string);
+    let mut triggers: Vec<String> = match m.get("triggers").and_then(Value::as_sequence) {
+        Some(list) => list.iter().filter_map(|t| Some(t.as_str()?.to_string())).collect(),
+        None => m.get("trigger").and_then(Value::as_str).map(str::to_string).into_iter().collect(),
+    };
+    if regex.is_none() && triggers.is_empty() {
+        return None;
+    }
+    let trigger = if triggers.is_empty() { String::new() } else { triggers.remove(0) };
+    let mut extra: Mapping = m.as_mapping()?
+        .iter()
+        .filter(|(key, _)| !key.as_str().is_some_and(|k| KNOWN_KEYS.contains(&k)))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let replace = match m.get("replace") {
+        Some(replace) => replace.as_str()?.to_string(),
+        None if CONTENT_KEYS.iter().any(|key| extra.contains_key(*key)) => String::new(),
+        None => return None,
+    };
+    // Variables the model can't represent stay untouched in `extra`
+    let vars = match m.get("vars").map(|v| serde_yaml::from_value::<Vec<Var>>(v.clone())) {
+        Some(Ok(vars)) => vars,
+        Some(Err(_)) => {
+            extra.insert(Value::String("vars".to_string()), m["vars"].clone());
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let flag = |key: &str| m.get(key).and_then(Value::as_bool).unwrap_or(false);
+    Some(Match {
+        trigger,
+        aliases: triggers,
+        regex,
+        replace,
+        word: flag("word"),
+        label: m.get("label").and_then(Value::as_str).map(str::to_string),
+        propagate_case: flag("propagate_case"),
+        force_clipboard: flag("force_clipboard"),
+        vars,
+        extra,
+    })
+}
+
+fn match_to_value(m: &Match) -> Value {
+    let trigger = match &m.regex {
+        Some(regex) => (Value::String("regex".to_string()), Value::String(regex.clone())),
+        None if m.aliases.is_empty() => (Value::String("trigger".to_string()), Value::String(m.trigger.clone())),
+        None => (
+            Value::String("triggers".to_string()),
+            Value::Sequence(m.all_triggers().into_iter().map(Value::String).collect()),
+        ),
+    };
+    let mut mapping = Mapping::from_iter(vec![trigger]);
+    // Form and image matches have no `replace`
+    if !m.replace.is_empty() || m.content_key().is_none() {
+        mapping.insert(Value::String("replace".to_string()), Value::String(m.replace.clone()));
+    }
+    if let Some(label) = &m.label {
+        mapping.insert(Value::String("label".to_string()), Value::String(label.clone()));
+    }
+    for (key, enabled) in [("word", m.word), ("propagate_case", m.propagate_case), ("force_clipboard", m.force_clipboard)] {
+        if enabled {
+            mapping.insert(Value::String(key.to_string()), Value::Bool(true));
+        }
+    }
+    if !m.vars.is_empty() {
+        mapping.insert(Value::String("vars".to_string()), serde_yaml::to_value(&m.vars).unwrap_or_default());
+    }
+    mapping.extend(m.extra.iter().map(|(key, value)| (key.clone(), value.clone())));
+    Value::Mapping(mapping)
+}
+
+/// The YAML written for a single match, as it appears in the `matches` list
+pub fn match_fragment(m: &Match) -> String {
+    serde_yaml::to_string(&Value::Sequence(vec![match_to_value(m)])).unwrap_or_default()
+}
+
+/// A self-contained match file for sharing: the matches plus the global variables
+/// they refer to, so the file works without the rest of the config
+pub fn render_standalone(header: &str, global_vars: &[Var], matches: &[Match]) -> String {
+    let fragments: Vec<String> = matches.iter().map(match_fragment).collect();
+    let used: Vec<&Var> = global_vars
+        .iter()
+        .filter(|var| fragments.iter().any(|f| f.contains(&format!("{{{{{}}}}}", var.name)) || f.contains(&format!("{{{{{}.", var.name))))
+        .collect();
+    let mut root = Mapping::new();
+    if !used.is_empty() {
+        root.insert("global_vars".into(), serde_yaml::to_value(&used).unwrap_or_default());
+    }
+    root.insert("matches".into(), Value::Sequence(matches.iter().map(match_to_value).collect()));
+    render_header(header) + &reindent(&serde_yaml::to_string(&Value::Mapping(root)).unwrap_or_default())
+}
+
+// serde_yaml always indents by two spaces; this widens its output to the configured
+// indent. Block scalar content keeps its own extra spaces. Anything that wouldn't read
+// back the same is returned unchanged.
+fn reindent(yaml: &str) -> String {
+    let width = INDENT.load(Ordering::Relaxed);
+    if width == 2 {
+        return yaml.to_string();
+    }
+    let mut output = String::new();
+    // Old and new column of the key owning the `|` or `>` block being copied
+    let mut block: Option<(usize, usize)> = None;
+    for line in yaml.split_inclusive('\n') {
+        let content = line.trim_start_matches(' ');
+        let old = line.len() - content.len();
+        if let Some((owner_old, owner_new)) = block {
+            if content.trim().is_empty() {
+                output.push_str(content);
+                continue;
+            }
+            if old > owner_old {
+                output.push_str(&" ".repeat(owner_new + width + old.saturating_sub(owner_old + 2)));
+                output.push_str(content);
+                continue;
+            }
+            block = None;
+        }
+        let (mut key_old, mut key_new) = (old, old / 2 * width);
+        output.push_str(&" ".repeat(key_new));
+        let mut rest = content;
+        while let Some(item) = rest.strip_prefix("- ") {
+            output.push('-');
+            output.push_str(&" ".repeat(width - 1));
+            rest = item;
+            key_old += 2;
+            key_new += width;
+        }
+        output.push_str(rest);
+        let value = rest.trim_end().rsplit(": ").next().unwrap_or_default();
+        if value.starts_with(['|', '>']) && value[1..].chars().all(|c| matches!(c, '-' | '+')) {
+            block = Some((key_old, key_new));
+        }
+    }
+    let same = |text: &str| serde_yaml::from_str::<Value>(text).ok();
+    match same(&output) {
+        Some(value) if Some(&value) == same(yaml).as_ref() => output,
+        _ => yaml.to_string(),
+    }
+}
+
+/// The first YAML syntax error in `contents`, if any
+pub fn parse_error(contents: &str) -> Option<String> {
+    let (_, body) = split_header(contents);
+    split_documents(body)
+        .iter()
+        .find_map(|doc| serde_yaml::from_str::<Value>(doc).err())
+        .map(|e| e.to_string())
+}
+
+// Separates the comment lines at the very top of a file from the rest
+fn split_header(contents: &str) -> (String, &str) {
+    let mut header = Vec::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            header.push(comment.strip_prefix(' ').unwrap_or(comment).to_string());
+        } else if !trimmed.is_empty() {
+            break;
+        }
+        offset += line.len();
+    }
+    (header.join("\n").trim_end().to_string(), &contents[offset..])
+}
+
+fn render_header(header: &str) -> String {
+    if header.trim().is_empty() {
+        return String::new();
+    }
+    let mut output: String = header
+        .lines()
+        .map(|line| if line.is_empty() { "#\n".to_string() } else { format!("# {}\n", line) })
+        .collect();
+    output.push('\n');
+    output
+}
+
+fn is_document_marker(line: &str) -> bool {
+    line == "---" || line.starts_with("--- ") || line.starts_with("---\t")
+}
+
+// Splits raw YAML text at `---` markers, keeping each marker with the document it opens
+fn split_documents(contents: &str) -> Vec<String> {
+    let mut documents = Vec::new();
+    let mut current = String::new();
+    for line in contents.split_inclusive('\n') {
+        if is_document_marker(line.trim_end_matches(['\r', '\n'])) && !current.trim().is_empty() {
+            documents.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+    }
+    documents.push(current);
+    documents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = "matches:\n  - trigger: \":a\"\n    replace: \"A\"\n  - trigger: \":b\"\n    replace: \"B\"\n";
+
+    #[test]
+    fn the_header_is_written_back_as_read() {
+        for header in ["#x\n", "# one\n#\n\n# two\n\n\n", "# no blank line after it\n", "  # indented\n\n"] {
+            let contents = format!("{}{}", header, BODY);
+            let file = MatchFile::parse(&contents);
+            let mut matches = file.matches();
+            assert_eq!(file.render(&matches).unwrap(), contents);
+            matches[0].replace = "changed".to_string();
+            assert!(file.render(&matches).unwrap().starts_with(&format!("{}matches:\n", header)), "{:?}", header);
+        }
+    }
+
+    #[test]
+    fn editing_one_match_leaves_every_other_line_alone() {
+        let contents = concat!(
+            "#synced from the laptop\n",
+            "global_vars:\n",
+            "    - name: who\n",
+            "      type: echo\n",
+            "      params: {echo: 'me'}\n",
+            "matches:\n",
+            "    # greetings\n",
+            "    - trigger: ':hi'\n",
+            "      replace: Hello {{who}}\n",
+            "\n",
+            "    - trigger: \":sig\"\n",
+            "      replace: |\n",
+            "        Regards\n",
+            "          indented\n",
+            "    - {trigger: \":x\", replace: \"x\"}  # flow style\n",
+        );
+        let file = MatchFile::parse(contents);
+        let mut matches = file.matches();
+        matches[0].replace = "Hi {{who}}".to_string();
+        let output = file.render(&matches).unwrap();
+        // Only the two lines of the edited entry are written anew
+        let changed: Vec<usize> = contents.lines().zip(output.lines()).enumerate().filter(|(_, (a, b))| a != b).map(|(n, _)| n).collect();
+        assert_eq!(contents.lines().count(), output.lines().count());
+        assert!(changed.iter().all(|n| (7..=8).contains(n)), "{}", output);
+        assert_eq!(MatchFile::parse(&output).matches(), matches);
+    }
+
+    #[test]
+    fn an_edited_header_is_written_fresh() {
+        let mut file = MatchFile::parse(&format!("#x\n{}", BODY));
+        assert_eq!(file.header, "x");
+        file.header = "first\n\nsecond".to_string();
+        let output = file.render(&file.matches()).unwrap();
+        assert_eq!(output, format!("# first\n#\n# second\n\n{}", BODY));
+        assert_eq!(MatchFile::parse(&output).header, file.header);
+    }
+
+    #[test]
+    fn an_unchanged_file_renders_as_read() {
+        let contents = format!("# header\n\nglobal_vars: []\n{}  # trailing comment\nother: {{key: 1}}\n", BODY);
+        let file = MatchFile::parse(&contents);
+        assert!(file.error().is_none());
+        assert_eq!(file.render(&file.matches()).unwrap(), contents);
+    }
+
+    #[test]
+    fn crlf_line_endings_are_kept() {
+        let contents = format!("# header\r\n\r\n{}", BODY.replace('\n', "\r\n"));
+        let file = MatchFile::parse(&contents);
+        let mut matches = file.matches();
+        assert_eq!(file.render(&matches).unwrap(), contents);
+        matches.push(Match { trigger: ":c".to_string(), replace: "C".to_string(), ..Default::default() });
+        let output = file.render(&matches).unwrap();
+        assert!(output.starts_with(&contents));
+        assert!(!output.replace("\r\n", "").contains('\n'), "{:?}", output);
+    }
+
+    #[test]
+    fn only_the_match_document_is_replaced() {
+        let contents = format!("name: first\n---\n{}--- # last\nkeep:   [as, is]\n", BODY);
+        let file = MatchFile::parse(&contents);
+        assert_eq!(file.document_count(), 3);
+        let mut matches = file.matches();
+        assert_eq!(matches.len(), 2);
+        matches.remove(1);
+        let output = file.render(&matches).unwrap();
+        assert_eq!(output, "name: first\n---\nmatches:\n  - trigger: \":a\"\n    replace: \"A\"\n--- # last\nkeep:   [as, is]\n");
+        assert_eq!(MatchFile::parse(&output).matches(), matches);
+    }
+
+    #[test]
+    fn parse_error_looks_at_every_document() {
+        assert_eq!(parse_error(&format!("# [not yaml\n{}", BODY)), None);
+        assert_eq!(parse_error("matches: []\n---\nkey: value\n"), None);
+        assert!(parse_error("matches: []\n---\nkey: [unclosed\n").is_some());
+        let file = MatchFile::parse("matches:\n  - trigger: \":a\n");
+        assert!(file.error().is_some());
+        assert!(file.clone().save(Path::new("/nonexistent/never-written.yml"), &[]).unwrap_err().contains("wasn't read in full"));
+    }
+}